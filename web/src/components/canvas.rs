@@ -69,6 +69,15 @@ where
     let display_size = use_state(|| (props.width, props.height));
     let size_listen_enent_state = use_state(|| EventListener::new(&window(), "resize", |_| ()));
 
+    // Sizes from new props are picked up too, e.g. when the pattern is resized
+    {
+        let display_size = display_size.clone();
+
+        use_effect_with((props.width, props.height), move |&(width, height)| {
+            display_size.set((width, height));
+        });
+    }
+
     {
         let node_ref = node_ref.clone();
         let display_size = display_size.clone();
@@ -1,64 +1,47 @@
-use implicit_clone::sync::{IArray, IMap};
+use implicit_clone::sync::IArray;
 use implicit_clone::ImplicitClone;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
 use std::fmt;
 
+pub mod cell_grid;
+pub mod chart;
+pub mod clipboard;
+pub mod color;
+pub mod document;
+pub mod fill;
 pub mod grid;
+pub mod image_import;
+pub mod line;
+pub mod overlay;
+pub mod palette;
+pub mod resize;
+pub mod selection;
+pub mod shape;
+pub mod symmetry;
+#[cfg(test)]
+pub(crate) mod test_fixtures;
+pub mod transform;
 
-#[non_exhaustive]
-#[derive(thiserror::Error, Debug)]
-pub enum RgbError {
-    #[error("invalid input")]
-    InvalidInput(String),
-    #[error("string to u32 conversion")]
-    Conversion(String),
-}
-
-#[derive(Clone, Copy, Debug, PartialEq)]
-pub struct Rgb {
-    pub r: u8,
-    pub g: u8,
-    pub b: u8,
-}
-
-impl Rgb {
-    pub fn as_hex(self) -> String {
-        format!("#{:02X}{:02X}{:02X}", self.r, self.g, self.b).to_lowercase()
-    }
-
-    pub fn from_hex(hex: String) -> Result<Rgb, RgbError> {
-        let hex = hex.trim_start_matches('#');
-
-        if hex.len() != 6 {
-            return Err(RgbError::InvalidInput(hex.to_string()));
-        }
-
-        let int_value =
-            u32::from_str_radix(hex, 16).map_err(|s| RgbError::Conversion(s.to_string()))?;
-
-        let r = ((int_value >> 16) & 0xFF) as u8;
-        let g = ((int_value >> 8) & 0xFF) as u8;
-        let b = (int_value & 0xFF) as u8;
-
-        Ok(Rgb { r, g, b })
-    }
-}
+pub use cell_grid::{CellGrid, CellThreads};
+pub use color::Rgb;
 
-impl fmt::Display for Rgb {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{} {} {}", self.r, self.g, self.b)
-    }
-}
+use palette::PaletteEntryId;
 
 #[derive(Clone, Copy, Debug, PartialEq, ImplicitClone)]
 pub struct Thread {
     pub color: Rgb,
     pub thread_type: ThreadType,
+    // The floss the colour came from, when it was picked from a palette
+    pub entry: Option<PaletteEntryId>,
 }
 
 impl Thread {
     pub fn new(color: Rgb, thread_type: ThreadType) -> Self {
-        Thread { color, thread_type }
+        Thread {
+            color,
+            thread_type,
+            entry: None,
+        }
     }
 }
 
@@ -69,7 +52,7 @@ impl fmt::Display for Thread {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, ImplicitClone, Deserialize, Serialize)]
 pub enum ThreadType {
     SlashBackwards,
     SlashForwards,
@@ -77,9 +60,77 @@ pub enum ThreadType {
     BorderRight,
     BorderBottom,
     BorderLeft,
+    // From a corner to the centre of the cell
+    QuarterTopLeft,
+    QuarterTopRight,
+    QuarterBottomRight,
+    QuarterBottomLeft,
+    // A quarter stitch from the named corner, plus the diagonal that doesn't touch that corner
+    ThreeQuarterTopLeft,
+    ThreeQuarterTopRight,
+    ThreeQuarterBottomRight,
+    ThreeQuarterBottomLeft,
 }
 
 impl ThreadType {
+    pub const ALL: [ThreadType; 14] = [
+        ThreadType::SlashBackwards,
+        ThreadType::SlashForwards,
+        ThreadType::BorderTop,
+        ThreadType::BorderRight,
+        ThreadType::BorderBottom,
+        ThreadType::BorderLeft,
+        ThreadType::QuarterTopLeft,
+        ThreadType::QuarterTopRight,
+        ThreadType::QuarterBottomRight,
+        ThreadType::QuarterBottomLeft,
+        ThreadType::ThreeQuarterTopLeft,
+        ThreadType::ThreeQuarterTopRight,
+        ThreadType::ThreeQuarterBottomRight,
+        ThreadType::ThreeQuarterBottomLeft,
+    ];
+    pub const COUNT: usize = ThreadType::ALL.len();
+
+    // Position of the thread type in `ThreadType::ALL`
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn quarter(corner: Corner) -> Self {
+        match corner {
+            Corner::TopLeft => ThreadType::QuarterTopLeft,
+            Corner::TopRight => ThreadType::QuarterTopRight,
+            Corner::BottomRight => ThreadType::QuarterBottomRight,
+            Corner::BottomLeft => ThreadType::QuarterBottomLeft,
+        }
+    }
+
+    pub fn three_quarter(corner: Corner) -> Self {
+        match corner {
+            Corner::TopLeft => ThreadType::ThreeQuarterTopLeft,
+            Corner::TopRight => ThreadType::ThreeQuarterTopRight,
+            Corner::BottomRight => ThreadType::ThreeQuarterBottomRight,
+            Corner::BottomLeft => ThreadType::ThreeQuarterBottomLeft,
+        }
+    }
+
+    // The corner a quarter or three-quarter stitch starts from
+    pub fn corner(self) -> Option<Corner> {
+        match self {
+            ThreadType::QuarterTopLeft | ThreadType::ThreeQuarterTopLeft => Some(Corner::TopLeft),
+            ThreadType::QuarterTopRight | ThreadType::ThreeQuarterTopRight => {
+                Some(Corner::TopRight)
+            }
+            ThreadType::QuarterBottomRight | ThreadType::ThreeQuarterBottomRight => {
+                Some(Corner::BottomRight)
+            }
+            ThreadType::QuarterBottomLeft | ThreadType::ThreeQuarterBottomLeft => {
+                Some(Corner::BottomLeft)
+            }
+            _ => None,
+        }
+    }
+
     pub fn as_str(&self) -> String {
         match &self {
             ThreadType::SlashBackwards => "SlashBackwards".to_string(),
@@ -88,6 +139,14 @@ impl ThreadType {
             ThreadType::BorderRight => "BorderRight".to_string(),
             ThreadType::BorderBottom => "BorderBottom".to_string(),
             ThreadType::BorderLeft => "BorderLeft".to_string(),
+            ThreadType::QuarterTopLeft => "QuarterTopLeft".to_string(),
+            ThreadType::QuarterTopRight => "QuarterTopRight".to_string(),
+            ThreadType::QuarterBottomRight => "QuarterBottomRight".to_string(),
+            ThreadType::QuarterBottomLeft => "QuarterBottomLeft".to_string(),
+            ThreadType::ThreeQuarterTopLeft => "ThreeQuarterTopLeft".to_string(),
+            ThreadType::ThreeQuarterTopRight => "ThreeQuarterTopRight".to_string(),
+            ThreadType::ThreeQuarterBottomRight => "ThreeQuarterBottomRight".to_string(),
+            ThreadType::ThreeQuarterBottomLeft => "ThreeQuarterBottomLeft".to_string(),
         }
     }
 }
@@ -99,18 +158,369 @@ impl fmt::Display for ThreadType {
 }
 
 // (column, row)
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, ImplicitClone)]
+#[derive(
+    Clone, Copy, Debug, Default, Eq, Hash, PartialEq, ImplicitClone, Deserialize, Serialize,
+)]
 pub struct GridSize(pub usize, pub usize);
 
+impl GridSize {
+    pub fn area(self) -> usize {
+        self.0 * self.1
+    }
+
+    pub fn contains(self, GridCell(col_index, row_index): GridCell) -> bool {
+        col_index < self.0 && row_index < self.1
+    }
+}
+
 // (column, row)
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, ImplicitClone)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, ImplicitClone, Deserialize, Serialize)]
 pub struct GridCell(pub usize, pub usize);
 
+// A point where grid lines cross, as (column line, row line). Cell `GridCell(c, r)` has its top
+// left corner at `GridIntersection(c, r)`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, ImplicitClone, Deserialize, Serialize)]
+pub struct GridIntersection(pub usize, pub usize);
+
+impl GridIntersection {
+    pub fn corner_of(GridCell(col, row): GridCell, corner: Corner) -> Self {
+        match corner {
+            Corner::TopLeft => GridIntersection(col, row),
+            Corner::TopRight => GridIntersection(col + 1, row),
+            Corner::BottomRight => GridIntersection(col + 1, row + 1),
+            Corner::BottomLeft => GridIntersection(col, row + 1),
+        }
+    }
+
+    /// The cells in `size` that have this intersection as one of their corners.
+    pub fn cells(self, size: GridSize) -> impl Iterator<Item = GridCell> {
+        let GridIntersection(col, row) = self;
+
+        [(0, 0), (1, 0), (0, 1), (1, 1)]
+            .into_iter()
+            .filter_map(move |(col_offset, row_offset)| {
+                let grid_cell =
+                    GridCell(col.checked_sub(col_offset)?, row.checked_sub(row_offset)?);
+
+                size.contains(grid_cell).then_some(grid_cell)
+            })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, ImplicitClone, Deserialize, Serialize)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomRight,
+    BottomLeft,
+}
+
+impl Corner {
+    pub fn clockwise(self) -> Self {
+        match self {
+            Corner::TopLeft => Corner::TopRight,
+            Corner::TopRight => Corner::BottomRight,
+            Corner::BottomRight => Corner::BottomLeft,
+            Corner::BottomLeft => Corner::TopLeft,
+        }
+    }
+
+    pub fn anticlockwise(self) -> Self {
+        match self {
+            Corner::TopLeft => Corner::BottomLeft,
+            Corner::TopRight => Corner::TopLeft,
+            Corner::BottomRight => Corner::TopRight,
+            Corner::BottomLeft => Corner::BottomRight,
+        }
+    }
+}
+
+/// A pointer position on the grid.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GridPoint {
+    pub grid_cell: GridCell,
+    // Position inside the cell, from (0.0, 0.0) at its top left to (1.0, 1.0) at its bottom right
+    pub offset: (f64, f64),
+}
+
+impl GridPoint {
+    /// The border of the cell closest to the pointer.
+    pub fn nearest_border(&self) -> ThreadType {
+        let (x, y) = self.offset;
+
+        [
+            (y, ThreadType::BorderTop),
+            (1.0 - x, ThreadType::BorderRight),
+            (1.0 - y, ThreadType::BorderBottom),
+            (x, ThreadType::BorderLeft),
+        ]
+        .into_iter()
+        .min_by(|(a, _), (b, _)| a.total_cmp(b))
+        .map(|(_, thread_type)| thread_type)
+        .unwrap_or(ThreadType::BorderTop)
+    }
+
+    /// The grid intersection closest to the pointer.
+    pub fn nearest_intersection(&self) -> GridIntersection {
+        let GridCell(col, row) = self.grid_cell;
+        let (x, y) = self.offset;
+
+        GridIntersection(col + x.round() as usize, row + y.round() as usize)
+    }
+
+    /// The quadrant of the cell the pointer is in.
+    pub fn corner(&self) -> Corner {
+        match (self.offset.0 < 0.5, self.offset.1 < 0.5) {
+            (true, true) => Corner::TopLeft,
+            (false, true) => Corner::TopRight,
+            (false, false) => Corner::BottomRight,
+            (true, false) => Corner::BottomLeft,
+        }
+    }
+}
+
+/// What the brush stitches into each cell it passes over.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ImplicitClone, Deserialize, Serialize)]
+pub enum StitchType {
+    #[default]
+    FullCross,
+    // A single thread, e.g. a half stitch or one border
+    Single(ThreadType),
+    // Backstitch along whichever border of the cell the pointer is closest to
+    NearestBorder,
+    // A quarter or three-quarter stitch from the corner of the quadrant under the pointer
+    Quarter,
+    ThreeQuarter,
+}
+
+impl StitchType {
+    pub const ALL: [StitchType; 10] = [
+        StitchType::FullCross,
+        StitchType::Single(ThreadType::SlashForwards),
+        StitchType::Single(ThreadType::SlashBackwards),
+        StitchType::Single(ThreadType::BorderTop),
+        StitchType::Single(ThreadType::BorderRight),
+        StitchType::Single(ThreadType::BorderBottom),
+        StitchType::Single(ThreadType::BorderLeft),
+        StitchType::NearestBorder,
+        StitchType::Quarter,
+        StitchType::ThreeQuarter,
+    ];
+
+    pub fn as_str(&self) -> String {
+        match &self {
+            StitchType::FullCross => "Full cross".to_string(),
+            StitchType::Single(ThreadType::SlashForwards) => "Half stitch /".to_string(),
+            StitchType::Single(ThreadType::SlashBackwards) => "Half stitch \\".to_string(),
+            StitchType::Single(ThreadType::BorderTop) => "Top border".to_string(),
+            StitchType::Single(ThreadType::BorderRight) => "Right border".to_string(),
+            StitchType::Single(ThreadType::BorderBottom) => "Bottom border".to_string(),
+            StitchType::Single(ThreadType::BorderLeft) => "Left border".to_string(),
+            StitchType::Single(thread_type) => thread_type.as_str(),
+            StitchType::NearestBorder => "Backstitch nearest edge".to_string(),
+            StitchType::Quarter => "Quarter stitch".to_string(),
+            StitchType::ThreeQuarter => "Three-quarter stitch".to_string(),
+        }
+    }
+
+    /// The threads stitched into the cell under `point`.
+    pub fn threads(self, color: Rgb, point: GridPoint) -> CellThreads {
+        match self {
+            StitchType::FullCross => CellThreads::full_cross(color),
+            StitchType::Single(thread_type) => CellThreads::single(thread_type, color),
+            StitchType::NearestBorder => CellThreads::single(point.nearest_border(), color),
+            StitchType::Quarter => CellThreads::single(ThreadType::quarter(point.corner()), color),
+            StitchType::ThreeQuarter => {
+                CellThreads::single(ThreadType::three_quarter(point.corner()), color)
+            }
+        }
+    }
+}
+
+impl fmt::Display for StitchType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", &self.as_str())
+    }
+}
+
 #[derive(PartialEq)]
 pub struct CellClick {
     pub thread_cell: (GridCell, IArray<Thread>),
     pub is_shift_active: bool,
 }
 
-pub type GridType = IMap<GridCell, IArray<Thread>>;
-pub type RawGridType = HashMap<GridCell, IArray<Thread>>;
+pub type GridType = CellGrid;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_fixtures::RED;
+
+    fn point(col: usize, row: usize, offset: (f64, f64)) -> GridPoint {
+        GridPoint {
+            grid_cell: GridCell(col, row),
+            offset,
+        }
+    }
+
+    #[test]
+    fn nearest_border_on_the_cell_edges() {
+        assert_eq!(
+            point(2, 2, (0.5, 0.0)).nearest_border(),
+            ThreadType::BorderTop
+        );
+        assert_eq!(
+            point(2, 2, (0.99, 0.5)).nearest_border(),
+            ThreadType::BorderRight
+        );
+        assert_eq!(
+            point(2, 2, (0.5, 0.99)).nearest_border(),
+            ThreadType::BorderBottom
+        );
+        assert_eq!(
+            point(2, 2, (0.0, 0.5)).nearest_border(),
+            ThreadType::BorderLeft
+        );
+        assert_eq!(
+            point(2, 2, (0.3, 0.2)).nearest_border(),
+            ThreadType::BorderTop
+        );
+    }
+
+    #[test]
+    fn nearest_border_ties_go_clockwise_from_the_top() {
+        // Exact corners and the centre are as close to two or four borders
+        assert_eq!(
+            point(2, 2, (0.0, 0.0)).nearest_border(),
+            ThreadType::BorderTop
+        );
+        assert_eq!(
+            point(2, 2, (1.0, 1.0)).nearest_border(),
+            ThreadType::BorderRight
+        );
+        assert_eq!(
+            point(2, 2, (0.0, 1.0)).nearest_border(),
+            ThreadType::BorderBottom
+        );
+        assert_eq!(
+            point(2, 2, (0.5, 0.5)).nearest_border(),
+            ThreadType::BorderTop
+        );
+    }
+
+    #[test]
+    fn nearest_intersection_rounds_to_the_closest_corner() {
+        assert_eq!(
+            point(2, 3, (0.0, 0.0)).nearest_intersection(),
+            GridIntersection(2, 3)
+        );
+        assert_eq!(
+            point(2, 3, (0.49, 0.51)).nearest_intersection(),
+            GridIntersection(2, 4)
+        );
+        // Halfway rounds up
+        assert_eq!(
+            point(2, 3, (0.5, 0.5)).nearest_intersection(),
+            GridIntersection(3, 4)
+        );
+        assert_eq!(
+            point(0, 0, (0.1, 0.1)).nearest_intersection(),
+            GridIntersection(0, 0)
+        );
+    }
+
+    #[test]
+    fn the_outer_edge_of_the_grid_has_intersections_but_no_cells() {
+        let size = GridSize(10, 8);
+        let last = point(9, 7, (0.9, 0.95));
+        let intersection = last.nearest_intersection();
+
+        assert_eq!(intersection, GridIntersection(10, 8));
+        assert_eq!(last.nearest_border(), ThreadType::BorderBottom);
+        assert_eq!(
+            intersection.cells(size).collect::<Vec<_>>(),
+            vec![GridCell(9, 7)]
+        );
+        assert_eq!(
+            GridIntersection(0, 4).cells(size).collect::<Vec<_>>(),
+            vec![GridCell(0, 4), GridCell(0, 3)]
+        );
+    }
+
+    #[test]
+    fn corner_splits_the_cell_at_its_middle() {
+        assert_eq!(point(0, 0, (0.0, 0.0)).corner(), Corner::TopLeft);
+        assert_eq!(point(0, 0, (0.49, 0.49)).corner(), Corner::TopLeft);
+        // The middle lines belong to the right and bottom halves
+        assert_eq!(point(0, 0, (0.5, 0.0)).corner(), Corner::TopRight);
+        assert_eq!(point(0, 0, (0.5, 0.5)).corner(), Corner::BottomRight);
+        assert_eq!(point(0, 0, (0.0, 0.5)).corner(), Corner::BottomLeft);
+        assert_eq!(point(0, 0, (0.99, 0.99)).corner(), Corner::BottomRight);
+    }
+
+    fn thread_types(threads: CellThreads) -> Vec<ThreadType> {
+        threads.iter().map(|thread| thread.thread_type).collect()
+    }
+
+    #[test]
+    fn stitch_types_fill_their_thread_slots() {
+        let color = RED;
+        let top_left = point(1, 1, (0.2, 0.1));
+
+        assert_eq!(
+            thread_types(StitchType::FullCross.threads(color, top_left)),
+            vec![ThreadType::SlashBackwards, ThreadType::SlashForwards]
+        );
+        assert_eq!(
+            thread_types(StitchType::Single(ThreadType::BorderLeft).threads(color, top_left)),
+            vec![ThreadType::BorderLeft]
+        );
+        assert_eq!(
+            thread_types(StitchType::NearestBorder.threads(color, top_left)),
+            vec![ThreadType::BorderTop]
+        );
+        assert!(StitchType::FullCross
+            .threads(color, top_left)
+            .iter()
+            .all(|thread| thread.color == color && thread.entry.is_none()));
+    }
+
+    #[test]
+    fn quarter_stitches_start_from_the_quadrant_under_the_pointer() {
+        let color = RED;
+
+        for (offset, quarter, three_quarter) in [
+            (
+                (0.2, 0.2),
+                ThreadType::QuarterTopLeft,
+                ThreadType::ThreeQuarterTopLeft,
+            ),
+            (
+                (0.8, 0.2),
+                ThreadType::QuarterTopRight,
+                ThreadType::ThreeQuarterTopRight,
+            ),
+            (
+                (0.8, 0.8),
+                ThreadType::QuarterBottomRight,
+                ThreadType::ThreeQuarterBottomRight,
+            ),
+            (
+                (0.2, 0.8),
+                ThreadType::QuarterBottomLeft,
+                ThreadType::ThreeQuarterBottomLeft,
+            ),
+        ] {
+            let point = point(0, 0, offset);
+
+            assert_eq!(
+                thread_types(StitchType::Quarter.threads(color, point)),
+                vec![quarter]
+            );
+            assert_eq!(
+                thread_types(StitchType::ThreeQuarter.threads(color, point)),
+                vec![three_quarter]
+            );
+        }
+    }
+}
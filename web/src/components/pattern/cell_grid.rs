@@ -0,0 +1,328 @@
+use std::sync::Arc;
+
+use implicit_clone::ImplicitClone;
+
+use super::palette::PaletteEntryId;
+use super::{GridCell, GridSize, Rgb, Thread, ThreadType};
+
+// Cells are stored row-major and split into fixed-size chunks so that cloning a grid and then
+// editing it only copies the chunks that were touched.
+const CHUNK_LEN: usize = 256;
+
+/// The threads stitched into a single cell, with one slot per `ThreadType`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, ImplicitClone)]
+pub struct CellThreads([Option<(Rgb, Option<PaletteEntryId>)>; ThreadType::COUNT]);
+
+impl CellThreads {
+    pub fn full_cross(color: Rgb) -> Self {
+        let mut threads = CellThreads::default();
+        threads.set(ThreadType::SlashForwards, color);
+        threads.set(ThreadType::SlashBackwards, color);
+        threads
+    }
+
+    pub fn single(thread_type: ThreadType, color: Rgb) -> Self {
+        let mut threads = CellThreads::default();
+        threads.set(thread_type, color);
+        threads
+    }
+
+    pub fn get(&self, thread_type: ThreadType) -> Option<Rgb> {
+        self.0[thread_type.index()].map(|(color, _)| color)
+    }
+
+    /// Sets a raw colour, dropping any palette entry the slot referenced.
+    pub fn set(&mut self, thread_type: ThreadType, color: Rgb) {
+        self.0[thread_type.index()] = Some((color, None));
+    }
+
+    pub fn set_thread(&mut self, thread: Thread) {
+        self.0[thread.thread_type.index()] = Some((thread.color, thread.entry));
+    }
+
+    pub fn remove(&mut self, thread_type: ThreadType) {
+        self.0[thread_type.index()] = None;
+    }
+
+    /// A copy with the given thread types taken out.
+    pub fn without(mut self, thread_types: &[ThreadType]) -> Self {
+        for &thread_type in thread_types {
+            self.remove(thread_type);
+        }
+        self
+    }
+
+    /// A copy with the threads of `other` stitched over it.
+    pub fn merged(mut self, other: CellThreads) -> Self {
+        for thread in other.iter() {
+            self.set_thread(thread);
+        }
+        self
+    }
+
+    /// A copy with every thread referring to `entry`, taking its colour when there is one.
+    pub fn with_entry(mut self, entry: Option<PaletteEntryId>) -> Self {
+        for slot in self.0.iter_mut().flatten() {
+            *slot = (entry.map_or(slot.0, PaletteEntryId::color), entry);
+        }
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(Option::is_none)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Thread> + '_ {
+        ThreadType::ALL.iter().filter_map(|&thread_type| {
+            self.0[thread_type.index()].map(|(color, entry)| Thread {
+                color,
+                thread_type,
+                entry,
+            })
+        })
+    }
+}
+
+impl FromIterator<Thread> for CellThreads {
+    fn from_iter<I: IntoIterator<Item = Thread>>(iter: I) -> Self {
+        let mut threads = CellThreads::default();
+        for thread in iter {
+            threads.set_thread(thread);
+        }
+        threads
+    }
+}
+
+/// A fixed-size pattern grid.
+///
+/// Cloning is cheap: clones share their storage until one of them is edited, at which point
+/// only the edited chunk is copied.
+#[derive(Clone, Debug, Default, ImplicitClone)]
+pub struct CellGrid {
+    size: GridSize,
+    chunks: Arc<Vec<Arc<Vec<CellThreads>>>>,
+}
+
+impl CellGrid {
+    pub fn new(size: GridSize) -> Self {
+        CellGrid::filled(size, CellThreads::default())
+    }
+
+    pub fn filled(size: GridSize, threads: CellThreads) -> Self {
+        let cell_count = size.area();
+        let full_chunk = Arc::new(vec![threads; CHUNK_LEN]);
+        let chunks = (0..cell_count)
+            .step_by(CHUNK_LEN)
+            .map(|start| {
+                let len = CHUNK_LEN.min(cell_count - start);
+                if len == CHUNK_LEN {
+                    full_chunk.clone()
+                } else {
+                    Arc::new(vec![threads; len])
+                }
+            })
+            .collect();
+
+        CellGrid {
+            size,
+            chunks: Arc::new(chunks),
+        }
+    }
+
+    pub fn size(&self) -> GridSize {
+        self.size
+    }
+
+    fn index(&self, cell: GridCell) -> Option<usize> {
+        if self.size.contains(cell) {
+            Some(cell.1 * self.size.0 + cell.0)
+        } else {
+            None
+        }
+    }
+
+    pub fn get(&self, cell: GridCell) -> Option<CellThreads> {
+        self.index(cell)
+            .map(|index| self.chunks[index / CHUNK_LEN][index % CHUNK_LEN])
+    }
+
+    /// Returns `true` if the cell is inside the grid and its threads changed.
+    pub fn set(&mut self, cell: GridCell, threads: CellThreads) -> bool {
+        if let Some(index) = self.index(cell) {
+            let (chunk_index, offset) = (index / CHUNK_LEN, index % CHUNK_LEN);
+
+            if self.chunks[chunk_index][offset] != threads {
+                let chunks = Arc::make_mut(&mut self.chunks);
+                Arc::make_mut(&mut chunks[chunk_index])[offset] = threads;
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Every cell in row-major order, including empty ones.
+    pub fn iter(&self) -> impl Iterator<Item = (GridCell, CellThreads)> + '_ {
+        let cols = self.size.0;
+
+        self.chunks
+            .iter()
+            .flat_map(|chunk| chunk.iter().copied())
+            .enumerate()
+            .map(move |(index, threads)| (GridCell(index % cols, index / cols), threads))
+    }
+
+    /// Cells whose threads differ between the two grids. Chunks still shared between them are
+    /// skipped without being compared.
+    pub fn diff(&self, other: &CellGrid) -> Vec<GridCell> {
+        if self.size != other.size {
+            return self.iter().map(|(cell, _)| cell).collect();
+        }
+
+        let cols = self.size.0;
+        let mut cells = vec![];
+
+        for (chunk_index, (chunk, other_chunk)) in
+            self.chunks.iter().zip(other.chunks.iter()).enumerate()
+        {
+            if Arc::ptr_eq(chunk, other_chunk) {
+                continue;
+            }
+
+            for (offset, (threads, other_threads)) in
+                chunk.iter().zip(other_chunk.iter()).enumerate()
+            {
+                if threads != other_threads {
+                    let index = chunk_index * CHUNK_LEN + offset;
+                    cells.push(GridCell(index % cols, index / cols));
+                }
+            }
+        }
+
+        cells
+    }
+}
+
+impl PartialEq for CellGrid {
+    fn eq(&self, other: &Self) -> bool {
+        self.size == other.size
+            && (Arc::ptr_eq(&self.chunks, &other.chunks)
+                || self
+                    .chunks
+                    .iter()
+                    .zip(other.chunks.iter())
+                    .all(|(a, b)| Arc::ptr_eq(a, b) || a == b))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::pattern::test_fixtures::{grid_with, stitched_cells, BLUE, RED};
+
+    // 20 columns put the chunk boundary between GridCell(15, 12) and GridCell(16, 12)
+    const SIZE: GridSize = GridSize(20, 20);
+
+    #[test]
+    fn cells_either_side_of_a_chunk_boundary_stay_apart() {
+        let mut grid = CellGrid::new(SIZE);
+
+        assert!(grid.set(GridCell(15, 12), CellThreads::full_cross(RED)));
+        assert!(grid.set(GridCell(16, 12), CellThreads::full_cross(BLUE)));
+
+        assert_eq!(grid.chunks[0][255], CellThreads::full_cross(RED));
+        assert_eq!(grid.chunks[1][0], CellThreads::full_cross(BLUE));
+        assert_eq!(
+            grid.get(GridCell(15, 12)),
+            Some(CellThreads::full_cross(RED))
+        );
+        assert_eq!(
+            grid.get(GridCell(16, 12)),
+            Some(CellThreads::full_cross(BLUE))
+        );
+        assert_eq!(
+            stitched_cells(&grid),
+            vec![GridCell(15, 12), GridCell(16, 12)]
+        );
+    }
+
+    #[test]
+    fn cells_outside_the_grid_are_ignored() {
+        let mut grid = CellGrid::new(SIZE);
+
+        assert!(!grid.set(GridCell(20, 0), CellThreads::full_cross(RED)));
+        assert_eq!(grid.get(GridCell(20, 0)), None);
+        assert_eq!(grid.get(GridCell(0, 20)), None);
+        assert_eq!(grid, CellGrid::new(SIZE));
+    }
+
+    #[test]
+    fn clones_copy_only_the_chunk_that_is_edited() {
+        let grid = CellGrid::new(SIZE);
+        let mut edited = grid.clone();
+
+        assert!(Arc::ptr_eq(&grid.chunks, &edited.chunks));
+
+        edited.set(GridCell(0, 0), CellThreads::full_cross(RED));
+
+        assert_eq!(grid.get(GridCell(0, 0)), Some(CellThreads::default()));
+        assert!(!Arc::ptr_eq(&grid.chunks[0], &edited.chunks[0]));
+        assert!(Arc::ptr_eq(&grid.chunks[1], &edited.chunks[1]));
+    }
+
+    #[test]
+    fn setting_the_same_threads_changes_nothing() {
+        let grid = grid_with(SIZE, &[(GridCell(3, 3), CellThreads::full_cross(RED))]);
+        let mut edited = grid.clone();
+
+        assert!(!edited.set(GridCell(3, 3), CellThreads::full_cross(RED)));
+        assert!(Arc::ptr_eq(&grid.chunks, &edited.chunks));
+    }
+
+    #[test]
+    fn diff_finds_the_changed_cells() {
+        let grid = CellGrid::new(SIZE);
+        let mut edited = grid.clone();
+
+        assert!(edited.diff(&grid).is_empty());
+
+        edited.set(GridCell(16, 12), CellThreads::full_cross(RED));
+        edited.set(GridCell(19, 19), CellThreads::full_cross(BLUE));
+
+        assert_eq!(edited.diff(&grid), vec![GridCell(16, 12), GridCell(19, 19)]);
+        assert_ne!(edited, grid);
+    }
+
+    #[test]
+    fn grids_built_separately_compare_by_content() {
+        let cells = [(GridCell(5, 15), CellThreads::full_cross(RED))];
+        let grid = grid_with(SIZE, &cells);
+        let other = grid_with(SIZE, &cells);
+
+        assert!(!Arc::ptr_eq(&grid.chunks[1], &other.chunks[1]));
+        assert!(grid.diff(&other).is_empty());
+        assert_eq!(grid, other);
+    }
+
+    #[test]
+    fn diff_with_a_different_size_covers_every_cell() {
+        let grid = CellGrid::new(GridSize(3, 2));
+        let smaller = CellGrid::new(GridSize(2, 2));
+
+        assert_eq!(grid.diff(&smaller).len(), 6);
+        assert_ne!(grid, smaller);
+    }
+
+    #[test]
+    fn filled_grids_end_with_a_partial_chunk() {
+        let threads = CellThreads::full_cross(RED);
+        let grid = CellGrid::filled(GridSize(17, 17), threads);
+        let chunk_lens: Vec<usize> = grid.chunks.iter().map(|chunk| chunk.len()).collect();
+
+        assert_eq!(chunk_lens, vec![256, 33]);
+        assert_eq!(grid.iter().count(), 289);
+        assert!(grid.iter().all(|(_, cell_threads)| cell_threads == threads));
+        assert_eq!(grid.iter().last(), Some((GridCell(16, 16), threads)));
+        assert_eq!(grid.get(GridCell(16, 16)), Some(threads));
+    }
+}
@@ -0,0 +1,1052 @@
+use std::fmt::Write;
+use std::ops::Range;
+
+use indexmap::IndexMap;
+
+use super::overlay::{MarkerKind, MarkerPosition, Overlay};
+use super::palette::PaletteEntryId;
+use super::{
+    CellThreads, Corner, GridCell, GridIntersection, GridSize, GridType, Rgb, Thread, ThreadType,
+};
+
+// Lengths are in millimetres, so that pages print at a known size
+const PAGE_MARGIN: f64 = 10.0;
+const HEADER_HEIGHT: f64 = 8.0;
+// Space between the header or the page's left edge and the grid, for the row and column numbers
+const NUMBERING_WIDTH: f64 = 8.0;
+const HEADER_FONT_SIZE: f64 = 4.0;
+const NUMBER_FONT_SIZE: f64 = 2.5;
+const MAJOR_LINE_INTERVAL: usize = 10;
+const MAJOR_LINE_WIDTH: f64 = 0.35;
+const MINOR_LINE_WIDTH: f64 = 0.1;
+const LINE_COLOR: &str = "#000000";
+const MINOR_LINE_COLOR: &str = "#808080";
+const LEGEND_WIDTH: f64 = 170.0;
+const LEGEND_ROW_HEIGHT: f64 = 7.0;
+const LEGEND_ROWS_PER_PAGE: usize = 32;
+const LEGEND_SAMPLE_SIZE: f64 = 5.0;
+
+// Shapes first as they're the easiest to tell apart, then letters and digits that can't be
+// mistaken for one another
+const SYMBOLS: &[&str] = &[
+    "●", "■", "▲", "◆", "★", "♥", "✚", "○", "□", "△", "◇", "☆", "♣", "♠", "×", "▼", "A", "B", "C",
+    "D", "E", "F", "G", "H", "J", "K", "L", "M", "N", "P", "R", "S", "T", "U", "V", "W", "X", "Y",
+    "Z", "2", "3", "4", "5", "6", "7", "8", "9", "#", "%", "&", "@", "+", "=", "?", "$",
+];
+
+#[non_exhaustive]
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum ChartError {
+    #[error("the pattern has no cells to chart")]
+    EmptyPattern,
+    #[error("a page has to fit at least one cell")]
+    EmptyPage,
+    #[error("the cell size has to be positive")]
+    InvalidCellSize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChartOptions {
+    // Width and height of a cell in millimetres
+    pub cell_size: f64,
+    // How many columns and rows fit on a page
+    pub page_size: GridSize,
+}
+
+impl Default for ChartOptions {
+    // Fits on A4 and US letter paper
+    fn default() -> Self {
+        ChartOptions {
+            cell_size: 3.0,
+            page_size: GridSize(50, 70),
+        }
+    }
+}
+
+/// A thread as a stitcher buys it: the floss when it came from a palette, otherwise its colour.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ChartThread {
+    pub color: Rgb,
+    pub entry: Option<PaletteEntryId>,
+}
+
+impl ChartThread {
+    pub fn label(self) -> String {
+        match self.entry {
+            Some(entry) => entry.to_string(),
+            None => self.color.as_hex(),
+        }
+    }
+}
+
+impl From<Thread> for ChartThread {
+    fn from(Thread { color, entry, .. }: Thread) -> Self {
+        ChartThread { color, entry }
+    }
+}
+
+/// How a thread is shown on the chart.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum LegendSample {
+    Symbol(String),
+    // Backstitch and borders, drawn in the thread's colour
+    Line,
+    Marker(MarkerKind),
+}
+
+impl LegendSample {
+    pub fn as_str(&self) -> String {
+        match self {
+            LegendSample::Symbol(_) => "Cross stitch".to_string(),
+            LegendSample::Line => "Backstitch".to_string(),
+            LegendSample::Marker(kind) => kind.as_str(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LegendEntry {
+    pub sample: LegendSample,
+    pub thread: ChartThread,
+    // Full crosses count once, as do every half, quarter and three-quarter stitch, backstitch
+    // between two holes, knot and bead
+    pub count: usize,
+}
+
+// How a thread shows up in its cell
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Mark {
+    FullCross,
+    // A half, quarter or three-quarter stitch, shown with a smaller symbol in one quadrant
+    Partial(ThreadType),
+    Border(ThreadType),
+}
+
+/// Works out a symbol for each thread colour, the most used getting the clearest ones, and
+/// counts the stitches of each thread.
+pub fn legend(grid: &GridType, overlay: &Overlay) -> Vec<LegendEntry> {
+    let mut stitches: IndexMap<ChartThread, usize> = IndexMap::new();
+    let mut lines: IndexMap<ChartThread, usize> = IndexMap::new();
+    let mut markers: IndexMap<(MarkerKind, ChartThread), usize> = IndexMap::new();
+    // Backstitch and markers only have a colour, so they take the floss of a stitch that matches
+    let mut entries: IndexMap<Rgb, PaletteEntryId> = IndexMap::new();
+
+    for (_, threads) in grid.iter() {
+        for (mark, thread) in cell_marks(&threads) {
+            let counts = match mark {
+                Mark::Border(_) => &mut lines,
+                _ => &mut stitches,
+            };
+
+            *counts.entry(thread).or_default() += 1;
+            if let Some(entry) = thread.entry {
+                entries.entry(thread.color).or_insert(entry);
+            }
+        }
+    }
+
+    let overlay_thread = |color: Rgb| ChartThread {
+        color,
+        entry: entries.get(&color).copied(),
+    };
+
+    for backstitch in overlay.backstitches() {
+        // A single point is a stitch too
+        let count = backstitch.segments().count().max(1);
+
+        *lines.entry(overlay_thread(backstitch.color)).or_default() += count;
+    }
+    for (_, marker) in overlay.markers() {
+        *markers
+            .entry((marker.kind, overlay_thread(marker.color)))
+            .or_default() += 1;
+    }
+
+    let stitches = by_count(stitches)
+        .into_iter()
+        .enumerate()
+        .map(|(index, (thread, count))| LegendEntry {
+            sample: LegendSample::Symbol(symbol(index)),
+            thread,
+            count,
+        });
+    let lines = by_count(lines)
+        .into_iter()
+        .map(|(thread, count)| LegendEntry {
+            sample: LegendSample::Line,
+            thread,
+            count,
+        });
+    let markers = by_count(markers)
+        .into_iter()
+        .map(|((kind, thread), count)| LegendEntry {
+            sample: LegendSample::Marker(kind),
+            thread,
+            count,
+        });
+
+    stitches.chain(lines).chain(markers).collect()
+}
+
+/// Draws a printable black and white chart as SVG pages, the pattern split across as many
+/// pages as it needs followed by the legend.
+pub fn symbol_chart(
+    grid: &GridType,
+    overlay: &Overlay,
+    title: &str,
+    options: ChartOptions,
+) -> Result<Vec<String>, ChartError> {
+    let ChartOptions {
+        cell_size,
+        page_size,
+    } = options;
+
+    if grid.size().area() == 0 {
+        return Err(ChartError::EmptyPattern);
+    }
+    if page_size.area() == 0 {
+        return Err(ChartError::EmptyPage);
+    }
+    if !cell_size.is_finite() || cell_size <= 0.0 {
+        return Err(ChartError::InvalidCellSize);
+    }
+
+    let legend = legend(grid, overlay);
+    let symbols: IndexMap<ChartThread, &str> = legend
+        .iter()
+        .filter_map(|entry| match &entry.sample {
+            LegendSample::Symbol(symbol) => Some((entry.thread, symbol.as_str())),
+            _ => None,
+        })
+        .collect();
+    let pages = page_ranges(grid.size(), page_size);
+    let legend_pages: Vec<&[LegendEntry]> = if legend.is_empty() {
+        vec![&[]]
+    } else {
+        legend.chunks(LEGEND_ROWS_PER_PAGE).collect()
+    };
+    let page_count = pages.len() + legend_pages.len();
+    let title = chart_title(title);
+
+    let chart_pages = pages.into_iter().enumerate().map(|(index, (cols, rows))| {
+        let header = format!(
+            "{} · Page {} of {} · Columns {}–{} · Rows {}–{}",
+            title,
+            index + 1,
+            page_count,
+            cols.start + 1,
+            cols.end,
+            rows.start + 1,
+            rows.end
+        );
+
+        chart_page(grid, overlay, &symbols, (cols, rows), cell_size, &header)
+    });
+    let first_legend_page = page_count - legend_pages.len();
+    let legend_pages = legend_pages
+        .into_iter()
+        .enumerate()
+        .map(|(index, entries)| {
+            let header = format!(
+                "{} · Key · Page {} of {}",
+                title,
+                first_legend_page + index + 1,
+                page_count
+            );
+
+            legend_page(entries, &header)
+        });
+
+    Ok(chart_pages.chain(legend_pages).collect())
+}
+
+/// Puts the pages of a chart into a single HTML file that prints each of them on a page of its
+/// own, so the whole chart is saved as one download.
+pub fn chart_document(pages: &[String], title: &str) -> String {
+    let mut html = format!(
+        r#"<!DOCTYPE html><html><head><meta charset="utf-8"><title>{}</title><style>@page {{ margin: 0; }} body {{ margin: 0; }} svg {{ display: block; break-after: page; }}</style></head><body>"#,
+        escape(chart_title(title))
+    );
+
+    for page in pages {
+        html.push_str(page);
+    }
+
+    html.push_str("</body></html>");
+    html
+}
+
+fn chart_title(title: &str) -> &str {
+    match title.trim() {
+        "" => "Pattern",
+        title => title,
+    }
+}
+
+fn by_count<K>(counts: IndexMap<K, usize>) -> Vec<(K, usize)> {
+    let mut counts: Vec<_> = counts.into_iter().collect();
+
+    // Stable, so threads used as often keep the order they were found in
+    counts.sort_by(|(_, a), (_, b)| b.cmp(a));
+    counts
+}
+
+fn symbol(index: usize) -> String {
+    let symbol = SYMBOLS[index % SYMBOLS.len()];
+
+    // Numbered once every symbol is taken
+    match index / SYMBOLS.len() {
+        0 => symbol.to_string(),
+        round => format!("{}{}", symbol, round + 1),
+    }
+}
+
+// Pages go across, then down, the way the pattern is read
+fn page_ranges(
+    GridSize(cols, rows): GridSize,
+    GridSize(page_cols, page_rows): GridSize,
+) -> Vec<(Range<usize>, Range<usize>)> {
+    (0..rows)
+        .step_by(page_rows)
+        .flat_map(|row| {
+            (0..cols).step_by(page_cols).map(move |col| {
+                (
+                    col..(col + page_cols).min(cols),
+                    row..(row + page_rows).min(rows),
+                )
+            })
+        })
+        .collect()
+}
+
+fn cell_marks(threads: &CellThreads) -> Vec<(Mark, ChartThread)> {
+    let find = |thread_type: ThreadType| {
+        threads
+            .iter()
+            .find(|thread| thread.thread_type == thread_type)
+            .map(ChartThread::from)
+    };
+    let full_cross = find(ThreadType::SlashForwards)
+        .filter(|&forwards| Some(forwards) == find(ThreadType::SlashBackwards));
+    let mut marks: Vec<(Mark, ChartThread)> = full_cross
+        .map(|thread| (Mark::FullCross, thread))
+        .into_iter()
+        .collect();
+
+    for thread in threads.iter() {
+        let mark = match thread.thread_type {
+            ThreadType::SlashForwards | ThreadType::SlashBackwards if full_cross.is_some() => {
+                continue
+            }
+            thread_type @ (ThreadType::BorderTop
+            | ThreadType::BorderRight
+            | ThreadType::BorderBottom
+            | ThreadType::BorderLeft) => Mark::Border(thread_type),
+            thread_type => Mark::Partial(thread_type),
+        };
+
+        marks.push((mark, ChartThread::from(thread)));
+    }
+
+    marks
+}
+
+// Rounded so the files don't fill up with floating point noise
+fn num(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn svg_start(svg: &mut String, (width, height): (f64, f64)) {
+    let _ = write!(
+        svg,
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{w}mm" height="{h}mm" viewBox="0 0 {w} {h}" font-family="sans-serif"><rect width="{w}" height="{h}" fill="#ffffff"/>"##,
+        w = num(width),
+        h = num(height),
+    );
+}
+
+fn text(svg: &mut String, content: &str, (x, y): (f64, f64), font_size: f64, anchor: &str) {
+    let _ = write!(
+        svg,
+        r#"<text x="{}" y="{}" font-size="{}" text-anchor="{}" dominant-baseline="central">{}</text>"#,
+        num(x),
+        num(y),
+        num(font_size),
+        anchor,
+        escape(content),
+    );
+}
+
+fn line(svg: &mut String, (x1, y1): (f64, f64), (x2, y2): (f64, f64), color: &str, width: f64) {
+    let _ = write!(
+        svg,
+        r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="{}" stroke-linecap="round"/>"#,
+        num(x1),
+        num(y1),
+        num(x2),
+        num(y2),
+        color,
+        num(width),
+    );
+}
+
+fn marker(svg: &mut String, kind: MarkerKind, color: Rgb, (x, y): (f64, f64), cell_size: f64) {
+    let _ = match kind {
+        MarkerKind::FrenchKnot => write!(
+            svg,
+            r#"<circle cx="{}" cy="{}" r="{}" fill="{}"/>"#,
+            num(x),
+            num(y),
+            num(cell_size * 0.2),
+            color.as_hex(),
+        ),
+        // A ring, like in the editor
+        MarkerKind::Bead => write!(
+            svg,
+            r##"<circle cx="{}" cy="{}" r="{}" fill="#ffffff" stroke="{}" stroke-width="{}"/>"##,
+            num(x),
+            num(y),
+            num(cell_size * 0.25),
+            color.as_hex(),
+            num(cell_size * 0.1),
+        ),
+    };
+}
+
+// Position of a corner in a cell, from (0, 0) at the top left to (1, 1) at the bottom right
+fn corner_offset(corner: Corner) -> (f64, f64) {
+    match corner {
+        Corner::TopLeft => (0.0, 0.0),
+        Corner::TopRight => (1.0, 0.0),
+        Corner::BottomRight => (1.0, 1.0),
+        Corner::BottomLeft => (0.0, 1.0),
+    }
+}
+
+fn draw_mark(
+    svg: &mut String,
+    (mark, thread): (Mark, ChartThread),
+    symbol: &str,
+    GridCell(col, row): GridCell,
+    cell_size: f64,
+) {
+    let at = |(x, y): (f64, f64)| ((col as f64 + x) * cell_size, (row as f64 + y) * cell_size);
+    let centre = (0.5, 0.5);
+
+    match mark {
+        Mark::FullCross => text(svg, symbol, at(centre), cell_size * 0.75, "middle"),
+        Mark::Partial(thread_type) => {
+            // The thread is drawn faintly under the symbol, so a half stitch can be told from a
+            // quarter
+            let (quadrant, threads) = match thread_type {
+                ThreadType::SlashForwards => (
+                    Corner::TopLeft,
+                    vec![(
+                        corner_offset(Corner::BottomLeft),
+                        corner_offset(Corner::TopRight),
+                    )],
+                ),
+                ThreadType::SlashBackwards => (
+                    Corner::TopRight,
+                    vec![(
+                        corner_offset(Corner::TopLeft),
+                        corner_offset(Corner::BottomRight),
+                    )],
+                ),
+                _ => {
+                    let corner = thread_type.corner().unwrap_or(Corner::TopLeft);
+                    let mut threads = vec![(corner_offset(corner), centre)];
+
+                    if thread_type == ThreadType::three_quarter(corner) {
+                        threads.push((
+                            corner_offset(corner.clockwise()),
+                            corner_offset(corner.anticlockwise()),
+                        ));
+                    }
+                    (corner, threads)
+                }
+            };
+            let (x, y) = corner_offset(quadrant);
+
+            for (start, end) in threads {
+                line(svg, at(start), at(end), MINOR_LINE_COLOR, MINOR_LINE_WIDTH);
+            }
+            text(
+                svg,
+                symbol,
+                at((0.25 + x * 0.5, 0.25 + y * 0.5)),
+                cell_size * 0.4,
+                "middle",
+            );
+        }
+        Mark::Border(thread_type) => {
+            let (start, end) = match thread_type {
+                ThreadType::BorderTop => (Corner::TopLeft, Corner::TopRight),
+                ThreadType::BorderRight => (Corner::TopRight, Corner::BottomRight),
+                ThreadType::BorderBottom => (Corner::BottomLeft, Corner::BottomRight),
+                _ => (Corner::TopLeft, Corner::BottomLeft),
+            };
+
+            line(
+                svg,
+                at(corner_offset(start)),
+                at(corner_offset(end)),
+                &thread.color.as_hex(),
+                cell_size * 0.15,
+            );
+        }
+    }
+}
+
+fn chart_page(
+    grid: &GridType,
+    overlay: &Overlay,
+    symbols: &IndexMap<ChartThread, &str>,
+    (cols, rows): (Range<usize>, Range<usize>),
+    cell_size: f64,
+    header: &str,
+) -> String {
+    let size = grid.size();
+    let grid_width = cols.len() as f64 * cell_size;
+    let grid_height = rows.len() as f64 * cell_size;
+    let grid_x = PAGE_MARGIN + NUMBERING_WIDTH;
+    let grid_y = PAGE_MARGIN + HEADER_HEIGHT + NUMBERING_WIDTH;
+    let line_x = |col: usize| grid_x + (col - cols.start) as f64 * cell_size;
+    let line_y = |row: usize| grid_y + (row - rows.start) as f64 * cell_size;
+    let on_page = |GridCell(col, row): GridCell| cols.contains(&col) && rows.contains(&row);
+    let mut svg = String::new();
+
+    svg_start(
+        &mut svg,
+        (
+            grid_x + grid_width + PAGE_MARGIN,
+            grid_y + grid_height + PAGE_MARGIN,
+        ),
+    );
+    text(
+        &mut svg,
+        header,
+        (PAGE_MARGIN, PAGE_MARGIN + HEADER_HEIGHT / 2.0),
+        HEADER_FONT_SIZE,
+        "start",
+    );
+
+    // Every tenth line is bold, as are the page's edges. Bold lines go last so the thin ones
+    // don't cross them.
+    let is_major = |line: usize, range: &Range<usize>| {
+        line.is_multiple_of(MAJOR_LINE_INTERVAL) || line == range.start || line == range.end
+    };
+    for major in [false, true] {
+        let (color, width) = if major {
+            (LINE_COLOR, MAJOR_LINE_WIDTH)
+        } else {
+            (MINOR_LINE_COLOR, MINOR_LINE_WIDTH)
+        };
+
+        for col in (cols.start..=cols.end).filter(|&col| is_major(col, &cols) == major) {
+            let x = line_x(col);
+
+            line(
+                &mut svg,
+                (x, grid_y),
+                (x, grid_y + grid_height),
+                color,
+                width,
+            );
+        }
+        for row in (rows.start..=rows.end).filter(|&row| is_major(row, &rows) == major) {
+            let y = line_y(row);
+
+            line(
+                &mut svg,
+                (grid_x, y),
+                (grid_x + grid_width, y),
+                color,
+                width,
+            );
+        }
+    }
+
+    // Numbered along the bold lines, counting from the pattern's top left rather than the page's
+    let is_numbered = |line: &usize| line.is_multiple_of(MAJOR_LINE_INTERVAL) && *line > 0;
+    for col in (cols.start..=cols.end).filter(is_numbered) {
+        text(
+            &mut svg,
+            &col.to_string(),
+            (line_x(col), grid_y - NUMBERING_WIDTH / 2.0),
+            NUMBER_FONT_SIZE,
+            "middle",
+        );
+    }
+    for row in (rows.start..=rows.end).filter(is_numbered) {
+        text(
+            &mut svg,
+            &row.to_string(),
+            (grid_x - 1.0, line_y(row)),
+            NUMBER_FONT_SIZE,
+            "end",
+        );
+    }
+
+    // The stitches are drawn in pattern coordinates inside a viewport that crops them to the
+    // page, so lines running off the page are cut at its edge
+    let _ = write!(
+        svg,
+        r#"<svg x="{}" y="{}" width="{}" height="{}" viewBox="{} {} {} {}" overflow="hidden">"#,
+        num(grid_x),
+        num(grid_y),
+        num(grid_width),
+        num(grid_height),
+        num(cols.start as f64 * cell_size),
+        num(rows.start as f64 * cell_size),
+        num(grid_width),
+        num(grid_height),
+    );
+
+    for row in rows.clone() {
+        for col in cols.clone() {
+            let grid_cell = GridCell(col, row);
+            let threads = grid.get(grid_cell).unwrap_or_default();
+
+            for (mark, thread) in cell_marks(&threads) {
+                let symbol = symbols.get(&thread).copied().unwrap_or_default();
+
+                draw_mark(&mut svg, (mark, thread), symbol, grid_cell, cell_size);
+            }
+        }
+    }
+
+    for backstitch in overlay.backstitches() {
+        if !backstitch.cells(size).into_iter().any(on_page) {
+            continue;
+        }
+
+        let points: Vec<String> = backstitch
+            .points
+            .iter()
+            .map(|GridIntersection(col, row)| {
+                format!(
+                    "{},{}",
+                    num(col as f64 * cell_size),
+                    num(row as f64 * cell_size)
+                )
+            })
+            .collect();
+        let _ = write!(
+            svg,
+            r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="{}" stroke-linecap="round" stroke-linejoin="round"/>"#,
+            points.join(" "),
+            backstitch.color.as_hex(),
+            num(cell_size * 0.15),
+        );
+    }
+
+    for (position, kind_color) in overlay.markers() {
+        if !position.cells(size).into_iter().any(on_page) {
+            continue;
+        }
+
+        let (x, y) = match position {
+            MarkerPosition::Centre(GridCell(col, row)) => (col as f64 + 0.5, row as f64 + 0.5),
+            MarkerPosition::Corner(GridIntersection(col, row)) => (col as f64, row as f64),
+        };
+
+        marker(
+            &mut svg,
+            kind_color.kind,
+            kind_color.color,
+            (x * cell_size, y * cell_size),
+            cell_size,
+        );
+    }
+
+    svg.push_str("</svg></svg>");
+    svg
+}
+
+fn legend_page(entries: &[LegendEntry], header: &str) -> String {
+    let table_y = PAGE_MARGIN + HEADER_HEIGHT;
+    let row_y = |index: usize| table_y + (index as f64 + 0.5) * LEGEND_ROW_HEIGHT;
+    let sample_x = PAGE_MARGIN + LEGEND_SAMPLE_SIZE / 2.0;
+    let thread_x = PAGE_MARGIN + 20.0;
+    let kind_x = PAGE_MARGIN + 115.0;
+    let count_x = PAGE_MARGIN + LEGEND_WIDTH;
+    let mut svg = String::new();
+
+    svg_start(
+        &mut svg,
+        (
+            PAGE_MARGIN * 2.0 + LEGEND_WIDTH,
+            table_y + (entries.len() + 1) as f64 * LEGEND_ROW_HEIGHT + PAGE_MARGIN,
+        ),
+    );
+    text(
+        &mut svg,
+        header,
+        (PAGE_MARGIN, PAGE_MARGIN + HEADER_HEIGHT / 2.0),
+        HEADER_FONT_SIZE,
+        "start",
+    );
+
+    for (content, x, anchor) in [
+        ("Symbol", PAGE_MARGIN, "start"),
+        ("Thread", thread_x, "start"),
+        ("Type", kind_x, "start"),
+        ("Stitches", count_x, "end"),
+    ] {
+        text(
+            &mut svg,
+            content,
+            (x, row_y(0)),
+            NUMBER_FONT_SIZE * 1.2,
+            anchor,
+        );
+    }
+
+    for (index, entry) in entries.iter().enumerate() {
+        let y = row_y(index + 1);
+        let half = LEGEND_SAMPLE_SIZE / 2.0;
+
+        line(
+            &mut svg,
+            (PAGE_MARGIN, y + LEGEND_ROW_HEIGHT / 2.0),
+            (count_x, y + LEGEND_ROW_HEIGHT / 2.0),
+            MINOR_LINE_COLOR,
+            MINOR_LINE_WIDTH,
+        );
+
+        match &entry.sample {
+            LegendSample::Symbol(symbol) => {
+                let _ = write!(
+                    svg,
+                    r#"<rect x="{}" y="{}" width="{}" height="{}" fill="none" stroke="{}" stroke-width="{}"/>"#,
+                    num(sample_x - half),
+                    num(y - half),
+                    num(LEGEND_SAMPLE_SIZE),
+                    num(LEGEND_SAMPLE_SIZE),
+                    LINE_COLOR,
+                    num(MINOR_LINE_WIDTH),
+                );
+                text(
+                    &mut svg,
+                    symbol,
+                    (sample_x, y),
+                    LEGEND_SAMPLE_SIZE * 0.75,
+                    "middle",
+                );
+            }
+            LegendSample::Line => line(
+                &mut svg,
+                (sample_x - half, y),
+                (sample_x + half, y),
+                &entry.thread.color.as_hex(),
+                LEGEND_SAMPLE_SIZE * 0.15,
+            ),
+            LegendSample::Marker(kind) => marker(
+                &mut svg,
+                *kind,
+                entry.thread.color,
+                (sample_x, y),
+                LEGEND_SAMPLE_SIZE,
+            ),
+        }
+
+        text(
+            &mut svg,
+            &entry.thread.label(),
+            (thread_x, y),
+            NUMBER_FONT_SIZE * 1.2,
+            "start",
+        );
+        text(
+            &mut svg,
+            &entry.sample.as_str(),
+            (kind_x, y),
+            NUMBER_FONT_SIZE * 1.2,
+            "start",
+        );
+        text(
+            &mut svg,
+            &entry.count.to_string(),
+            (count_x, y),
+            NUMBER_FONT_SIZE * 1.2,
+            "end",
+        );
+    }
+
+    svg.push_str("</svg>");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use implicit_clone::sync::IArray;
+
+    use super::*;
+    use crate::components::pattern::overlay::{Backstitch, Marker};
+    use crate::components::pattern::palette::Palette;
+    use crate::components::pattern::test_fixtures::{grid_with, BLUE, RED};
+
+    fn thread(color: Rgb) -> ChartThread {
+        ChartThread { color, entry: None }
+    }
+
+    #[test]
+    fn most_used_threads_get_the_first_symbols() {
+        let grid = grid_with(
+            GridSize(3, 1),
+            &[
+                (GridCell(0, 0), CellThreads::full_cross(RED)),
+                (GridCell(1, 0), CellThreads::full_cross(BLUE)),
+                (GridCell(2, 0), CellThreads::full_cross(BLUE)),
+            ],
+        );
+        let legend = legend(&grid, &Overlay::default());
+
+        assert_eq!(
+            legend,
+            vec![
+                LegendEntry {
+                    sample: LegendSample::Symbol(SYMBOLS[0].to_string()),
+                    thread: thread(BLUE),
+                    count: 2,
+                },
+                LegendEntry {
+                    sample: LegendSample::Symbol(SYMBOLS[1].to_string()),
+                    thread: thread(RED),
+                    count: 1,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn counts_each_kind_of_stitch() {
+        let mut partial = CellThreads::single(ThreadType::QuarterTopLeft, RED);
+        partial.set(ThreadType::ThreeQuarterBottomRight, RED);
+        partial.set(ThreadType::BorderTop, BLUE);
+        let grid = grid_with(
+            GridSize(3, 1),
+            &[
+                (GridCell(0, 0), CellThreads::full_cross(RED)),
+                (GridCell(1, 0), partial),
+                (
+                    GridCell(2, 0),
+                    CellThreads::single(ThreadType::SlashForwards, RED),
+                ),
+            ],
+        );
+        let mut overlay = Overlay::default();
+        overlay.insert_backstitch(
+            0,
+            Backstitch {
+                points: IArray::from(vec![
+                    GridIntersection(0, 0),
+                    GridIntersection(1, 1),
+                    GridIntersection(2, 1),
+                ]),
+                color: BLUE,
+            },
+        );
+        overlay.set_marker(
+            MarkerPosition::Centre(GridCell(0, 0)),
+            Some(Marker {
+                kind: MarkerKind::FrenchKnot,
+                color: RED,
+            }),
+        );
+        let counts: Vec<(LegendSample, usize)> = legend(&grid, &overlay)
+            .into_iter()
+            .map(|entry| (entry.sample, entry.count))
+            .collect();
+
+        assert_eq!(
+            counts,
+            vec![
+                // The full cross, the quarter, the three-quarter and the half stitch
+                (LegendSample::Symbol(SYMBOLS[0].to_string()), 4),
+                // The border and both backstitches
+                (LegendSample::Line, 3),
+                (LegendSample::Marker(MarkerKind::FrenchKnot), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn lines_take_the_floss_of_a_matching_stitch() {
+        let black = Palette::Dmc.find("310").unwrap();
+        let grid = grid_with(
+            GridSize(1, 1),
+            &[(
+                GridCell(0, 0),
+                CellThreads::full_cross(black.color()).with_entry(Some(black)),
+            )],
+        );
+        let mut overlay = Overlay::default();
+        overlay.insert_backstitch(
+            0,
+            Backstitch {
+                points: IArray::from(vec![GridIntersection(0, 0), GridIntersection(1, 0)]),
+                color: black.color(),
+            },
+        );
+        let legend = legend(&grid, &overlay);
+
+        assert_eq!(legend.len(), 2);
+        assert!(legend.iter().all(|entry| entry.thread.entry == Some(black)));
+        assert_eq!(legend[1].thread.label(), "DMC 310 Black");
+    }
+
+    #[test]
+    fn symbols_are_numbered_once_they_run_out() {
+        assert_eq!(symbol(0), SYMBOLS[0]);
+        assert_eq!(symbol(SYMBOLS.len()), format!("{}2", SYMBOLS[0]));
+        assert_eq!(symbol(SYMBOLS.len() * 2 + 1), format!("{}3", SYMBOLS[1]));
+    }
+
+    #[test]
+    fn splits_large_patterns_across_pages() {
+        assert_eq!(
+            page_ranges(GridSize(120, 80), GridSize(50, 70)),
+            vec![
+                (0..50, 0..70),
+                (50..100, 0..70),
+                (100..120, 0..70),
+                (0..50, 70..80),
+                (50..100, 70..80),
+                (100..120, 70..80),
+            ]
+        );
+
+        let grid = grid_with(
+            GridSize(120, 80),
+            &[(GridCell(0, 0), CellThreads::full_cross(RED))],
+        );
+        let pages = symbol_chart(&grid, &Overlay::default(), "", ChartOptions::default()).unwrap();
+
+        // Six pages of chart and one of legend
+        assert_eq!(pages.len(), 7);
+        assert!(pages[0].contains("Pattern · Page 1 of 7 · Columns 1–50 · Rows 1–70"));
+        assert!(pages[6].contains("Pattern · Key · Page 7 of 7"));
+        assert!(pages[0].contains(SYMBOLS[0]));
+        assert!(!pages[1].contains(SYMBOLS[0]));
+    }
+
+    #[test]
+    fn pages_have_bold_lines_and_numbers_every_ten_cells() {
+        let grid = grid_with(
+            GridSize(25, 12),
+            &[(GridCell(0, 0), CellThreads::full_cross(RED))],
+        );
+        let options = ChartOptions {
+            cell_size: 2.0,
+            page_size: GridSize(25, 12),
+        };
+        let pages = symbol_chart(&grid, &Overlay::default(), "Rose", options).unwrap();
+        let bold_lines = pages[0]
+            .matches(&format!(r#"stroke-width="{}""#, MAJOR_LINE_WIDTH))
+            .count();
+
+        // Columns 0, 10, 20 and 25 and rows 0, 10 and 12
+        assert_eq!(bold_lines, 7);
+        assert!(pages[0].contains(">10</text>"));
+        assert!(pages[0].contains(">20</text>"));
+        assert!(!pages[0].contains(">25</text>"));
+    }
+
+    #[test]
+    fn legend_lists_symbol_floss_and_count() {
+        let ecru = Palette::Dmc.find("ecru").unwrap();
+        let grid = grid_with(
+            GridSize(2, 1),
+            &[
+                (
+                    GridCell(0, 0),
+                    CellThreads::full_cross(ecru.color()).with_entry(Some(ecru)),
+                ),
+                (
+                    GridCell(1, 0),
+                    CellThreads::full_cross(ecru.color()).with_entry(Some(ecru)),
+                ),
+            ],
+        );
+        let pages =
+            symbol_chart(&grid, &Overlay::default(), "A & B", ChartOptions::default()).unwrap();
+        let key = pages.last().unwrap();
+
+        assert_eq!(pages.len(), 2);
+        assert!(key.contains(&format!(">{}</text>", SYMBOLS[0])));
+        assert!(key.contains(">DMC ECRU Ecru</text>"));
+        assert!(key.contains(">2</text>"));
+        assert!(key.contains("A &amp; B"));
+    }
+
+    #[test]
+    fn pages_are_saved_as_one_document() {
+        let grid = grid_with(
+            GridSize(12, 3),
+            &[(GridCell(0, 0), CellThreads::full_cross(RED))],
+        );
+        let options = ChartOptions {
+            page_size: GridSize(5, 5),
+            ..ChartOptions::default()
+        };
+        let pages = symbol_chart(&grid, &Overlay::default(), "A & B", options).unwrap();
+        let document = chart_document(&pages, "A & B");
+
+        assert_eq!(pages.len(), 4);
+        assert!(document.contains("<title>A &amp; B</title>"));
+        assert!(document.contains("break-after: page"));
+        assert_eq!(document.matches(r#"<svg xmlns="#).count(), pages.len());
+        assert!(pages.iter().all(|page| document.contains(page.as_str())));
+        assert!(chart_document(&pages, " ").contains("<title>Pattern</title>"));
+    }
+
+    #[test]
+    fn rejects_charts_that_cannot_be_drawn() {
+        let options = ChartOptions::default();
+        let empty = GridType::new(GridSize(0, 0));
+        let grid = GridType::new(GridSize(1, 1));
+
+        assert_eq!(
+            symbol_chart(&empty, &Overlay::default(), "", options),
+            Err(ChartError::EmptyPattern)
+        );
+        assert_eq!(
+            symbol_chart(
+                &grid,
+                &Overlay::default(),
+                "",
+                ChartOptions {
+                    page_size: GridSize(0, 10),
+                    ..options
+                }
+            ),
+            Err(ChartError::EmptyPage)
+        );
+
+        for cell_size in [0.0, -3.0, f64::NAN, f64::INFINITY] {
+            assert_eq!(
+                symbol_chart(
+                    &grid,
+                    &Overlay::default(),
+                    "",
+                    ChartOptions {
+                        cell_size,
+                        ..options
+                    }
+                ),
+                Err(ChartError::InvalidCellSize),
+                "cell size {}",
+                cell_size
+            );
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use implicit_clone::ImplicitClone;
+
+use super::document::{PatternDocument, PatternDocumentError, PatternMetadata};
+use super::overlay::Overlay;
+use super::{CellGrid, CellThreads, GridCell, GridSize, GridType};
+
+/// Threads copied out of a pattern. They're kept on a grid the size of the selection's bounding
+/// box, so each cell sits at its offset from the top left of the selection.
+#[derive(Clone, Debug, PartialEq, ImplicitClone)]
+pub struct ClipboardCells {
+    grid: GridType,
+}
+
+impl ClipboardCells {
+    /// Copies the threads of the selected cells. Returns `None` when nothing is selected.
+    pub fn from_selection(grid: &GridType, selected_cells: &[GridCell]) -> Option<Self> {
+        let GridCell(min_col, min_row) = selection_origin(selected_cells)?;
+        let (max_col, max_row) = selected_cells.iter().fold(
+            (min_col, min_row),
+            |(max_col, max_row), &GridCell(col, row)| (max_col.max(col), max_row.max(row)),
+        );
+        let mut cells = CellGrid::new(GridSize(max_col - min_col + 1, max_row - min_row + 1));
+
+        for &grid_cell in selected_cells {
+            if let Some(threads) = grid.get(grid_cell) {
+                cells.set(
+                    GridCell(grid_cell.0 - min_col, grid_cell.1 - min_row),
+                    threads,
+                );
+            }
+        }
+
+        Some(ClipboardCells { grid: cells })
+    }
+
+    /// The cells with threads, moved so the top left of the copied area lands on `origin`.
+    /// Anything that falls outside of `size` is left out.
+    pub fn placed(&self, origin: GridCell, size: GridSize) -> Vec<(GridCell, CellThreads)> {
+        self.grid
+            .iter()
+            .filter(|(_, threads)| !threads.is_empty())
+            .map(|(GridCell(col, row), threads)| {
+                (GridCell(origin.0 + col, origin.1 + row), threads)
+            })
+            .filter(|&(grid_cell, _)| size.contains(grid_cell))
+            .collect()
+    }
+
+    /// The origin closest to `origin` that keeps every copied cell inside `size`. Cells that
+    /// are wider or taller than `size` are kept against its left or top edge.
+    pub fn fitted_origin(&self, origin: GridCell, GridSize(cols, rows): GridSize) -> GridCell {
+        let GridSize(width, height) = self.grid.size();
+
+        GridCell(
+            origin.0.min(cols.saturating_sub(width)),
+            origin.1.min(rows.saturating_sub(height)),
+        )
+    }
+
+    /// Stored in the pattern file format, so the system clipboard holds something other tabs
+    /// can read back.
+    pub fn to_json(&self) -> Result<String, PatternDocumentError> {
+        PatternDocument::new(&self.grid, &Overlay::default(), PatternMetadata::default()).to_json()
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, PatternDocumentError> {
+        let grid = PatternDocument::from_json(json)?.to_grid()?;
+
+        Ok(ClipboardCells { grid })
+    }
+}
+
+/// The top left corner of the smallest rectangle holding every selected cell.
+pub fn selection_origin(selected_cells: &[GridCell]) -> Option<GridCell> {
+    selected_cells
+        .iter()
+        .copied()
+        .reduce(|GridCell(min_col, min_row), GridCell(col, row)| {
+            GridCell(min_col.min(col), min_row.min(row))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::pattern::test_fixtures::{grid_with, BLUE, RED};
+
+    const SIZE: GridSize = GridSize(8, 8);
+
+    // Two stitched cells at opposite corners of a sparse selection, with an empty cell between
+    fn copied() -> ClipboardCells {
+        let grid = grid_with(
+            SIZE,
+            &[
+                (GridCell(2, 3), CellThreads::full_cross(RED)),
+                (GridCell(4, 5), CellThreads::full_cross(BLUE)),
+                (GridCell(7, 7), CellThreads::full_cross(BLUE)),
+            ],
+        );
+
+        ClipboardCells::from_selection(&grid, &[GridCell(4, 5), GridCell(3, 4), GridCell(2, 3)])
+            .unwrap()
+    }
+
+    #[test]
+    fn copied_cells_keep_their_offsets_from_the_selection() {
+        let cells = copied();
+
+        assert_eq!(cells.grid.size(), GridSize(3, 3));
+        assert_eq!(
+            cells.placed(GridCell(0, 0), SIZE),
+            vec![
+                (GridCell(0, 0), CellThreads::full_cross(RED)),
+                (GridCell(2, 2), CellThreads::full_cross(BLUE)),
+            ]
+        );
+        assert_eq!(
+            cells.placed(GridCell(5, 1), SIZE),
+            vec![
+                (GridCell(5, 1), CellThreads::full_cross(RED)),
+                (GridCell(7, 3), CellThreads::full_cross(BLUE)),
+            ]
+        );
+        assert_eq!(
+            ClipboardCells::from_selection(&CellGrid::new(SIZE), &[]),
+            None
+        );
+    }
+
+    #[test]
+    fn cells_past_the_grid_edge_are_left_out() {
+        let cells = copied();
+
+        assert_eq!(
+            cells.placed(GridCell(6, 6), SIZE),
+            vec![(GridCell(6, 6), CellThreads::full_cross(RED))]
+        );
+        assert!(cells.placed(GridCell(8, 0), SIZE).is_empty());
+    }
+
+    #[test]
+    fn fitted_origin_keeps_every_cell_inside_the_grid() {
+        let cells = copied();
+
+        assert_eq!(cells.fitted_origin(GridCell(3, 2), SIZE), GridCell(3, 2));
+        assert_eq!(cells.fitted_origin(GridCell(7, 6), SIZE), GridCell(5, 5));
+        assert_eq!(cells.fitted_origin(GridCell(2, 40), SIZE), GridCell(2, 5));
+
+        let fitted = cells.fitted_origin(GridCell(7, 7), SIZE);
+
+        assert_eq!(cells.placed(fitted, SIZE).len(), 2);
+        // Cells larger than the grid stay against its top left
+        assert_eq!(
+            cells.fitted_origin(GridCell(1, 1), GridSize(2, 4)),
+            GridCell(0, 1)
+        );
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let cells = copied();
+        let json = cells.to_json().unwrap();
+
+        assert_eq!(ClipboardCells::from_json(&json).unwrap(), cells);
+        assert!(ClipboardCells::from_json("not a pattern").is_err());
+    }
+}
@@ -0,0 +1,833 @@
+use std::f64::consts::PI;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+mod named;
+
+use named::NAMED_COLORS;
+
+// D65 reference white, which sRGB is defined against
+const WHITE_X: f64 = 0.95047;
+const WHITE_Y: f64 = 1.0;
+const WHITE_Z: f64 = 1.08883;
+
+#[non_exhaustive]
+#[derive(thiserror::Error, Debug)]
+pub enum RgbError {
+    #[error("invalid input")]
+    InvalidInput(String),
+    #[error("string to u32 conversion")]
+    Conversion(String),
+    #[error("colour {0} is not fully opaque")]
+    Translucent(String),
+}
+
+// Serialized as a hex string so pattern files stay readable
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Rgb {
+    pub const BLACK: Rgb = Rgb { r: 0, g: 0, b: 0 };
+    pub const WHITE: Rgb = Rgb {
+        r: 255,
+        g: 255,
+        b: 255,
+    };
+
+    pub fn as_hex(self) -> String {
+        format!("#{:02X}{:02X}{:02X}", self.r, self.g, self.b).to_lowercase()
+    }
+
+    /// Reads `#rrggbb` or the `#rgb` shorthand. The `#` is optional.
+    pub fn from_hex(hex: String) -> Result<Rgb, RgbError> {
+        let digits = hex.trim_start_matches('#');
+
+        if !digits.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+            return Err(RgbError::InvalidInput(hex));
+        }
+
+        let int_value =
+            u32::from_str_radix(digits, 16).map_err(|s| RgbError::Conversion(s.to_string()))?;
+
+        match digits.len() {
+            // Each digit is doubled up, so #f80 is #ff8800
+            3 => Ok(Rgb {
+                r: ((int_value >> 8) & 0xF) as u8 * 0x11,
+                g: ((int_value >> 4) & 0xF) as u8 * 0x11,
+                b: (int_value & 0xF) as u8 * 0x11,
+            }),
+            6 => Ok(Rgb {
+                r: ((int_value >> 16) & 0xFF) as u8,
+                g: ((int_value >> 8) & 0xFF) as u8,
+                b: (int_value & 0xFF) as u8,
+            }),
+            _ => Err(RgbError::InvalidInput(hex)),
+        }
+    }
+
+    /// Relative luminance as defined by WCAG, from 0 for black to 1 for white.
+    pub fn luminance(self) -> f64 {
+        let (r, g, b) = self.linear();
+
+        0.2126 * r + 0.7152 * g + 0.0722 * b
+    }
+
+    /// The WCAG contrast ratio between two colours, from 1 for the same colour to 21 for black on
+    /// white. It's the same whichever way round the colours are.
+    pub fn contrast_ratio(self, other: Rgb) -> f64 {
+        let (a, b) = (self.luminance(), other.luminance());
+
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
+
+    /// Whichever of black and white is easier to read on this colour.
+    pub fn contrasting(self) -> Rgb {
+        if self.contrast_ratio(Rgb::BLACK) >= self.contrast_ratio(Rgb::WHITE) {
+            Rgb::BLACK
+        } else {
+            Rgb::WHITE
+        }
+    }
+
+    /// Mixes in `amount` of `other`, from 0 for this colour to 1 for `other`. Channels are mixed
+    /// in sRGB, the same way a browser blends a translucent colour over another.
+    pub fn blend(self, other: Rgb, amount: f64) -> Rgb {
+        let amount = amount.clamp(0.0, 1.0);
+        let mix =
+            |from: u8, to: u8| (from as f64 + (to as f64 - from as f64) * amount).round() as u8;
+
+        Rgb {
+            r: mix(self.r, other.r),
+            g: mix(self.g, other.g),
+            b: mix(self.b, other.b),
+        }
+    }
+
+    /// The CIEDE2000 difference between two colours. Around 1 is the smallest difference most
+    /// people can see, and anything under about 2 is hard to tell apart on fabric.
+    pub fn delta_e(self, other: Rgb) -> f64 {
+        Lab::from(self).delta_e(Lab::from(other))
+    }
+
+    // Each channel from 0 to 1 with the sRGB gamma curve taken off
+    fn linear(self) -> (f64, f64, f64) {
+        let linear = |channel: u8| {
+            let channel = channel as f64 / 255.0;
+            if channel <= 0.04045 {
+                channel / 12.92
+            } else {
+                ((channel + 0.055) / 1.055).powf(2.4)
+            }
+        };
+
+        (linear(self.r), linear(self.g), linear(self.b))
+    }
+
+    // The inverse of `linear`, clamping colours that are out of the sRGB gamut
+    fn from_linear(r: f64, g: f64, b: f64) -> Rgb {
+        let encode = |channel: f64| {
+            let channel = channel.clamp(0.0, 1.0);
+            let channel = if channel <= 0.0031308 {
+                channel * 12.92
+            } else {
+                1.055 * channel.powf(1.0 / 2.4) - 0.055
+            };
+            (channel * 255.0).round() as u8
+        };
+
+        Rgb {
+            r: encode(r),
+            g: encode(g),
+            b: encode(b),
+        }
+    }
+
+    // Each channel from 0 to 1
+    fn unit(self) -> (f64, f64, f64) {
+        (
+            self.r as f64 / 255.0,
+            self.g as f64 / 255.0,
+            self.b as f64 / 255.0,
+        )
+    }
+
+    // Hue in degrees along with the largest channel and the chroma, shared by HSL and HSV
+    fn hue(self) -> (f64, f64, f64) {
+        let (r, g, b) = self.unit();
+        let max = r.max(g).max(b);
+        let chroma = max - r.min(g).min(b);
+        let hue = if chroma == 0.0 {
+            0.0
+        } else if max == r {
+            60.0 * ((g - b) / chroma).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / chroma + 2.0)
+        } else {
+            60.0 * ((r - g) / chroma + 4.0)
+        };
+
+        (hue, max, chroma)
+    }
+
+    // The colour with the given hue and chroma, lifted by `lightness` on every channel
+    fn from_hue(hue: f64, chroma: f64, lightness: f64) -> Rgb {
+        let sector = hue.rem_euclid(360.0) / 60.0;
+        let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+        let (r, g, b) = match sector as u8 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        let channel = |value: f64| ((value + lightness).clamp(0.0, 1.0) * 255.0).round() as u8;
+
+        Rgb {
+            r: channel(r),
+            g: channel(g),
+            b: channel(b),
+        }
+    }
+}
+
+impl TryFrom<String> for Rgb {
+    type Error = RgbError;
+
+    fn try_from(hex: String) -> Result<Self, Self::Error> {
+        Rgb::from_hex(hex)
+    }
+}
+
+impl From<Rgb> for String {
+    fn from(color: Rgb) -> Self {
+        color.as_hex()
+    }
+}
+
+impl fmt::Display for Rgb {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} {}", self.r, self.g, self.b)
+    }
+}
+
+/// Parses the opaque CSS colour syntaxes: `#rgb`, `#rrggbb`, `rgb()`, `rgba()`, `hsl()`,
+/// `hsla()` and named colours, with either comma or space separated arguments. Colours with an
+/// alpha below 1 are rejected, since a thread can't be see-through.
+impl FromStr for Rgb {
+    type Err = RgbError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value = s.trim().to_ascii_lowercase();
+        let invalid = || RgbError::InvalidInput(s.to_string());
+
+        if let Some(hex) = value.strip_prefix('#') {
+            return match hex.len() {
+                3 | 6 => Rgb::from_hex(hex.to_string()),
+                // The alpha digits are dropped once they're checked
+                4 | 8 => {
+                    let (hex, alpha) = hex.split_at(hex.len() / 4 * 3);
+
+                    if alpha.chars().all(|digit| digit == 'f') {
+                        Rgb::from_hex(hex.to_string())
+                    } else {
+                        Err(RgbError::Translucent(s.to_string()))
+                    }
+                }
+                _ => Err(invalid()),
+            };
+        }
+
+        if let Some((function, arguments)) = value
+            .strip_suffix(')')
+            .and_then(|value| value.split_once('('))
+        {
+            let arguments = arguments.replace([',', '/'], " ");
+            let arguments: Vec<&str> = arguments.split_whitespace().collect();
+            let (channels, alpha) = match arguments.as_slice() {
+                [a, b, c] => ([*a, *b, *c], None),
+                [a, b, c, alpha] => ([*a, *b, *c], Some(*alpha)),
+                _ => return Err(invalid()),
+            };
+
+            if let Some(alpha) = alpha {
+                if css_fraction(alpha).ok_or_else(invalid)? < 1.0 {
+                    return Err(RgbError::Translucent(s.to_string()));
+                }
+            }
+
+            return match function.trim() {
+                "rgb" | "rgba" => {
+                    let channel = |value: &str| {
+                        let value = match value.strip_suffix('%') {
+                            Some(percent) => percent.parse::<f64>().ok()? / 100.0 * 255.0,
+                            None => value.parse::<f64>().ok()?,
+                        };
+                        value
+                            .is_finite()
+                            .then(|| value.clamp(0.0, 255.0).round() as u8)
+                    };
+
+                    Ok(Rgb {
+                        r: channel(channels[0]).ok_or_else(invalid)?,
+                        g: channel(channels[1]).ok_or_else(invalid)?,
+                        b: channel(channels[2]).ok_or_else(invalid)?,
+                    })
+                }
+                "hsl" | "hsla" => {
+                    let percent = |value: &str| {
+                        let value = value.strip_suffix('%').unwrap_or(value);
+                        let value = value.parse::<f64>().ok()?;
+                        value.is_finite().then(|| value.clamp(0.0, 100.0) / 100.0)
+                    };
+
+                    Ok(Rgb::from(Hsl {
+                        h: css_angle(channels[0]).ok_or_else(invalid)?,
+                        s: percent(channels[1]).ok_or_else(invalid)?,
+                        l: percent(channels[2]).ok_or_else(invalid)?,
+                    }))
+                }
+                _ => Err(invalid()),
+            };
+        }
+
+        NAMED_COLORS
+            .binary_search_by(|(name, _)| (*name).cmp(value.as_str()))
+            .map(|index| NAMED_COLORS[index].1)
+            .map_err(|_| invalid())
+    }
+}
+
+// An alpha value, either a number from 0 to 1 or a percentage
+fn css_fraction(value: &str) -> Option<f64> {
+    let fraction = match value.strip_suffix('%') {
+        Some(percent) => percent.parse::<f64>().ok()? / 100.0,
+        None => value.parse::<f64>().ok()?,
+    };
+
+    fraction.is_finite().then_some(fraction)
+}
+
+// A hue in degrees. Plain numbers are degrees too.
+fn css_angle(value: &str) -> Option<f64> {
+    let (number, degrees_per_unit) = if let Some(number) = value.strip_suffix("deg") {
+        (number, 1.0)
+    } else if let Some(number) = value.strip_suffix("grad") {
+        (number, 0.9)
+    } else if let Some(number) = value.strip_suffix("rad") {
+        (number, 180.0 / PI)
+    } else if let Some(number) = value.strip_suffix("turn") {
+        (number, 360.0)
+    } else {
+        (value, 1.0)
+    };
+    let degrees = number.parse::<f64>().ok()? * degrees_per_unit;
+
+    degrees.is_finite().then_some(degrees)
+}
+
+/// Hue in degrees, with saturation and lightness from 0 to 1.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hsl {
+    pub h: f64,
+    pub s: f64,
+    pub l: f64,
+}
+
+impl From<Rgb> for Hsl {
+    fn from(color: Rgb) -> Self {
+        let (h, max, chroma) = color.hue();
+        let l = max - chroma / 2.0;
+        let s = if chroma == 0.0 {
+            0.0
+        } else {
+            chroma / (1.0 - (2.0 * l - 1.0).abs())
+        };
+
+        Hsl { h, s, l }
+    }
+}
+
+impl From<Hsl> for Rgb {
+    fn from(Hsl { h, s, l }: Hsl) -> Self {
+        let chroma = (1.0 - (2.0 * l - 1.0).abs()) * s;
+
+        Rgb::from_hue(h, chroma, l - chroma / 2.0)
+    }
+}
+
+/// Hue in degrees, with saturation and value from 0 to 1.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hsv {
+    pub h: f64,
+    pub s: f64,
+    pub v: f64,
+}
+
+impl From<Rgb> for Hsv {
+    fn from(color: Rgb) -> Self {
+        let (h, v, chroma) = color.hue();
+        let s = if v == 0.0 { 0.0 } else { chroma / v };
+
+        Hsv { h, s, v }
+    }
+}
+
+impl From<Hsv> for Rgb {
+    fn from(Hsv { h, s, v }: Hsv) -> Self {
+        let chroma = v * s;
+
+        Rgb::from_hue(h, chroma, v - chroma)
+    }
+}
+
+/// CIELAB under a D65 white point. `l` runs from 0 for black to 100 for white, and equal steps
+/// look roughly equally different.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Lab {
+    pub l: f64,
+    pub a: f64,
+    pub b: f64,
+}
+
+impl Lab {
+    /// The CIEDE2000 colour difference.
+    pub fn delta_e(self, other: Lab) -> f64 {
+        let Lab {
+            l: l1,
+            a: a1,
+            b: b1,
+        } = self;
+        let Lab {
+            l: l2,
+            a: a2,
+            b: b2,
+        } = other;
+        let pow_25_7 = 25f64.powi(7);
+
+        // Greys are pulled towards neutral by stretching the a axis
+        let mean_chroma = (a1.hypot(b1) + a2.hypot(b2)) / 2.0;
+        let g = 0.5 * (1.0 - (mean_chroma.powi(7) / (mean_chroma.powi(7) + pow_25_7)).sqrt());
+        let (a1, a2) = ((1.0 + g) * a1, (1.0 + g) * a2);
+        let (c1, c2) = (a1.hypot(b1), a2.hypot(b2));
+        let hue = |a: f64, b: f64| {
+            if a == 0.0 && b == 0.0 {
+                0.0
+            } else {
+                b.atan2(a).to_degrees().rem_euclid(360.0)
+            }
+        };
+        let (h1, h2) = (hue(a1, b1), hue(a2, b2));
+        let is_grey = c1 * c2 == 0.0;
+
+        let delta_l = l2 - l1;
+        let delta_c = c2 - c1;
+        let delta_h = if is_grey {
+            0.0
+        } else {
+            let delta = h2 - h1;
+            if delta > 180.0 {
+                delta - 360.0
+            } else if delta < -180.0 {
+                delta + 360.0
+            } else {
+                delta
+            }
+        };
+        let delta_h = 2.0 * (c1 * c2).sqrt() * (delta_h / 2.0).to_radians().sin();
+
+        let mean_l = (l1 + l2) / 2.0;
+        let mean_c = (c1 + c2) / 2.0;
+        let mean_h = if is_grey {
+            h1 + h2
+        } else if (h1 - h2).abs() <= 180.0 {
+            (h1 + h2) / 2.0
+        } else if h1 + h2 < 360.0 {
+            (h1 + h2 + 360.0) / 2.0
+        } else {
+            (h1 + h2 - 360.0) / 2.0
+        };
+
+        let t = 1.0 - 0.17 * (mean_h - 30.0).to_radians().cos()
+            + 0.24 * (2.0 * mean_h).to_radians().cos()
+            + 0.32 * (3.0 * mean_h + 6.0).to_radians().cos()
+            - 0.20 * (4.0 * mean_h - 63.0).to_radians().cos();
+        let delta_theta = 30.0 * (-((mean_h - 275.0) / 25.0).powi(2)).exp();
+        let r_c = 2.0 * (mean_c.powi(7) / (mean_c.powi(7) + pow_25_7)).sqrt();
+        let s_l = 1.0 + 0.015 * (mean_l - 50.0).powi(2) / (20.0 + (mean_l - 50.0).powi(2)).sqrt();
+        let s_c = 1.0 + 0.045 * mean_c;
+        let s_h = 1.0 + 0.015 * mean_c * t;
+        // Corrects for blues, where hue and chroma differences interact
+        let r_t = -(2.0 * delta_theta).to_radians().sin() * r_c;
+
+        let (l, c, h) = (delta_l / s_l, delta_c / s_c, delta_h / s_h);
+
+        (l * l + c * c + h * h + r_t * c * h).sqrt()
+    }
+}
+
+impl From<Rgb> for Lab {
+    fn from(color: Rgb) -> Self {
+        let (r, g, b) = color.linear();
+        let x = (0.4124564 * r + 0.3575761 * g + 0.1804375 * b) / WHITE_X;
+        let y = (0.2126729 * r + 0.7151522 * g + 0.0721750 * b) / WHITE_Y;
+        let z = (0.0193339 * r + 0.1191920 * g + 0.9503041 * b) / WHITE_Z;
+        let f = |t: f64| {
+            if t > 216.0 / 24389.0 {
+                t.cbrt()
+            } else {
+                (24389.0 / 27.0 * t + 16.0) / 116.0
+            }
+        };
+        let (fx, fy, fz) = (f(x), f(y), f(z));
+
+        Lab {
+            l: 116.0 * fy - 16.0,
+            a: 500.0 * (fx - fy),
+            b: 200.0 * (fy - fz),
+        }
+    }
+}
+
+/// Colours outside of the sRGB gamut are clamped to it.
+impl From<Lab> for Rgb {
+    fn from(Lab { l, a, b }: Lab) -> Self {
+        let fy = (l + 16.0) / 116.0;
+        let (fx, fz) = (fy + a / 500.0, fy - b / 200.0);
+        let f_inverse = |t: f64| {
+            if t.powi(3) > 216.0 / 24389.0 {
+                t.powi(3)
+            } else {
+                (116.0 * t - 16.0) * 27.0 / 24389.0
+            }
+        };
+        let (x, y, z) = (
+            f_inverse(fx) * WHITE_X,
+            f_inverse(fy) * WHITE_Y,
+            f_inverse(fz) * WHITE_Z,
+        );
+
+        Rgb::from_linear(
+            3.2404542 * x - 1.5371385 * y - 0.4985314 * z,
+            -0.9692660 * x + 1.8760108 * y + 0.0415560 * z,
+            0.0556434 * x - 0.2040259 * y + 1.0572252 * z,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rgb(r: u8, g: u8, b: u8) -> Rgb {
+        Rgb { r, g, b }
+    }
+
+    // A spread of colours covering every corner of the RGB cube
+    fn samples() -> impl Iterator<Item = Rgb> {
+        (0..=255u8).step_by(15).flat_map(|r| {
+            (0..=255u8)
+                .step_by(15)
+                .flat_map(move |g| (0..=255u8).step_by(15).map(move |b| rgb(r, g, b)))
+        })
+    }
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        assert!(
+            (actual - expected).abs() <= tolerance,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn hex_accepts_short_and_long_forms() {
+        assert_eq!(
+            Rgb::from_hex("#1a2B3c".to_string()).unwrap(),
+            rgb(26, 43, 60)
+        );
+        assert_eq!(
+            Rgb::from_hex("1a2b3c".to_string()).unwrap(),
+            rgb(26, 43, 60)
+        );
+        assert_eq!(Rgb::from_hex("#f80".to_string()).unwrap(), rgb(255, 136, 0));
+        assert!(Rgb::from_hex("#12345".to_string()).is_err());
+        assert!(Rgb::from_hex("#+12345".to_string()).is_err());
+        assert!(Rgb::from_hex("#ggg".to_string()).is_err());
+        assert_eq!(rgb(26, 43, 60).as_hex(), "#1a2b3c");
+    }
+
+    #[test]
+    fn serializes_as_hex() {
+        let json = serde_json::to_string(&rgb(255, 136, 0)).unwrap();
+
+        assert_eq!(json, "\"#ff8800\"");
+        assert_eq!(
+            serde_json::from_str::<Rgb>(&json).unwrap(),
+            rgb(255, 136, 0)
+        );
+    }
+
+    #[test]
+    fn parses_css_hex() {
+        assert_eq!("#F80".parse::<Rgb>().unwrap(), rgb(255, 136, 0));
+        assert_eq!(" #ff8800 ".parse::<Rgb>().unwrap(), rgb(255, 136, 0));
+        assert_eq!("#ff8800ff".parse::<Rgb>().unwrap(), rgb(255, 136, 0));
+        assert_eq!("#f80f".parse::<Rgb>().unwrap(), rgb(255, 136, 0));
+        assert!(matches!(
+            "#ff880080".parse::<Rgb>(),
+            Err(RgbError::Translucent(_))
+        ));
+        assert!("#ff88".parse::<Rgb>().is_err());
+    }
+
+    #[test]
+    fn parses_css_rgb_functions() {
+        assert_eq!("rgb(255, 136, 0)".parse::<Rgb>().unwrap(), rgb(255, 136, 0));
+        assert_eq!("RGB(255 136 0)".parse::<Rgb>().unwrap(), rgb(255, 136, 0));
+        assert_eq!(
+            "rgba(255,136,0,1)".parse::<Rgb>().unwrap(),
+            rgb(255, 136, 0)
+        );
+        assert_eq!(
+            "rgb(255 136 0 / 100%)".parse::<Rgb>().unwrap(),
+            rgb(255, 136, 0)
+        );
+        assert_eq!(
+            "rgb(100%, 50%, 0%)".parse::<Rgb>().unwrap(),
+            rgb(255, 128, 0)
+        );
+        // Out of range channels are clamped, as browsers do
+        assert_eq!(
+            "rgb(300, -20, 12.4)".parse::<Rgb>().unwrap(),
+            rgb(255, 0, 12)
+        );
+        assert!(matches!(
+            "rgba(255, 136, 0, 0.5)".parse::<Rgb>(),
+            Err(RgbError::Translucent(_))
+        ));
+        assert!("rgb(255, 136)".parse::<Rgb>().is_err());
+        assert!("rgb(255, 136, zero)".parse::<Rgb>().is_err());
+        assert!("rgb(255, 136, 0".parse::<Rgb>().is_err());
+        assert!("cmyk(0, 0, 0, 0)".parse::<Rgb>().is_err());
+    }
+
+    #[test]
+    fn parses_css_hsl_functions() {
+        assert_eq!("hsl(0, 100%, 50%)".parse::<Rgb>().unwrap(), rgb(255, 0, 0));
+        assert_eq!(
+            "hsl(120deg 100% 25%)".parse::<Rgb>().unwrap(),
+            rgb(0, 128, 0)
+        );
+        assert_eq!(
+            "hsla(0.5turn, 100%, 50%, 1)".parse::<Rgb>().unwrap(),
+            rgb(0, 255, 255)
+        );
+        assert_eq!(
+            "hsl(-120, 100%, 50%)".parse::<Rgb>().unwrap(),
+            rgb(0, 0, 255)
+        );
+        assert_eq!(
+            "hsl(3.14159rad, 100%, 50%)".parse::<Rgb>().unwrap(),
+            rgb(0, 255, 255)
+        );
+        assert!("hsl(0, 100%, 50%, 0.2)".parse::<Rgb>().is_err());
+    }
+
+    #[test]
+    fn parses_named_colors() {
+        assert_eq!("rebeccapurple".parse::<Rgb>().unwrap(), rgb(102, 51, 153));
+        assert_eq!("White".parse::<Rgb>().unwrap(), Rgb::WHITE);
+        assert!("transparent".parse::<Rgb>().is_err());
+        assert!("notacolour".parse::<Rgb>().is_err());
+        assert!(NAMED_COLORS.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+
+    #[test]
+    fn hsl_known_values() {
+        let hsl = Hsl::from(rgb(255, 0, 0));
+        assert_eq!((hsl.h, hsl.s, hsl.l), (0.0, 1.0, 0.5));
+
+        let hsl = Hsl::from(rgb(0, 0, 255));
+        assert_eq!((hsl.h, hsl.s, hsl.l), (240.0, 1.0, 0.5));
+
+        let hsl = Hsl::from(rgb(128, 128, 128));
+        assert_eq!((hsl.h, hsl.s), (0.0, 0.0));
+        assert_close(hsl.l, 128.0 / 255.0, 1e-9);
+
+        assert_eq!(
+            Rgb::from(Hsl {
+                h: 30.0,
+                s: 1.0,
+                l: 0.5
+            }),
+            rgb(255, 128, 0)
+        );
+        // Hues wrap around
+        assert_eq!(
+            Rgb::from(Hsl {
+                h: 480.0,
+                s: 1.0,
+                l: 0.5
+            }),
+            rgb(0, 255, 0)
+        );
+    }
+
+    #[test]
+    fn hsv_known_values() {
+        let hsv = Hsv::from(rgb(255, 255, 0));
+        assert_eq!((hsv.h, hsv.s, hsv.v), (60.0, 1.0, 1.0));
+
+        let hsv = Hsv::from(Rgb::BLACK);
+        assert_eq!((hsv.h, hsv.s, hsv.v), (0.0, 0.0, 0.0));
+
+        let hsv = Hsv::from(rgb(255, 0, 255));
+        assert_eq!((hsv.h, hsv.s, hsv.v), (300.0, 1.0, 1.0));
+
+        assert_eq!(
+            Rgb::from(Hsv {
+                h: 210.0,
+                s: 0.5,
+                v: 1.0
+            }),
+            rgb(128, 191, 255)
+        );
+    }
+
+    #[test]
+    fn lab_known_values() {
+        let white = Lab::from(Rgb::WHITE);
+        assert_close(white.l, 100.0, 1e-3);
+        assert_close(white.a, 0.0, 1e-3);
+        assert_close(white.b, 0.0, 1e-3);
+
+        let black = Lab::from(Rgb::BLACK);
+        assert_eq!((black.l, black.a, black.b), (0.0, 0.0, 0.0));
+
+        let red = Lab::from(rgb(255, 0, 0));
+        assert_close(red.l, 53.24, 0.01);
+        assert_close(red.a, 80.09, 0.01);
+        assert_close(red.b, 67.20, 0.01);
+
+        let blue = Lab::from(rgb(0, 0, 255));
+        assert_close(blue.l, 32.30, 0.01);
+        assert_close(blue.a, 79.19, 0.01);
+        assert_close(blue.b, -107.86, 0.01);
+    }
+
+    #[test]
+    fn lab_out_of_gamut_is_clamped() {
+        assert_eq!(
+            Rgb::from(Lab {
+                l: 150.0,
+                a: 0.0,
+                b: 0.0
+            }),
+            Rgb::WHITE
+        );
+        assert_eq!(
+            Rgb::from(Lab {
+                l: 50.0,
+                a: 0.0,
+                b: -200.0
+            })
+            .r,
+            0
+        );
+    }
+
+    #[test]
+    fn conversions_round_trip() {
+        for color in samples() {
+            assert_eq!(Rgb::from(Hsl::from(color)), color, "HSL {color}");
+            assert_eq!(Rgb::from(Hsv::from(color)), color, "HSV {color}");
+            assert_eq!(Rgb::from(Lab::from(color)), color, "Lab {color}");
+        }
+    }
+
+    // Reference pairs from Sharma, Wu and Dalal's CIEDE2000 test data
+    #[test]
+    fn delta_e_matches_reference_data() {
+        let pairs = [
+            ((50.0, 2.6772, -79.7751), (50.0, 0.0, -82.7485), 2.0425),
+            ((50.0, 3.1571, -77.2803), (50.0, 0.0, -82.7485), 2.8615),
+            ((50.0, 2.8361, -74.0200), (50.0, 0.0, -82.7485), 3.4412),
+            ((50.0, -1.3802, -84.2814), (50.0, 0.0, -82.7485), 1.0000),
+            ((50.0, 0.0, 0.0), (50.0, -1.0, 2.0), 2.3669),
+            ((50.0, 2.5, 0.0), (73.0, 25.0, -18.0), 27.1492),
+            ((50.0, 2.5, 0.0), (61.0, -5.0, 29.0), 22.8977),
+            ((50.0, 2.5, 0.0), (56.0, -27.0, -3.0), 31.9030),
+            ((50.0, 2.5, 0.0), (58.0, 24.0, 15.0), 19.4535),
+            ((50.0, 2.5, 0.0), (50.0, 3.1736, 0.5854), 1.0000),
+            (
+                (2.0776, 0.0795, -1.1350),
+                (0.9033, -0.0636, -0.5514),
+                0.9082,
+            ),
+            (
+                (22.7233, 20.0904, -46.6940),
+                (23.0331, 14.9730, -42.5619),
+                2.0373,
+            ),
+            (
+                (60.2574, -34.0099, 36.2677),
+                (60.4626, -34.1751, 39.4387),
+                1.2644,
+            ),
+        ];
+
+        for ((l1, a1, b1), (l2, a2, b2), expected) in pairs {
+            let first = Lab {
+                l: l1,
+                a: a1,
+                b: b1,
+            };
+            let second = Lab {
+                l: l2,
+                a: a2,
+                b: b2,
+            };
+
+            assert_close(first.delta_e(second), expected, 1e-4);
+            assert_close(second.delta_e(first), expected, 1e-4);
+        }
+    }
+
+    #[test]
+    fn delta_e_of_rgb() {
+        assert_eq!(rgb(10, 200, 30).delta_e(rgb(10, 200, 30)), 0.0);
+        assert_close(Rgb::BLACK.delta_e(Rgb::WHITE), 100.0, 1e-3);
+        assert!(rgb(200, 40, 60).delta_e(rgb(199, 43, 59)) < 2.0);
+    }
+
+    #[test]
+    fn contrast_ratio() {
+        assert_close(Rgb::BLACK.contrast_ratio(Rgb::WHITE), 21.0, 1e-9);
+        assert_close(Rgb::WHITE.contrast_ratio(Rgb::BLACK), 21.0, 1e-9);
+        assert_close(rgb(118, 118, 118).contrast_ratio(Rgb::WHITE), 4.54, 0.01);
+        assert_eq!(rgb(40, 90, 200).contrast_ratio(rgb(40, 90, 200)), 1.0);
+        assert_eq!(rgb(255, 255, 0).contrasting(), Rgb::BLACK);
+        assert_eq!(rgb(0, 0, 128).contrasting(), Rgb::WHITE);
+    }
+
+    #[test]
+    fn blend() {
+        let red = rgb(255, 0, 0);
+        let blue = rgb(0, 0, 255);
+
+        assert_eq!(red.blend(blue, 0.0), red);
+        assert_eq!(red.blend(blue, 1.0), blue);
+        assert_eq!(red.blend(blue, 0.5), rgb(128, 0, 128));
+        assert_eq!(Rgb::BLACK.blend(Rgb::WHITE, 0.25), rgb(64, 64, 64));
+        // Amounts are clamped
+        assert_eq!(red.blend(blue, 2.0), blue);
+        assert_eq!(red.blend(blue, -1.0), red);
+    }
+}
@@ -0,0 +1,443 @@
+use indexmap::IndexSet;
+use serde::{Deserialize, Serialize};
+
+use super::overlay::{Backstitch, Marker, MarkerKind, MarkerPosition, Overlay};
+use super::palette::PaletteEntryId;
+use super::{
+    CellGrid, CellThreads, GridCell, GridIntersection, GridSize, GridType, Rgb, Thread, ThreadType,
+};
+
+/// Bumped whenever the pattern file format changes in a way older readers can't handle.
+///
+/// Version 2 added quarter and three-quarter stitches, markers, backstitch lines and palette
+/// entries. Version 1 files are still read.
+pub const PATTERN_DOCUMENT_VERSION: u32 = 2;
+
+#[non_exhaustive]
+#[derive(thiserror::Error, Debug)]
+pub enum PatternDocumentError {
+    #[error("invalid pattern file: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("unsupported pattern file version {0}")]
+    UnsupportedVersion(u32),
+    #[error("palette index {0} does not exist")]
+    InvalidPaletteIndex(usize),
+    #[error("cell {0:?} is outside of the pattern")]
+    CellOutOfBounds(GridCell),
+    #[error("marker {0:?} is outside of the pattern")]
+    MarkerOutOfBounds(MarkerPosition),
+    #[error("backstitch {0} is outside of the pattern")]
+    BackstitchOutOfBounds(usize),
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PatternMetadata {
+    pub name: String,
+    pub author: String,
+    pub description: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct PatternDocumentThread {
+    pub thread_type: ThreadType,
+    // Index into `PatternDocument::palette`
+    pub color: usize,
+    // Only written for threads picked from a palette
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entry: Option<PaletteEntryId>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct PatternDocumentCell {
+    pub cell: GridCell,
+    pub threads: Vec<PatternDocumentThread>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct PatternDocumentMarker {
+    pub position: MarkerPosition,
+    pub kind: MarkerKind,
+    // Index into `PatternDocument::palette`
+    pub color: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct PatternDocumentBackstitch {
+    pub points: Vec<GridIntersection>,
+    // Index into `PatternDocument::palette`
+    pub color: usize,
+}
+
+/// The saved form of a pattern. Only cells with threads are stored, and thread colours refer to
+/// the palette so each colour is written once.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct PatternDocument {
+    pub version: u32,
+    #[serde(default)]
+    pub metadata: PatternMetadata,
+    pub size: GridSize,
+    pub palette: Vec<Rgb>,
+    pub cells: Vec<PatternDocumentCell>,
+    // Missing from files saved before markers existed
+    #[serde(default)]
+    pub markers: Vec<PatternDocumentMarker>,
+    // In drawing order. Missing from files saved before backstitch lines existed.
+    #[serde(default)]
+    pub backstitches: Vec<PatternDocumentBackstitch>,
+}
+
+// Read on its own first so files from a newer version fail with a useful error
+#[derive(Deserialize)]
+pub(crate) struct PatternDocumentVersion {
+    pub(crate) version: u32,
+}
+
+impl PatternDocument {
+    pub fn new(grid: &GridType, overlay: &Overlay, metadata: PatternMetadata) -> Self {
+        let mut palette: IndexSet<Rgb> = IndexSet::new();
+        let cells = grid
+            .iter()
+            .filter(|(_, threads)| !threads.is_empty())
+            .map(|(cell, threads)| PatternDocumentCell {
+                cell,
+                threads: threads
+                    .iter()
+                    .map(|thread| PatternDocumentThread {
+                        thread_type: thread.thread_type,
+                        color: palette.insert_full(thread.color).0,
+                        entry: thread.entry,
+                    })
+                    .collect(),
+            })
+            .collect();
+        let markers = overlay
+            .markers()
+            .map(|(position, marker)| PatternDocumentMarker {
+                position,
+                kind: marker.kind,
+                color: palette.insert_full(marker.color).0,
+            })
+            .collect();
+        let backstitches = overlay
+            .backstitches()
+            .map(|backstitch| PatternDocumentBackstitch {
+                points: backstitch.points.to_vec(),
+                color: palette.insert_full(backstitch.color).0,
+            })
+            .collect();
+
+        PatternDocument {
+            version: PATTERN_DOCUMENT_VERSION,
+            metadata,
+            size: grid.size(),
+            palette: palette.into_iter().collect(),
+            cells,
+            markers,
+            backstitches,
+        }
+    }
+
+    pub fn to_grid(&self) -> Result<GridType, PatternDocumentError> {
+        let mut grid = CellGrid::new(self.size);
+
+        for PatternDocumentCell { cell, threads } in self.cells.iter() {
+            if !self.size.contains(*cell) {
+                return Err(PatternDocumentError::CellOutOfBounds(*cell));
+            }
+
+            let threads = threads
+                .iter()
+                .map(|thread| {
+                    self.palette
+                        .get(thread.color)
+                        .map(|&color| Thread {
+                            color,
+                            thread_type: thread.thread_type,
+                            entry: thread.entry,
+                        })
+                        .ok_or(PatternDocumentError::InvalidPaletteIndex(thread.color))
+                })
+                .collect::<Result<CellThreads, _>>()?;
+
+            grid.set(*cell, threads);
+        }
+
+        Ok(grid)
+    }
+
+    pub fn to_overlay(&self) -> Result<Overlay, PatternDocumentError> {
+        let mut overlay: Overlay = self
+            .markers
+            .iter()
+            .map(|marker| {
+                if !marker.position.is_within(self.size) {
+                    return Err(PatternDocumentError::MarkerOutOfBounds(marker.position));
+                }
+
+                self.palette
+                    .get(marker.color)
+                    .map(|&color| {
+                        (
+                            marker.position,
+                            Marker {
+                                kind: marker.kind,
+                                color,
+                            },
+                        )
+                    })
+                    .ok_or(PatternDocumentError::InvalidPaletteIndex(marker.color))
+            })
+            .collect::<Result<_, _>>()?;
+
+        for (index, PatternDocumentBackstitch { points, color }) in
+            self.backstitches.iter().enumerate()
+        {
+            let color = *self
+                .palette
+                .get(*color)
+                .ok_or(PatternDocumentError::InvalidPaletteIndex(*color))?;
+            let backstitch = Backstitch {
+                points: points.iter().copied().collect(),
+                color,
+            };
+
+            if !backstitch.is_within(self.size) {
+                return Err(PatternDocumentError::BackstitchOutOfBounds(index));
+            }
+
+            overlay.insert_backstitch(index, backstitch);
+        }
+
+        Ok(overlay)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, PatternDocumentError> {
+        let PatternDocumentVersion { version } = serde_json::from_str(json)?;
+
+        if version > PATTERN_DOCUMENT_VERSION {
+            return Err(PatternDocumentError::UnsupportedVersion(version));
+        }
+
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn to_json(&self) -> Result<String, PatternDocumentError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// Serializes a grid and its overlay to the pattern file format.
+pub fn save_pattern(
+    grid: &GridType,
+    overlay: &Overlay,
+    metadata: PatternMetadata,
+) -> Result<String, PatternDocumentError> {
+    PatternDocument::new(grid, overlay, metadata).to_json()
+}
+
+/// Reads a pattern file back into a grid and overlay.
+pub fn load_pattern(
+    json: &str,
+) -> Result<(GridType, Overlay, PatternMetadata), PatternDocumentError> {
+    let document = PatternDocument::from_json(json)?;
+
+    Ok((
+        document.to_grid()?,
+        document.to_overlay()?,
+        document.metadata,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::pattern::palette::Palette;
+    use crate::components::pattern::test_fixtures::{grid_with, BLUE, RED};
+
+    fn document(size: GridSize) -> PatternDocument {
+        PatternDocument {
+            version: PATTERN_DOCUMENT_VERSION,
+            metadata: PatternMetadata::default(),
+            size,
+            palette: vec![RED],
+            cells: vec![],
+            markers: vec![],
+            backstitches: vec![],
+        }
+    }
+
+    #[test]
+    fn patterns_round_trip_through_json() {
+        let black = Palette::Dmc.find("310").unwrap();
+        let grid = grid_with(
+            GridSize(4, 3),
+            &[
+                (GridCell(0, 0), CellThreads::full_cross(RED)),
+                (
+                    GridCell(3, 2),
+                    CellThreads::single(ThreadType::QuarterTopLeft, BLUE).with_entry(Some(black)),
+                ),
+            ],
+        );
+        let mut overlay: Overlay = [(
+            MarkerPosition::Corner(GridIntersection(4, 3)),
+            Marker {
+                kind: MarkerKind::Bead,
+                color: BLUE,
+            },
+        )]
+        .into_iter()
+        .collect();
+        overlay.insert_backstitch(
+            0,
+            Backstitch {
+                points: [GridIntersection(0, 0), GridIntersection(4, 3)]
+                    .into_iter()
+                    .collect(),
+                color: RED,
+            },
+        );
+        let metadata = PatternMetadata {
+            name: "Rose".to_string(),
+            author: "Ann".to_string(),
+            description: "A small rose".to_string(),
+        };
+
+        let json = save_pattern(&grid, &overlay, metadata.clone()).unwrap();
+        let (loaded_grid, loaded_overlay, loaded_metadata) = load_pattern(&json).unwrap();
+
+        assert_eq!(loaded_grid, grid);
+        assert_eq!(loaded_overlay, overlay);
+        assert_eq!(loaded_metadata, metadata);
+        // Each colour is written to the palette once
+        assert_eq!(
+            PatternDocument::from_json(&json).unwrap().palette,
+            vec![RED, black.color(), BLUE]
+        );
+    }
+
+    #[test]
+    fn files_from_a_newer_version_are_refused() {
+        let mut newer = document(GridSize(1, 1));
+        newer.version = PATTERN_DOCUMENT_VERSION + 1;
+
+        let json = newer.to_json().unwrap();
+
+        assert!(matches!(
+            PatternDocument::from_json(&json),
+            Err(PatternDocumentError::UnsupportedVersion(version))
+                if version == PATTERN_DOCUMENT_VERSION + 1
+        ));
+        assert!(load_pattern(&json).is_err());
+    }
+
+    #[test]
+    fn version_1_files_still_load() {
+        let json = r##"{
+            "version": 1,
+            "metadata": { "name": "Heart" },
+            "size": [3, 2],
+            "palette": ["#ff0000", "#0000ff"],
+            "cells": [
+                {
+                    "cell": [0, 0],
+                    "threads": [
+                        { "thread_type": "SlashForwards", "color": 0 },
+                        { "thread_type": "SlashBackwards", "color": 0 }
+                    ]
+                },
+                {
+                    "cell": [2, 1],
+                    "threads": [{ "thread_type": "BorderTop", "color": 1 }]
+                }
+            ]
+        }"##;
+
+        let (grid, overlay, metadata) = load_pattern(json).unwrap();
+
+        assert_eq!(
+            grid,
+            grid_with(
+                GridSize(3, 2),
+                &[
+                    (GridCell(0, 0), CellThreads::full_cross(RED)),
+                    (
+                        GridCell(2, 1),
+                        CellThreads::single(ThreadType::BorderTop, BLUE)
+                    ),
+                ],
+            )
+        );
+        assert_eq!(overlay, Overlay::default());
+        assert_eq!(metadata.name, "Heart");
+    }
+
+    #[test]
+    fn cells_outside_the_pattern_are_refused() {
+        let mut document = document(GridSize(2, 2));
+        document.cells.push(PatternDocumentCell {
+            cell: GridCell(2, 0),
+            threads: vec![],
+        });
+
+        assert!(matches!(
+            document.to_grid(),
+            Err(PatternDocumentError::CellOutOfBounds(GridCell(2, 0)))
+        ));
+    }
+
+    #[test]
+    fn unknown_palette_indices_are_refused() {
+        let mut document = document(GridSize(2, 2));
+        document.cells.push(PatternDocumentCell {
+            cell: GridCell(0, 0),
+            threads: vec![PatternDocumentThread {
+                thread_type: ThreadType::SlashForwards,
+                color: 1,
+                entry: None,
+            }],
+        });
+
+        assert!(matches!(
+            document.to_grid(),
+            Err(PatternDocumentError::InvalidPaletteIndex(1))
+        ));
+    }
+
+    #[test]
+    fn markers_outside_the_pattern_are_refused() {
+        let position = MarkerPosition::Centre(GridCell(0, 2));
+        let mut document = document(GridSize(2, 2));
+        document.markers.push(PatternDocumentMarker {
+            position,
+            kind: MarkerKind::FrenchKnot,
+            color: 0,
+        });
+
+        assert!(matches!(
+            document.to_overlay(),
+            Err(PatternDocumentError::MarkerOutOfBounds(out_of_bounds)) if out_of_bounds == position
+        ));
+    }
+
+    #[test]
+    fn backstitches_outside_the_pattern_are_refused() {
+        let mut document = document(GridSize(2, 2));
+        document.backstitches = vec![
+            PatternDocumentBackstitch {
+                points: vec![GridIntersection(0, 0), GridIntersection(2, 2)],
+                color: 0,
+            },
+            PatternDocumentBackstitch {
+                points: vec![GridIntersection(0, 0), GridIntersection(3, 0)],
+                color: 0,
+            },
+        ];
+
+        assert!(matches!(
+            document.to_overlay(),
+            Err(PatternDocumentError::BackstitchOutOfBounds(1))
+        ));
+    }
+}
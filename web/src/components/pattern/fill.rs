@@ -0,0 +1,237 @@
+use serde::{Deserialize, Serialize};
+
+use super::{CellThreads, GridCell, GridSize, GridType, Rgb, ThreadType};
+
+/// Which neighbours of a cell count as touching it.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub enum Connectivity {
+    // Edge neighbours only
+    #[default]
+    Four,
+    // Edge and corner neighbours
+    Eight,
+}
+
+impl Connectivity {
+    fn offsets(self) -> &'static [(isize, isize)] {
+        match self {
+            Connectivity::Four => &[(0, -1), (1, 0), (0, 1), (-1, 0)],
+            Connectivity::Eight => &[
+                (0, -1),
+                (1, -1),
+                (1, 0),
+                (1, 1),
+                (0, 1),
+                (-1, 1),
+                (-1, 0),
+                (-1, -1),
+            ],
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct FillOptions {
+    pub connectivity: Connectivity,
+    // Largest difference of any colour channel for two threads to still match
+    pub tolerance: u8,
+    // Fill every matching cell in the grid rather than only the region touching the seed
+    pub global: bool,
+}
+
+/// The cells a bucket fill starting at `seed` covers: every cell that matches the seed's threads
+/// and is connected to it, or every matching cell when the fill is global.
+pub fn fill_region(grid: &GridType, seed: GridCell, options: FillOptions) -> Vec<GridCell> {
+    let size = grid.size();
+    let Some(seed_threads) = grid.get(seed) else {
+        return vec![];
+    };
+    let is_match = |threads: CellThreads| threads_match(seed_threads, threads, options.tolerance);
+
+    if options.global {
+        return grid
+            .iter()
+            .filter(|(_, threads)| is_match(*threads))
+            .map(|(grid_cell, _)| grid_cell)
+            .collect();
+    }
+
+    let GridSize(cols, _) = size;
+    let mut visited = vec![false; size.area()];
+    let mut region = vec![];
+    // Cells still to be visited. A stack rather than recursion so large regions can't overflow.
+    let mut stack = vec![seed];
+
+    visited[seed.1 * cols + seed.0] = true;
+
+    while let Some(grid_cell) = stack.pop() {
+        region.push(grid_cell);
+
+        for neighbour in neighbours(size, grid_cell, options.connectivity) {
+            let index = neighbour.1 * cols + neighbour.0;
+
+            if !visited[index] && grid.get(neighbour).is_some_and(is_match) {
+                visited[index] = true;
+                stack.push(neighbour);
+            }
+        }
+    }
+
+    region
+}
+
+fn neighbours(
+    size: GridSize,
+    GridCell(col, row): GridCell,
+    connectivity: Connectivity,
+) -> impl Iterator<Item = GridCell> {
+    connectivity
+        .offsets()
+        .iter()
+        .filter_map(move |&(col_offset, row_offset)| {
+            let grid_cell = GridCell(
+                col.checked_add_signed(col_offset)?,
+                row.checked_add_signed(row_offset)?,
+            );
+
+            size.contains(grid_cell).then_some(grid_cell)
+        })
+}
+
+// Cells match when they have threads of the same types, in colours within the tolerance
+fn threads_match(a: CellThreads, b: CellThreads, tolerance: u8) -> bool {
+    ThreadType::ALL.iter().all(
+        |&thread_type| match (a.get(thread_type), b.get(thread_type)) {
+            (Some(a), Some(b)) => colors_match(a, b, tolerance),
+            (None, None) => true,
+            _ => false,
+        },
+    )
+}
+
+fn colors_match(a: Rgb, b: Rgb, tolerance: u8) -> bool {
+    a.r.abs_diff(b.r) <= tolerance
+        && a.g.abs_diff(b.g) <= tolerance
+        && a.b.abs_diff(b.b) <= tolerance
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::pattern::test_fixtures::{full_crosses, sorted, RED};
+
+    // A red diagonal from the bottom left to the top right corner of a 3x3 grid
+    fn diagonal_wall() -> GridType {
+        full_crosses(
+            GridSize(3, 3),
+            &[GridCell(0, 2), GridCell(1, 1), GridCell(2, 0)],
+        )
+    }
+
+    #[test]
+    fn diagonal_gaps_only_stop_four_way_fills() {
+        let grid = diagonal_wall();
+        let four = fill_region(&grid, GridCell(0, 0), FillOptions::default());
+        let eight = fill_region(
+            &grid,
+            GridCell(0, 0),
+            FillOptions {
+                connectivity: Connectivity::Eight,
+                ..FillOptions::default()
+            },
+        );
+
+        assert_eq!(
+            sorted(four),
+            vec![GridCell(0, 0), GridCell(1, 0), GridCell(0, 1)]
+        );
+        assert_eq!(
+            sorted(eight),
+            vec![
+                GridCell(0, 0),
+                GridCell(1, 0),
+                GridCell(0, 1),
+                GridCell(2, 1),
+                GridCell(1, 2),
+                GridCell(2, 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn tolerance_is_inclusive_for_every_channel() {
+        let seed = CellThreads::full_cross(RED);
+        let close = CellThreads::full_cross(Rgb { r: 245, g: 0, b: 0 });
+        let bluer = CellThreads::full_cross(Rgb {
+            r: 255,
+            g: 0,
+            b: 11,
+        });
+
+        assert!(threads_match(seed, close, 10));
+        assert!(!threads_match(seed, close, 9));
+        assert!(threads_match(seed, bluer, 11));
+        assert!(!threads_match(seed, bluer, 10));
+
+        let black = CellThreads::full_cross(Rgb { r: 0, g: 0, b: 0 });
+        let white = CellThreads::full_cross(Rgb {
+            r: 255,
+            g: 255,
+            b: 255,
+        });
+
+        assert!(threads_match(black, white, u8::MAX));
+        assert!(!threads_match(black, white, u8::MAX - 1));
+        // No tolerance makes different stitches match
+        assert!(!threads_match(
+            seed,
+            CellThreads::single(ThreadType::SlashForwards, RED),
+            u8::MAX
+        ));
+    }
+
+    #[test]
+    fn global_fills_reach_disconnected_cells() {
+        let grid = full_crosses(GridSize(5, 5), &[GridCell(0, 0), GridCell(4, 4)]);
+        let local = fill_region(&grid, GridCell(0, 0), FillOptions::default());
+        let global = fill_region(
+            &grid,
+            GridCell(0, 0),
+            FillOptions {
+                global: true,
+                ..FillOptions::default()
+            },
+        );
+
+        assert_eq!(local, vec![GridCell(0, 0)]);
+        assert_eq!(global, vec![GridCell(0, 0), GridCell(4, 4)]);
+    }
+
+    #[test]
+    fn seeds_outside_the_grid_fill_nothing() {
+        let grid = diagonal_wall();
+
+        assert!(fill_region(&grid, GridCell(3, 0), FillOptions::default()).is_empty());
+        assert!(fill_region(
+            &grid,
+            GridCell(0, 3),
+            FillOptions {
+                global: true,
+                ..FillOptions::default()
+            }
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn large_uniform_grids_fill_completely() {
+        let grid = GridType::new(GridSize(200, 200));
+        let region = fill_region(&grid, GridCell(100, 100), FillOptions::default());
+
+        assert_eq!(region.len(), 200 * 200);
+        assert_eq!(
+            sorted(region),
+            grid.iter().map(|(cell, _)| cell).collect::<Vec<_>>()
+        );
+    }
+}
@@ -1,93 +1,125 @@
+use std::cell::RefCell;
+use std::f64::consts::TAU;
+use std::fmt;
+use std::rc::{Rc, Weak};
+
+use gloo::render::{request_animation_frame, AnimationFrame};
 use implicit_clone::sync::IArray;
-use wasm_bindgen::prelude::Closure;
+use indexmap::IndexSet;
 use wasm_bindgen::{JsCast, JsValue};
-use web_sys::window;
 use web_sys::{CanvasRenderingContext2d, DomRect, HtmlCanvasElement};
 use yew::prelude::*;
 
-use super::{GridCell, GridType, Rgb, Thread, ThreadType};
+use super::overlay::{Backstitch, Marker, MarkerKind, MarkerPosition, Overlay, OverlayCells};
+use super::symmetry::{GuideLine, Symmetry};
+use super::{
+    CellThreads, Corner, GridCell, GridIntersection, GridPoint, GridSize, GridType, Thread,
+    ThreadType,
+};
 use crate::components::canvas::{Canvas, WithRender};
 
+mod background;
+mod export;
+
+const SELECTION_FILL: &str = "rgba(26, 115, 232, 0.15)";
+const SELECTION_OUTLINE: &str = "#1a73e8";
+const SELECTION_OUTLINE_WIDTH: f64 = 2.0;
+const SYMMETRY_GUIDE: &str = "rgba(219, 68, 55, 0.8)";
+const SYMMETRY_GUIDE_WIDTH: f64 = 1.5;
+
+pub use background::GridBackground;
+pub use export::{render_png, ImageExportOptions};
+
+/// A mouse event on the grid, with the point under the pointer and the modifier keys held.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GridMouseEvent {
+    pub point: GridPoint,
+    pub shift_key: bool,
+    // Cmd counts as Ctrl on macOS
+    pub ctrl_key: bool,
+}
+
+impl GridMouseEvent {
+    fn new(point: GridPoint, event: &MouseEvent) -> Self {
+        GridMouseEvent {
+            point,
+            shift_key: event.shift_key(),
+            ctrl_key: event.ctrl_key() || event.meta_key(),
+        }
+    }
+}
+
 #[derive(Properties, PartialEq)]
 pub struct PatternGridProps {
     #[prop_or_default]
-    pub onrender: Callback<()>,
+    pub onmousedown: Callback<GridMouseEvent>,
     #[prop_or_default]
-    pub onmousedown: Callback<GridCell>,
+    pub onmousemove: Callback<GridMouseEvent>,
     #[prop_or_default]
-    pub onmousemove: Callback<GridCell>,
+    pub selected_cells: IArray<GridCell>,
+    pub grid: GridType,
+    // Backstitch lines, knots and beads drawn above the threads
     #[prop_or_default]
-    pub overwrite: GridType,
+    pub overlay: Overlay,
+    // Index of the backstitch line in `overlay` to highlight
     #[prop_or_default]
-    pub selected_cells: IArray<GridCell>,
-    pub cols: usize,
-    pub rows: usize,
-    pub default_color: Rgb,
-    pub clear: bool,
+    pub selected_backstitch: Option<usize>,
+    #[prop_or(3.0)]
+    pub backstitch_width: f64,
+    // Its axes are drawn over the pattern as guides
+    #[prop_or_default]
+    pub symmetry: Symmetry,
+    #[prop_or_default]
+    pub background: GridBackground,
+    #[prop_or(20.0)]
+    pub cell_width: f64,
+    #[prop_or(20.0)]
+    pub cell_height: f64,
 }
 
 #[function_component(PatternGrid)]
 pub fn pattern_grid(props: &PatternGridProps) -> Html {
     let PatternGridProps {
-        cols,
-        onrender,
         onmousedown,
         onmousemove,
-        overwrite,
-        rows,
+        grid,
+        overlay,
         selected_cells,
-        default_color,
-        clear,
+        selected_backstitch,
+        backstitch_width,
+        symmetry,
+        background,
+        cell_width,
+        cell_height,
     } = props;
-    // let mut is_first_render_ref = use_mut_ref(|| true);
-    // let offscreen_canvas_ref = use_state(|| create_offscreen_canvas());
-
-    // let grid: Rc<IMap<(usize, usize), IArray<Thread>>> = use_memo(
-    //     (thread_cells.clone(), overwrite.clone()),
-    //     |(thread_cells, overwrite)| {
-    //         let mut grid: HashMap<(usize, usize), IArray<Thread>> = HashMap::default();
-    //         for row in 0..*rows {
-    //             for col in 0..*cols {
-    //                 if let Some((k, v)) = overwrite.iter().find(|(k, _)| k.0 == col && k.1 == row) {
-    //                     grid.insert(k, v);
-    //                 } else if let Some((k, v)) =
-    //                     thread_cells.iter().find(|(k, _)| k.0 == col && k.1 == row)
-    //                 {
-    //                     grid.insert(k, v);
-    //                 }
-    //             }
-    //         }
-
-    //         grid.iter()
-    //             .map(|(&k, v)| (k, v.clone()))
-    //             .collect::<IMap<(usize, usize), IArray<Thread>>>()
-    //     },
-    // );
-    // {
-    //     let onload = onload.clone();
-
-    //     use_effect_with((), move |_| {
-    //         onload.emit(());
-    //     })
-    // };
-    let cell_width = 20.0;
-    let cell_height = 20.0;
+    let GridSize(cols, rows) = grid.size();
+    let frame_queue = use_memo((), |_| FrameQueue::default());
+    let grid_ctx = GridContext::new(
+        *cell_width,
+        *cell_height,
+        1.0,
+        *backstitch_width,
+        *background,
+    );
     let render = use_memo(
         (
-            overwrite.clone(),
+            grid.clone(),
+            overlay.clone(),
             selected_cells.clone(),
-            *clear,
-            onrender.clone(),
+            *selected_backstitch,
+            *symmetry,
+            grid_ctx,
         ),
-        |(overwrite, selected_cells, clear, onrender)| Render {
-            redraw: (*clear, onrender.clone()),
-            size: GridCell(*cols, *rows),
-            default_color: *default_color,
-            thread_cells: overwrite.clone(),
-            selected_cells: selected_cells.clone(),
-            cell_width,
-            cell_height,
-            border_width: 1.0,
+        |(grid, overlay, selected_cells, selected_backstitch, symmetry, grid_ctx)| Render {
+            frame: Frame {
+                grid: grid.clone(),
+                overlay: overlay.clone(),
+                selected_cells: selected_cells.clone(),
+                selected_backstitch: *selected_backstitch,
+                symmetry: *symmetry,
+                grid_ctx: *grid_ctx,
+            },
+            frame_queue: (*frame_queue).clone(),
         },
     );
     let node_ref = NodeRef::default();
@@ -99,7 +131,10 @@ pub fn pattern_grid(props: &PatternGridProps) -> Html {
                 let client_y = event.client_y();
                 let rect = canvas.get_bounding_client_rect();
 
-                onmousedown.emit(get_thread_cell((client_x, client_y), rect, render));
+                onmousedown.emit(GridMouseEvent::new(
+                    get_thread_cell((client_x, client_y), rect, render),
+                    &event,
+                ));
             }
         },
     );
@@ -111,48 +146,19 @@ pub fn pattern_grid(props: &PatternGridProps) -> Html {
                 let client_y = event.client_y();
                 let rect = canvas.get_bounding_client_rect();
 
-                onmousemove.emit(get_thread_cell((client_x, client_y), rect, render));
+                onmousemove.emit(GridMouseEvent::new(
+                    get_thread_cell((client_x, client_y), rect, render),
+                    &event,
+                ));
             }
         },
     );
 
-    // let message_count = use_mut_ref(|| 0);
-    // let message_count = use_mut_ref(|| 0);
-
-    // info!("isfirstrender: {:?}", *is_first_render_handle);
-    // let meh = is_first_render_ref::clone();
-    // let is_first_render_ref = use_mut_ref(|| true);
-    // if (*is_first_render_ref).borrow_mut() == true {
-    //     *is_first_render_ref.borrow_mut() = false;
-    // };
-    // let is_first_render_ref = use_mut_ref(|| true);
-    // let is_first_render_ref = use_mut_ref(|| Rc::new(RefCell::new(true)));
-    // if *is_first_render_ref.borrow() {
-    //     is_first_render_ref.borrow_mut().replace(false);
-    // }
-    // if *message_count.borrow_mut() > 3 {
-    //     window.alert_with_message("Message limit reached");
-    // } else {
-    //     *message_count.borrow_mut() += 1;
-    //     window.alert_with_message("Message sent");
-    // }
-    // {
-    //     let is_first_render_handle = is_first_render_handle;
-    //     use_effect(move || if is_first_render_handle.set(false));
-    //     // use_effect(move || {
-    //     //     // Make a call to DOM API after component is rendered
-    //     //     gloo_utils::document().set_title(&format!("You clicked {} times", *counter));
-
-    //     //     // Perform the cleanup
-    //     //     || gloo_utils::document().set_title("You clicked 0 times")
-    //     // });
-    // };
-
     html! {<>
     <Canvas<CanvasRenderingContext2d, Render>
         style=""
-        width={rows * cell_width as usize}
-        height={cols * cell_height as usize}
+        width={(cols as f64 * cell_width) as usize}
+        height={(rows as f64 * cell_height) as usize}
         node_ref={node_ref}
         render={Box::new((*render).clone())}
         onmousedown={handle_mousedown}
@@ -163,128 +169,89 @@ pub fn pattern_grid(props: &PatternGridProps) -> Html {
     </>}
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 struct GridContext {
     cell_width: f64,
     cell_height: f64,
     border_width: f64,
+    backstitch_width: f64,
+    background: GridBackground,
 }
 
 impl GridContext {
-    pub fn new(cell_width: f64, cell_height: f64, border_width: f64) -> Self {
+    pub fn new(
+        cell_width: f64,
+        cell_height: f64,
+        border_width: f64,
+        backstitch_width: f64,
+        background: GridBackground,
+    ) -> Self {
         GridContext {
             cell_width,
             cell_height,
             border_width,
+            backstitch_width,
+            background,
         }
     }
-}
 
-fn draw_grid(
-    GridCell(cols, rows): GridCell,
-    color: Rgb,
-    ctx: &CanvasRenderingContext2d,
-    grid_ctx: &GridContext,
-) {
-    let grid_width = cols as f64 * grid_ctx.cell_width;
-    let grid_height = rows as f64 * grid_ctx.cell_height;
-    ctx.clear_rect(0.0, 0.0, grid_width, grid_height);
-    ctx.set_stroke_style(&JsValue::from_str(&color.as_hex()));
-
-    for col in 0..cols {
-        let cell_x = col as f64 * grid_ctx.cell_width;
-        let cell_y = 0 as f64;
-        ctx.set_line_width(grid_ctx.border_width);
-        ctx.begin_path();
-        ctx.move_to(cell_x, cell_y);
-        ctx.line_to(grid_width, (cols - col) as f64 * grid_ctx.cell_height);
-        ctx.stroke();
-        ctx.fill();
-
-        let cell_x = (cols - col) as f64 * grid_ctx.cell_width;
-        let cell_y = grid_height;
-        ctx.set_line_width(grid_ctx.border_width);
-        ctx.begin_path();
-        ctx.move_to(cell_x, cell_y);
-        ctx.line_to(grid_width, (cols - col) as f64 * grid_ctx.cell_height);
-        ctx.stroke();
-        ctx.fill();
-    }
-
-    for row in 1..(rows + 1) {
-        let cell_x = 0 as f64;
-        let cell_y = row as f64 * grid_ctx.cell_width;
-        ctx.set_line_width(grid_ctx.border_width);
-        ctx.begin_path();
-        ctx.move_to(cell_x, cell_y);
-        ctx.line_to((rows - row) as f64 * grid_ctx.cell_width, grid_width);
-        ctx.stroke();
-        ctx.fill();
-
-        let cell_x = 0_f64;
-        let cell_y = row as f64 * grid_ctx.cell_height;
-        ctx.set_line_width(grid_ctx.border_width);
-        ctx.begin_path();
-        ctx.move_to(cell_x, cell_y);
-        ctx.line_to(row as f64 * grid_ctx.cell_width, 0 as f64);
-        ctx.stroke();
-        ctx.fill();
+    fn cell_size(&self) -> (f64, f64) {
+        (self.cell_width, self.cell_height)
     }
 }
 
 fn draw_cell(
     grid_ctx: &GridContext,
     ctx: &CanvasRenderingContext2d,
-    (GridCell(col_index, row_index), threads): (GridCell, IArray<Thread>),
-    selected_cells: &IArray<GridCell>,
+    size: GridSize,
+    (grid_cell, threads): (GridCell, CellThreads),
+    is_selected: bool,
 ) {
-    let is_selected = selected_cells
-        .iter()
-        .any(|t| t.0 == col_index && t.1 == row_index);
+    let GridCell(col_index, row_index) = grid_cell;
     let col_index = col_index as f64;
     let row_index = row_index as f64;
     let cell_x = col_index * grid_ctx.cell_width;
     let cell_y = row_index * grid_ctx.cell_height;
 
-    ctx.set_line_width(grid_ctx.border_width);
     ctx.clear_rect(cell_x, cell_y, grid_ctx.cell_width, grid_ctx.cell_height);
+    grid_ctx
+        .background
+        .draw_cell(ctx, size, grid_cell, grid_ctx.cell_size());
+    ctx.set_line_width(grid_ctx.border_width);
+
+    if is_selected {
+        ctx.set_fill_style(&JsValue::from_str(SELECTION_FILL));
+        ctx.fill_rect(cell_x, cell_y, grid_ctx.cell_width, grid_ctx.cell_height);
+    }
 
     for thread in threads.iter() {
         match thread {
             Thread {
                 thread_type: ThreadType::BorderTop,
                 color,
+                ..
             } => {
-                // let hex_color = if is_selected {
-                //     &JsValue::from_str("#000000")
-                // } else {
                 let hex_color = &JsValue::from_str(&color.as_hex());
-                // };
                 ctx.set_fill_style(hex_color);
                 ctx.fill_rect(cell_x, cell_y, grid_ctx.cell_width, grid_ctx.border_width);
             }
             Thread {
                 thread_type: ThreadType::BorderRight,
                 color,
+                ..
             } => {
-                // let hex_color = if is_selected {
-                //     &JsValue::from_str("#000000")
-                // } else {
                 let hex_color = &JsValue::from_str(&color.as_hex());
-                // };
                 let x = cell_x + grid_ctx.cell_width - grid_ctx.border_width;
                 let y = cell_y;
                 ctx.set_fill_style(hex_color);
-                ctx.fill_rect(x, y, grid_ctx.border_width, grid_ctx.cell_width);
+                ctx.fill_rect(x, y, grid_ctx.border_width, grid_ctx.cell_height);
             }
             Thread {
                 thread_type: ThreadType::BorderBottom,
                 color,
+                ..
             } => {
-                // let hex_color = if is_selected {
-                //     &JsValue::from_str("#000000")
-                // } else {
                 let hex_color = &JsValue::from_str(&color.as_hex());
-                // };
                 let x = cell_x;
                 let y = cell_y + grid_ctx.cell_height - grid_ctx.border_width;
                 ctx.set_fill_style(hex_color);
@@ -293,24 +260,18 @@ fn draw_cell(
             Thread {
                 thread_type: ThreadType::BorderLeft,
                 color,
+                ..
             } => {
-                // let hex_color = if is_selected {
-                //     &JsValue::from_str("#000000")
-                // } else {
                 let hex_color = &JsValue::from_str(&color.as_hex());
-                // };
                 ctx.set_fill_style(hex_color);
-                ctx.fill_rect(cell_x, cell_y, grid_ctx.border_width, grid_ctx.cell_width);
+                ctx.fill_rect(cell_x, cell_y, grid_ctx.border_width, grid_ctx.cell_height);
             }
             Thread {
                 thread_type: ThreadType::SlashForwards,
                 color,
+                ..
             } => {
-                // let hex_color = if is_selected {
-                //     &JsValue::from_str("#000000")
-                // } else {
                 let hex_color = &JsValue::from_str(&color.as_hex());
-                // };
                 ctx.set_stroke_style(hex_color);
                 ctx.begin_path();
                 ctx.move_to(
@@ -324,12 +285,9 @@ fn draw_cell(
             Thread {
                 thread_type: ThreadType::SlashBackwards,
                 color,
+                ..
             } => {
-                // let hex_color = if is_selected {
-                //     &JsValue::from_str("#000000")
-                // } else {
                 let hex_color = &JsValue::from_str(&color.as_hex());
-                // };
                 ctx.set_stroke_style(hex_color);
                 ctx.begin_path();
                 ctx.move_to(cell_x, cell_y);
@@ -337,25 +295,489 @@ fn draw_cell(
                 ctx.stroke();
                 ctx.fill();
             }
+            Thread {
+                thread_type:
+                    ThreadType::QuarterTopLeft
+                    | ThreadType::QuarterTopRight
+                    | ThreadType::QuarterBottomRight
+                    | ThreadType::QuarterBottomLeft,
+                color,
+                ..
+            } => {
+                let hex_color = &JsValue::from_str(&color.as_hex());
+                let corner = thread.thread_type.corner().unwrap_or(Corner::TopLeft);
+                let (x, y) = corner_position(grid_ctx, (cell_x, cell_y), corner);
+
+                ctx.set_stroke_style(hex_color);
+                ctx.begin_path();
+                ctx.move_to(x, y);
+                ctx.line_to(
+                    cell_x + grid_ctx.cell_width / 2.0,
+                    cell_y + grid_ctx.cell_height / 2.0,
+                );
+                ctx.stroke();
+            }
+            Thread {
+                thread_type:
+                    ThreadType::ThreeQuarterTopLeft
+                    | ThreadType::ThreeQuarterTopRight
+                    | ThreadType::ThreeQuarterBottomRight
+                    | ThreadType::ThreeQuarterBottomLeft,
+                color,
+                ..
+            } => {
+                let hex_color = &JsValue::from_str(&color.as_hex());
+                let corner = thread.thread_type.corner().unwrap_or(Corner::TopLeft);
+                let (x, y) = corner_position(grid_ctx, (cell_x, cell_y), corner);
+                // The full diagonal runs between the two corners next to the quarter's corner
+                let (start_x, start_y) =
+                    corner_position(grid_ctx, (cell_x, cell_y), corner.clockwise());
+                let (end_x, end_y) =
+                    corner_position(grid_ctx, (cell_x, cell_y), corner.anticlockwise());
+
+                ctx.set_stroke_style(hex_color);
+                ctx.begin_path();
+                ctx.move_to(start_x, start_y);
+                ctx.line_to(end_x, end_y);
+                ctx.move_to(x, y);
+                ctx.line_to(
+                    cell_x + grid_ctx.cell_width / 2.0,
+                    cell_y + grid_ctx.cell_height / 2.0,
+                );
+                ctx.stroke();
+            }
+        }
+    }
+}
+
+// Drawn inside the cell along each edge that borders an unselected cell, so the selection gets a
+// single outline around it however many cells it has
+fn draw_selection_outline(
+    grid_ctx: &GridContext,
+    ctx: &CanvasRenderingContext2d,
+    GridCell(col_index, row_index): GridCell,
+    selected_cells: &IndexSet<GridCell>,
+) {
+    let cell_x = col_index as f64 * grid_ctx.cell_width;
+    let cell_y = row_index as f64 * grid_ctx.cell_height;
+    let is_selected = |col: Option<usize>, row: Option<usize>| match (col, row) {
+        (Some(col), Some(row)) => selected_cells.contains(&GridCell(col, row)),
+        _ => false,
+    };
+    let (width, height) = grid_ctx.cell_size();
+
+    ctx.set_fill_style(&JsValue::from_str(SELECTION_OUTLINE));
+
+    if !is_selected(Some(col_index), row_index.checked_sub(1)) {
+        ctx.fill_rect(cell_x, cell_y, width, SELECTION_OUTLINE_WIDTH);
+    }
+    if !is_selected(col_index.checked_add(1), Some(row_index)) {
+        ctx.fill_rect(
+            cell_x + width - SELECTION_OUTLINE_WIDTH,
+            cell_y,
+            SELECTION_OUTLINE_WIDTH,
+            height,
+        );
+    }
+    if !is_selected(Some(col_index), row_index.checked_add(1)) {
+        ctx.fill_rect(
+            cell_x,
+            cell_y + height - SELECTION_OUTLINE_WIDTH,
+            width,
+            SELECTION_OUTLINE_WIDTH,
+        );
+    }
+    if !is_selected(col_index.checked_sub(1), Some(row_index)) {
+        ctx.fill_rect(cell_x, cell_y, SELECTION_OUTLINE_WIDTH, height);
+    }
+}
+
+// The cell and the cells sharing an edge with it
+fn with_neighbours(grid_cell: GridCell, size: GridSize) -> impl Iterator<Item = GridCell> {
+    let GridCell(col, row) = grid_cell;
+
+    [
+        Some(grid_cell),
+        row.checked_sub(1).map(|row| GridCell(col, row)),
+        Some(GridCell(col + 1, row)),
+        Some(GridCell(col, row + 1)),
+        col.checked_sub(1).map(|col| GridCell(col, row)),
+    ]
+    .into_iter()
+    .flatten()
+    .filter(move |&grid_cell| size.contains(grid_cell))
+}
+
+fn draw_backstitch(
+    grid_ctx: &GridContext,
+    ctx: &CanvasRenderingContext2d,
+    backstitch: &Backstitch,
+    is_selected: bool,
+) {
+    let mut points = backstitch.points.iter().map(|GridIntersection(col, row)| {
+        (
+            col as f64 * grid_ctx.cell_width,
+            row as f64 * grid_ctx.cell_height,
+        )
+    });
+
+    let Some((x, y)) = points.next() else {
+        return;
+    };
+
+    ctx.begin_path();
+    ctx.move_to(x, y);
+    // A single point is drawn as a dot by the round line cap
+    ctx.line_to(x, y);
+    for (x, y) in points {
+        ctx.line_to(x, y);
+    }
+
+    ctx.set_line_cap("round");
+    ctx.set_line_join("round");
+
+    // Selected lines get a dark outline
+    if is_selected {
+        ctx.set_stroke_style(&JsValue::from_str("#000000"));
+        ctx.set_line_width(grid_ctx.backstitch_width + 4.0);
+        ctx.stroke();
+    }
+
+    ctx.set_stroke_style(&JsValue::from_str(&backstitch.color.as_hex()));
+    ctx.set_line_width(grid_ctx.backstitch_width);
+    ctx.stroke();
+
+    ctx.set_line_cap("butt");
+    ctx.set_line_join("miter");
+    ctx.set_line_width(grid_ctx.border_width);
+}
+
+// Markers are kept within half a cell of their position, so they never reach past the cells
+// around it
+fn draw_marker(
+    grid_ctx: &GridContext,
+    ctx: &CanvasRenderingContext2d,
+    position: MarkerPosition,
+    Marker { kind, color }: Marker,
+) {
+    let (x, y) = match position {
+        MarkerPosition::Centre(GridCell(col_index, row_index)) => (
+            (col_index as f64 + 0.5) * grid_ctx.cell_width,
+            (row_index as f64 + 0.5) * grid_ctx.cell_height,
+        ),
+        MarkerPosition::Corner(GridIntersection(col_index, row_index)) => (
+            col_index as f64 * grid_ctx.cell_width,
+            row_index as f64 * grid_ctx.cell_height,
+        ),
+    };
+    let cell_length = grid_ctx.cell_width.min(grid_ctx.cell_height);
+    let hex_color = &JsValue::from_str(&color.as_hex());
+
+    ctx.begin_path();
+
+    match kind {
+        MarkerKind::FrenchKnot => {
+            let _ = ctx.arc(x, y, cell_length * 0.2, 0.0, TAU);
+            ctx.set_fill_style(hex_color);
+            ctx.fill();
+        }
+        // A ring, so beads can be told apart from knots of the same colour
+        MarkerKind::Bead => {
+            let _ = ctx.arc(x, y, cell_length * 0.25, 0.0, TAU);
+            ctx.set_fill_style(&JsValue::from_str("#ffffff"));
+            ctx.fill();
+            ctx.set_line_width(cell_length * 0.12);
+            ctx.set_stroke_style(hex_color);
+            ctx.stroke();
+            ctx.set_line_width(grid_ctx.border_width);
+        }
+    }
+}
+
+// Lines are in cells, as given by `Symmetry::axes`
+fn draw_symmetry_axes(grid_ctx: &GridContext, ctx: &CanvasRenderingContext2d, axes: &[GuideLine]) {
+    if axes.is_empty() {
+        return;
+    }
+
+    ctx.begin_path();
+
+    for &((start_x, start_y), (end_x, end_y)) in axes {
+        ctx.move_to(
+            start_x * grid_ctx.cell_width,
+            start_y * grid_ctx.cell_height,
+        );
+        ctx.line_to(end_x * grid_ctx.cell_width, end_y * grid_ctx.cell_height);
+    }
+
+    ctx.set_line_width(SYMMETRY_GUIDE_WIDTH);
+    ctx.set_stroke_style(&JsValue::from_str(SYMMETRY_GUIDE));
+    ctx.stroke();
+    ctx.set_line_width(grid_ctx.border_width);
+}
+
+fn corner_position(
+    grid_ctx: &GridContext,
+    (cell_x, cell_y): (f64, f64),
+    corner: Corner,
+) -> (f64, f64) {
+    match corner {
+        Corner::TopLeft => (cell_x, cell_y),
+        Corner::TopRight => (cell_x + grid_ctx.cell_width, cell_y),
+        Corner::BottomRight => (cell_x + grid_ctx.cell_width, cell_y + grid_ctx.cell_height),
+        Corner::BottomLeft => (cell_x, cell_y + grid_ctx.cell_height),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Frame {
+    grid: GridType,
+    overlay: Overlay,
+    selected_cells: IArray<GridCell>,
+    selected_backstitch: Option<usize>,
+    symmetry: Symmetry,
+    grid_ctx: GridContext,
+}
+
+/// The part of the canvas a frame has to paint over the one before it.
+#[derive(Debug, PartialEq)]
+enum Repaint {
+    Nothing,
+    Everything,
+    Cells(IndexSet<GridCell>),
+}
+
+impl Frame {
+    fn repaint(&self, painted: Option<&Frame>) -> Repaint {
+        let size = self.grid.size();
+
+        match painted {
+            // Only repaint the cells that changed since the last frame
+            Some(painted)
+                if painted.grid.size() == size
+                    && painted.grid_ctx == self.grid_ctx
+                    // The guides cross the whole grid, so moving them repaints everything
+                    && painted.symmetry == self.symmetry =>
+            {
+                let selected_cells: IndexSet<GridCell> = self.selected_cells.iter().collect();
+                let painted_selected_cells: IndexSet<GridCell> =
+                    painted.selected_cells.iter().collect();
+                // The outline around a cell depends on whether the cells next to it are selected
+                let selected_cell_changes: Vec<GridCell> = selected_cells
+                    .symmetric_difference(&painted_selected_cells)
+                    .flat_map(|&grid_cell| with_neighbours(grid_cell, size))
+                    .collect();
+                // A line that's been selected or deselected is drawn differently
+                let selected_backstitch_changes: Vec<GridCell> =
+                    if painted.selected_backstitch == self.selected_backstitch {
+                        vec![]
+                    } else {
+                        [
+                            painted
+                                .selected_backstitch
+                                .and_then(|index| painted.overlay.backstitch(index)),
+                            self.selected_backstitch
+                                .and_then(|index| self.overlay.backstitch(index)),
+                        ]
+                        .into_iter()
+                        .flatten()
+                        .flat_map(|backstitch| backstitch.cells(size))
+                        .collect()
+                    };
+                // Overlay items spill over onto the cells around them, so those are repainted
+                // too
+                let dirty_cells: IndexSet<GridCell> = self
+                    .grid
+                    .diff(&painted.grid)
+                    .into_iter()
+                    .chain(
+                        self.overlay
+                            .diff(&painted.overlay)
+                            .into_iter()
+                            .flat_map(|position| position.cells(size)),
+                    )
+                    .chain(
+                        self.overlay
+                            .backstitch_diff(&painted.overlay)
+                            .into_iter()
+                            .flat_map(|backstitch| backstitch.cells(size)),
+                    )
+                    .chain(selected_backstitch_changes)
+                    .chain(selected_cell_changes)
+                    .collect();
+
+                if dirty_cells.is_empty() {
+                    Repaint::Nothing
+                } else {
+                    Repaint::Cells(dirty_cells)
+                }
+            }
+            _ => Repaint::Everything,
         }
     }
+
+    fn paint(&self, ctx: &CanvasRenderingContext2d, painted: Option<&Frame>) {
+        let grid_ctx = &self.grid_ctx;
+        let size = self.grid.size();
+        let selected_cells: IndexSet<GridCell> = self.selected_cells.iter().collect();
+
+        match self.repaint(painted) {
+            Repaint::Nothing => {}
+            Repaint::Cells(dirty_cells) => {
+                let axes = self.symmetry.axes(size);
+                let overlay_cells = OverlayCells::new(&self.overlay, size);
+
+                // Each cell is clipped, so the overlay items over it can be drawn again without
+                // drawing over the cells around it a second time
+                for &grid_cell in dirty_cells.iter() {
+                    let GridCell(col_index, row_index) = grid_cell;
+                    let threads = self.grid.get(grid_cell).unwrap_or_default();
+
+                    ctx.save();
+                    ctx.begin_path();
+                    ctx.rect(
+                        col_index as f64 * grid_ctx.cell_width,
+                        row_index as f64 * grid_ctx.cell_height,
+                        grid_ctx.cell_width,
+                        grid_ctx.cell_height,
+                    );
+                    ctx.clip();
+
+                    draw_cell(
+                        grid_ctx,
+                        ctx,
+                        size,
+                        (grid_cell, threads),
+                        selected_cells.contains(&grid_cell),
+                    );
+
+                    for &index in overlay_cells.backstitches(grid_cell) {
+                        if let Some(backstitch) = self.overlay.backstitch(index) {
+                            let is_selected = self.selected_backstitch == Some(index);
+                            draw_backstitch(grid_ctx, ctx, &backstitch, is_selected);
+                        }
+                    }
+
+                    for &(position, marker) in overlay_cells.markers(grid_cell) {
+                        draw_marker(grid_ctx, ctx, position, marker);
+                    }
+
+                    if selected_cells.contains(&grid_cell) {
+                        draw_selection_outline(grid_ctx, ctx, grid_cell, &selected_cells);
+                    }
+
+                    draw_symmetry_axes(grid_ctx, ctx, &axes);
+                    ctx.restore();
+                }
+            }
+            Repaint::Everything => {
+                grid_ctx.background.draw(ctx, size, grid_ctx.cell_size());
+
+                for (grid_cell, threads) in self.grid.iter() {
+                    let is_selected = selected_cells.contains(&grid_cell);
+
+                    if is_selected || !threads.is_empty() {
+                        draw_cell(grid_ctx, ctx, size, (grid_cell, threads), is_selected);
+                    }
+                }
+
+                for (index, backstitch) in self.overlay.backstitches().enumerate() {
+                    let is_selected = self.selected_backstitch == Some(index);
+                    draw_backstitch(grid_ctx, ctx, &backstitch, is_selected);
+                }
+
+                for (position, marker) in self.overlay.markers() {
+                    draw_marker(grid_ctx, ctx, position, marker);
+                }
+
+                for &grid_cell in selected_cells.iter() {
+                    draw_selection_outline(grid_ctx, ctx, grid_cell, &selected_cells);
+                }
+
+                draw_symmetry_axes(grid_ctx, ctx, &self.symmetry.axes(size));
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct FrameQueueState {
+    // The latest frame that hasn't been painted yet. Frames that arrive before the next
+    // animation frame replace it, so several prop updates are painted at once.
+    pending: Option<Frame>,
+    painted: Option<Frame>,
+    painted_canvas_size: (u32, u32),
+    animation_frame: Option<AnimationFrame>,
+}
+
+impl FrameQueueState {
+    // The frame that's still on a canvas of this size, if any
+    fn painted_on(&self, canvas_size: (u32, u32)) -> Option<&Frame> {
+        self.painted
+            .as_ref()
+            .filter(|_| canvas_size == self.painted_canvas_size)
+    }
+}
+
+// Lives for as long as the PatternGrid it belongs to and keeps track of what's on the canvas
+#[derive(Clone, Default)]
+struct FrameQueue(Rc<RefCell<FrameQueueState>>);
+
+impl FrameQueue {
+    fn push(&self, frame: Frame, ctx: CanvasRenderingContext2d) {
+        let mut state = self.0.borrow_mut();
+        let is_scheduled = state.pending.is_some();
+
+        state.pending = Some(frame);
+
+        if !is_scheduled {
+            let queue = Rc::downgrade(&self.0);
+
+            // Replacing the handle drops the previous animation frame, which has already run
+            state.animation_frame = Some(request_animation_frame(move |_| {
+                FrameQueue::paint_pending(queue, &ctx);
+            }));
+        }
+    }
+
+    fn paint_pending(queue: Weak<RefCell<FrameQueueState>>, ctx: &CanvasRenderingContext2d) {
+        if let Some(queue) = queue.upgrade() {
+            let mut state = queue.borrow_mut();
+
+            if let Some(frame) = state.pending.take() {
+                // Resizing a canvas wipes it, so anything painted before is gone
+                let canvas_size = ctx
+                    .canvas()
+                    .map(|canvas| (canvas.width(), canvas.height()))
+                    .unwrap_or_default();
+                frame.paint(ctx, state.painted_on(canvas_size));
+                state.painted = Some(frame);
+                state.painted_canvas_size = canvas_size;
+            }
+        }
+    }
+}
+
+impl PartialEq for FrameQueue {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl fmt::Debug for FrameQueue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FrameQueue").finish_non_exhaustive()
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
 struct Render {
-    redraw: (bool, Callback<()>),
-    size: GridCell,
-    default_color: Rgb,
-    thread_cells: GridType,
-    selected_cells: IArray<GridCell>,
-    cell_width: f64,
-    cell_height: f64,
-    border_width: f64,
+    frame: Frame,
+    frame_queue: FrameQueue,
 }
 
 impl WithRender for Render {
     fn render(self, canvas: &HtmlCanvasElement) {
-        let grid_ctx = GridContext::new(self.cell_width, self.cell_height, self.border_width);
         let ctx: CanvasRenderingContext2d = canvas
             .get_context("2d")
             .unwrap()
@@ -363,34 +785,131 @@ impl WithRender for Render {
             .dyn_into()
             .unwrap();
 
-        let closure = Closure::wrap(Box::new(move || {
-            if self.redraw.0 {
-                draw_grid(self.size, self.default_color, &ctx, &grid_ctx);
-            }
+        self.frame_queue.push(self.frame, ctx);
+    }
+}
 
-            for thread_cell in self.thread_cells.iter() {
-                draw_cell(&grid_ctx, &ctx, thread_cell, &self.selected_cells);
-            }
-        }) as Box<dyn FnMut()>);
+fn get_thread_cell((client_x, client_y): (i32, i32), rect: DomRect, render: &Render) -> GridPoint {
+    let relative_x = (client_x as f64) - rect.x();
+    let relative_y = (client_y as f64) - rect.y();
 
-        window()
-            .unwrap()
-            .request_animation_frame(closure.as_ref().unchecked_ref())
-            .expect("not able to get raf");
-        closure.forget(); // Prevents the closure from being cleaned up
+    let col = relative_x / render.frame.grid_ctx.cell_width;
+    let row = relative_y / render.frame.grid_ctx.cell_height;
 
-        if self.redraw.0 {
-            self.redraw.1.emit(());
-        }
+    GridPoint {
+        grid_cell: GridCell(col.floor() as usize, row.floor() as usize),
+        // Lets callers tell which border or quadrant was clicked
+        offset: (col.fract(), row.fract()),
     }
 }
 
-fn get_thread_cell((client_x, client_y): (i32, i32), rect: DomRect, render: &Render) -> GridCell {
-    let relative_x = (client_x as f64) - rect.x();
-    let relative_y = (client_y as f64) - rect.y();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::pattern::symmetry::SymmetryMode;
+    use crate::components::pattern::test_fixtures::{full_crosses, sorted, RED};
+
+    const SIZE: GridSize = GridSize(5, 5);
 
-    let col_index = (relative_x / render.cell_width).floor() as usize;
-    let row_index = (relative_y / render.cell_height).floor() as usize;
+    fn frame(grid: GridType) -> Frame {
+        Frame {
+            grid,
+            overlay: Overlay::default(),
+            selected_cells: IArray::default(),
+            selected_backstitch: None,
+            symmetry: Symmetry::default(),
+            grid_ctx: GridContext::new(10.0, 10.0, 1.0, 2.0, GridBackground::default()),
+        }
+    }
+
+    fn knot() -> Marker {
+        Marker {
+            kind: MarkerKind::FrenchKnot,
+            color: RED,
+        }
+    }
+
+    #[test]
+    fn first_frame_paints_everything() {
+        let next = frame(full_crosses(SIZE, &[GridCell(1, 1)]));
+
+        assert_eq!(next.repaint(None), Repaint::Everything);
+    }
+
+    #[test]
+    fn unchanged_frames_paint_nothing() {
+        let painted = frame(full_crosses(SIZE, &[GridCell(1, 1)]));
+
+        assert_eq!(painted.clone().repaint(Some(&painted)), Repaint::Nothing);
+    }
+
+    #[test]
+    fn changes_since_the_painted_frame_are_merged_into_one_set_of_cells() {
+        let painted = frame(full_crosses(SIZE, &[GridCell(1, 1)]));
+        let mut next = frame(full_crosses(SIZE, &[GridCell(2, 3), GridCell(4, 0)]));
+        next.overlay
+            .set_marker(MarkerPosition::Centre(GridCell(2, 3)), Some(knot()));
+        next.overlay
+            .set_marker(MarkerPosition::Centre(GridCell(0, 4)), Some(knot()));
+
+        let Repaint::Cells(cells) = next.repaint(Some(&painted)) else {
+            panic!("expected only some cells to be repainted");
+        };
 
-    GridCell(col_index, row_index)
+        // The cell with both a new cross and a new knot is only painted once
+        assert_eq!(
+            sorted(cells.into_iter().collect()),
+            [
+                GridCell(4, 0),
+                GridCell(1, 1),
+                GridCell(2, 3),
+                GridCell(0, 4)
+            ]
+        );
+    }
+
+    #[test]
+    fn selecting_a_cell_repaints_its_neighbours() {
+        let painted = frame(full_crosses(SIZE, &[]));
+        let mut next = painted.clone();
+        next.selected_cells = IArray::from(vec![GridCell(0, 0)]);
+
+        assert_eq!(
+            next.repaint(Some(&painted)),
+            Repaint::Cells(IndexSet::from([
+                GridCell(0, 0),
+                GridCell(1, 0),
+                GridCell(0, 1)
+            ]))
+        );
+    }
+
+    #[test]
+    fn a_full_repaint_takes_priority_over_changed_cells() {
+        let painted = frame(full_crosses(SIZE, &[GridCell(1, 1)]));
+
+        let mut mirrored = frame(full_crosses(SIZE, &[GridCell(2, 2)]));
+        mirrored.symmetry.mode = SymmetryMode::Vertical;
+        assert_eq!(mirrored.repaint(Some(&painted)), Repaint::Everything);
+
+        let mut zoomed = frame(full_crosses(SIZE, &[GridCell(2, 2)]));
+        zoomed.grid_ctx.cell_width = 20.0;
+        zoomed.grid_ctx.cell_height = 20.0;
+        assert_eq!(zoomed.repaint(Some(&painted)), Repaint::Everything);
+
+        let resized = frame(full_crosses(GridSize(6, 5), &[GridCell(2, 2)]));
+        assert_eq!(resized.repaint(Some(&painted)), Repaint::Everything);
+    }
+
+    #[test]
+    fn a_resized_canvas_has_nothing_painted_on_it() {
+        let state = FrameQueueState {
+            painted: Some(frame(full_crosses(SIZE, &[GridCell(1, 1)]))),
+            painted_canvas_size: (50, 50),
+            ..FrameQueueState::default()
+        };
+
+        assert!(state.painted_on((50, 50)).is_some());
+        assert!(state.painted_on((100, 50)).is_none());
+    }
 }
@@ -0,0 +1,198 @@
+use wasm_bindgen::JsValue;
+use web_sys::CanvasRenderingContext2d;
+
+use crate::components::pattern::{GridCell, GridSize, Rgb};
+
+/// The cell dividers drawn behind the threads.
+///
+/// Every cell owns the lines on its top and left edges, and the last column and row also own
+/// their right and bottom edges. That way a single cell can be cleared and redrawn without
+/// touching its neighbours.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GridBackground {
+    pub line_color: Rgb,
+    pub line_width: f64,
+    pub major_line_color: Rgb,
+    pub major_line_width: f64,
+    // A heavier line is drawn every `major_line_interval` cells. 0 disables them.
+    pub major_line_interval: usize,
+}
+
+impl Default for GridBackground {
+    fn default() -> Self {
+        GridBackground {
+            line_color: Rgb {
+                r: 204,
+                g: 204,
+                b: 204,
+            },
+            line_width: 1.0,
+            major_line_color: Rgb {
+                r: 102,
+                g: 102,
+                b: 102,
+            },
+            major_line_width: 2.0,
+            major_line_interval: 10,
+        }
+    }
+}
+
+// A line along a column (vertical) or row (horizontal) boundary
+#[derive(Clone, Copy)]
+enum Line {
+    Column(usize),
+    Row(usize),
+}
+
+impl GridBackground {
+    // When major lines are enabled the outer edges of the grid are major lines too
+    fn is_major_line(&self, line_index: usize, line_count: usize) -> bool {
+        self.major_line_interval > 0
+            && (line_index == line_count || line_index.is_multiple_of(self.major_line_interval))
+    }
+
+    fn line_style(&self, line_index: usize, line_count: usize) -> (Rgb, f64) {
+        if self.is_major_line(line_index, line_count) {
+            (self.major_line_color, self.major_line_width)
+        } else {
+            (self.line_color, self.line_width)
+        }
+    }
+
+    // Offset of a line on the canvas. The closing line is drawn inside the last cell so that it
+    // stays on the canvas.
+    fn line_offset(line_index: usize, line_count: usize, cell_length: f64, width: f64) -> f64 {
+        if line_index == line_count {
+            line_index as f64 * cell_length - width
+        } else {
+            line_index as f64 * cell_length
+        }
+    }
+
+    // Draws a line, clipped to `(start, length)` along its own direction
+    fn draw_line(
+        &self,
+        ctx: &CanvasRenderingContext2d,
+        GridSize(cols, rows): GridSize,
+        line: Line,
+        (start, length): (f64, f64),
+        (cell_width, cell_height): (f64, f64),
+    ) {
+        match line {
+            Line::Column(col_line) => {
+                let (color, width) = self.line_style(col_line, cols);
+                let x = GridBackground::line_offset(col_line, cols, cell_width, width);
+
+                ctx.set_fill_style(&JsValue::from_str(&color.as_hex()));
+                ctx.fill_rect(x, start, width, length);
+            }
+            Line::Row(row_line) => {
+                let (color, height) = self.line_style(row_line, rows);
+                let y = GridBackground::line_offset(row_line, rows, cell_height, height);
+
+                ctx.set_fill_style(&JsValue::from_str(&color.as_hex()));
+                ctx.fill_rect(start, y, length, height);
+            }
+        }
+    }
+
+    fn is_major(&self, GridSize(cols, rows): GridSize, line: Line) -> bool {
+        match line {
+            Line::Column(col_line) => self.is_major_line(col_line, cols),
+            Line::Row(row_line) => self.is_major_line(row_line, rows),
+        }
+    }
+
+    pub fn draw(&self, ctx: &CanvasRenderingContext2d, size: GridSize, cell_size: (f64, f64)) {
+        let GridSize(cols, rows) = size;
+        let grid_width = cols as f64 * cell_size.0;
+        let grid_height = rows as f64 * cell_size.1;
+
+        ctx.clear_rect(0.0, 0.0, grid_width, grid_height);
+
+        if size.area() == 0 {
+            return;
+        }
+
+        let lines = (0..=cols)
+            .map(|col| (Line::Column(col), (0.0, grid_height)))
+            .chain((0..=rows).map(|row| (Line::Row(row), (0.0, grid_width))));
+
+        // Major lines go last so they aren't drawn over by the minor ones that cross them
+        let (major_lines, minor_lines): (Vec<_>, Vec<_>) =
+            lines.partition(|(line, _)| self.is_major(size, *line));
+
+        for (line, extent) in minor_lines.into_iter().chain(major_lines) {
+            self.draw_line(ctx, size, line, extent, cell_size);
+        }
+    }
+
+    /// Redraws the lines owned by a single cell. The cell is expected to have been cleared.
+    pub fn draw_cell(
+        &self,
+        ctx: &CanvasRenderingContext2d,
+        size: GridSize,
+        GridCell(col_index, row_index): GridCell,
+        cell_size: (f64, f64),
+    ) {
+        let GridSize(cols, rows) = size;
+        let cell_x = col_index as f64 * cell_size.0;
+        let cell_y = row_index as f64 * cell_size.1;
+        let mut lines = vec![
+            (Line::Column(col_index), (cell_y, cell_size.1)),
+            (Line::Row(row_index), (cell_x, cell_size.0)),
+        ];
+
+        if col_index + 1 == cols {
+            lines.push((Line::Column(cols), (cell_y, cell_size.1)));
+        }
+        if row_index + 1 == rows {
+            lines.push((Line::Row(rows), (cell_x, cell_size.0)));
+        }
+
+        let (major_lines, minor_lines): (Vec<_>, Vec<_>) = lines
+            .into_iter()
+            .partition(|(line, _)| self.is_major(size, *line));
+
+        for (line, extent) in minor_lines.into_iter().chain(major_lines) {
+            self.draw_line(ctx, size, line, extent, cell_size);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn major_lines_fall_on_multiples_of_the_interval_and_the_last_line() {
+        let background = GridBackground {
+            major_line_interval: 5,
+            ..GridBackground::default()
+        };
+        let major_lines: Vec<usize> = (0..=12)
+            .filter(|&line_index| background.is_major_line(line_index, 12))
+            .collect();
+
+        assert_eq!(major_lines, vec![0, 5, 10, 12]);
+        assert_eq!(
+            background.line_style(10, 12),
+            (background.major_line_color, background.major_line_width)
+        );
+        assert_eq!(
+            background.line_style(11, 12),
+            (background.line_color, background.line_width)
+        );
+    }
+
+    #[test]
+    fn an_interval_of_zero_draws_no_major_lines() {
+        let background = GridBackground {
+            major_line_interval: 0,
+            ..GridBackground::default()
+        };
+
+        assert!((0..=12).all(|line_index| !background.is_major_line(line_index, 12)));
+    }
+}
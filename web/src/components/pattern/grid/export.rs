@@ -0,0 +1,320 @@
+use gloo::file::Blob;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::js_sys::Function;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
+
+use super::{draw_backstitch, draw_cell, draw_marker, GridBackground, GridContext};
+use crate::components::pattern::overlay::Overlay;
+use crate::components::pattern::{GridSize, GridType};
+
+// The cell size the editor draws at, which line widths are scaled from
+const SCREEN_CELL_SIZE: f64 = 20.0;
+// Rows and columns are numbered along every major grid line
+const NUMBERING_INTERVAL: usize = 10;
+// Browsers refuse to draw on larger canvases
+const MAX_IMAGE_LENGTH: u32 = 16384;
+const NUMBERING_COLOR: &str = "#000000";
+const BACKGROUND_COLOR: &str = "#ffffff";
+
+#[non_exhaustive]
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum ImageExportError {
+    #[error("the pattern has no cells to export")]
+    EmptyPattern,
+    #[error("a {width}x{height} image is too large, the cell size has to be smaller")]
+    TooLarge { width: u32, height: u32 },
+    #[error("the cell size has to be at least one pixel")]
+    InvalidCellSize,
+    #[error("unable to draw the image")]
+    Canvas,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ImageExportOptions {
+    // Width and height of a cell in pixels
+    pub cell_size: f64,
+    pub grid_lines: bool,
+    pub numbering: bool,
+    // At the editor's cell size, scaled along with the cells
+    pub backstitch_width: f64,
+}
+
+impl Default for ImageExportOptions {
+    fn default() -> Self {
+        ImageExportOptions {
+            cell_size: SCREEN_CELL_SIZE,
+            grid_lines: true,
+            numbering: true,
+            backstitch_width: 3.0,
+        }
+    }
+}
+
+// Where everything goes on the image, worked out before any drawing so it can be tested
+#[derive(Clone, Debug, PartialEq)]
+struct ImageLayout {
+    width: u32,
+    height: u32,
+    // Space around the grid for the numbers. Every side gets it so the last number isn't cut off.
+    margin: f64,
+    font_size: f64,
+    // Offsets of the numbered lines from the grid's left and top edges
+    column_numbers: Vec<(f64, usize)>,
+    row_numbers: Vec<(f64, usize)>,
+}
+
+impl ImageLayout {
+    fn new(
+        GridSize(cols, rows): GridSize,
+        options: ImageExportOptions,
+    ) -> Result<Self, ImageExportError> {
+        let ImageExportOptions {
+            cell_size,
+            numbering,
+            ..
+        } = options;
+
+        if cols == 0 || rows == 0 {
+            return Err(ImageExportError::EmptyPattern);
+        }
+        if cell_size.is_nan() || cell_size < 1.0 {
+            return Err(ImageExportError::InvalidCellSize);
+        }
+
+        let numbered_lines = |count: usize| -> Vec<(f64, usize)> {
+            if numbering {
+                (NUMBERING_INTERVAL..=count)
+                    .step_by(NUMBERING_INTERVAL)
+                    .map(|line| (line as f64 * cell_size, line))
+                    .collect()
+            } else {
+                vec![]
+            }
+        };
+        let column_numbers = numbered_lines(cols);
+        let row_numbers = numbered_lines(rows);
+        let font_size = (cell_size * 0.6).max(8.0).round();
+        let margin = if numbering {
+            // Wide enough for the longest number at roughly 0.6em a digit, plus some padding
+            let digits = cols.max(rows).to_string().len() as f64;
+            (digits * font_size * 0.6 + font_size).ceil()
+        } else {
+            0.0
+        };
+        let width = (cols as f64 * cell_size + margin * 2.0).ceil();
+        let height = (rows as f64 * cell_size + margin * 2.0).ceil();
+
+        if width > MAX_IMAGE_LENGTH as f64 || height > MAX_IMAGE_LENGTH as f64 {
+            return Err(ImageExportError::TooLarge {
+                width: width as u32,
+                height: height as u32,
+            });
+        }
+
+        Ok(ImageLayout {
+            width: width as u32,
+            height: height as u32,
+            margin,
+            font_size,
+            column_numbers,
+            row_numbers,
+        })
+    }
+}
+
+// The editor's look with its line widths grown or shrunk along with the cells
+fn export_context(options: ImageExportOptions) -> GridContext {
+    let scale = options.cell_size / SCREEN_CELL_SIZE;
+    let default = GridBackground::default();
+    let background = if options.grid_lines {
+        GridBackground {
+            line_width: (default.line_width * scale).max(1.0),
+            major_line_width: (default.major_line_width * scale).max(1.0),
+            ..default
+        }
+    } else {
+        GridBackground {
+            line_width: 0.0,
+            major_line_width: 0.0,
+            major_line_interval: 0,
+            ..default
+        }
+    };
+
+    GridContext::new(
+        options.cell_size,
+        options.cell_size,
+        scale.max(1.0),
+        options.backstitch_width * scale,
+        background,
+    )
+}
+
+/// Draws the pattern onto a canvas of its own, so the image doesn't depend on how large the
+/// editor shows it, and hands the PNG to `on_render` once the browser has encoded it.
+pub fn render_png(
+    grid: &GridType,
+    overlay: &Overlay,
+    options: ImageExportOptions,
+    on_render: impl FnOnce(Option<Blob>) + 'static,
+) -> Result<(), ImageExportError> {
+    let size = grid.size();
+    let layout = ImageLayout::new(size, options)?;
+    let canvas = gloo::utils::document()
+        .create_element("canvas")
+        .ok()
+        .and_then(|element| element.dyn_into::<HtmlCanvasElement>().ok())
+        .ok_or(ImageExportError::Canvas)?;
+
+    canvas.set_width(layout.width);
+    canvas.set_height(layout.height);
+
+    let ctx = canvas
+        .get_context("2d")
+        .ok()
+        .flatten()
+        .and_then(|ctx| ctx.dyn_into::<CanvasRenderingContext2d>().ok())
+        .ok_or(ImageExportError::Canvas)?;
+
+    draw_pattern(&ctx, grid, overlay, options, &layout);
+
+    let callback = Closure::once_into_js(move |blob: JsValue| {
+        on_render(blob.dyn_into::<web_sys::Blob>().ok().map(Blob::from));
+    });
+
+    canvas
+        .to_blob_with_type(callback.unchecked_ref::<Function>(), "image/png")
+        .map_err(|_| ImageExportError::Canvas)
+}
+
+fn draw_pattern(
+    ctx: &CanvasRenderingContext2d,
+    grid: &GridType,
+    overlay: &Overlay,
+    options: ImageExportOptions,
+    layout: &ImageLayout,
+) {
+    let grid_ctx = export_context(options);
+    let size = grid.size();
+
+    let _ = ctx.translate(layout.margin, layout.margin);
+    grid_ctx.background.draw(ctx, size, grid_ctx.cell_size());
+
+    for (grid_cell, threads) in grid.iter() {
+        if !threads.is_empty() {
+            draw_cell(&grid_ctx, ctx, size, (grid_cell, threads), false);
+        }
+    }
+
+    for backstitch in overlay.backstitches() {
+        draw_backstitch(&grid_ctx, ctx, &backstitch, false);
+    }
+
+    for (position, marker) in overlay.markers() {
+        draw_marker(&grid_ctx, ctx, position, marker);
+    }
+
+    draw_numbering(ctx, layout);
+    let _ = ctx.set_transform(1.0, 0.0, 0.0, 1.0, 0.0, 0.0);
+
+    // Cells are cleared before they're drawn, so the white goes in behind everything at the end
+    let _ = ctx.set_global_composite_operation("destination-over");
+    ctx.set_fill_style(&JsValue::from_str(BACKGROUND_COLOR));
+    ctx.fill_rect(0.0, 0.0, layout.width as f64, layout.height as f64);
+    let _ = ctx.set_global_composite_operation("source-over");
+}
+
+// Expects the origin to be at the grid's top left corner
+fn draw_numbering(ctx: &CanvasRenderingContext2d, layout: &ImageLayout) {
+    let gap = layout.font_size / 2.0;
+
+    ctx.set_font(&format!("{}px sans-serif", layout.font_size));
+    ctx.set_fill_style(&JsValue::from_str(NUMBERING_COLOR));
+
+    ctx.set_text_align("center");
+    ctx.set_text_baseline("bottom");
+    for (x, number) in &layout.column_numbers {
+        let _ = ctx.fill_text(&number.to_string(), *x, -gap);
+    }
+
+    ctx.set_text_align("right");
+    ctx.set_text_baseline("middle");
+    for (y, number) in &layout.row_numbers {
+        let _ = ctx.fill_text(&number.to_string(), -gap, *y);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn image_size_follows_the_cell_size_not_the_editor() {
+        let options = ImageExportOptions {
+            cell_size: 8.0,
+            numbering: false,
+            ..ImageExportOptions::default()
+        };
+        let layout = ImageLayout::new(GridSize(30, 12), options).unwrap();
+
+        assert_eq!((layout.width, layout.height), (240, 96));
+        assert_eq!(layout.margin, 0.0);
+        assert!(layout.column_numbers.is_empty());
+        assert!(layout.row_numbers.is_empty());
+    }
+
+    #[test]
+    fn numbers_go_on_every_tenth_line_inside_a_margin() {
+        let layout = ImageLayout::new(GridSize(25, 120), ImageExportOptions::default()).unwrap();
+
+        assert_eq!(layout.column_numbers, vec![(200.0, 10), (400.0, 20)]);
+        assert_eq!(layout.row_numbers.len(), 12);
+        assert_eq!(layout.row_numbers.last(), Some(&(2400.0, 120)));
+        // Three digits at 12px
+        assert_eq!(layout.margin, 34.0);
+        assert_eq!((layout.width, layout.height), (25 * 20 + 68, 120 * 20 + 68));
+    }
+
+    #[test]
+    fn rejects_images_that_cannot_be_drawn() {
+        let options = ImageExportOptions::default();
+
+        assert_eq!(
+            ImageLayout::new(GridSize(0, 10), options),
+            Err(ImageExportError::EmptyPattern)
+        );
+        assert_eq!(
+            ImageLayout::new(
+                GridSize(10, 10),
+                ImageExportOptions {
+                    cell_size: 0.5,
+                    ..options
+                }
+            ),
+            Err(ImageExportError::InvalidCellSize)
+        );
+        assert!(matches!(
+            ImageLayout::new(GridSize(1000, 10), options),
+            Err(ImageExportError::TooLarge { .. })
+        ));
+    }
+
+    #[test]
+    fn line_widths_scale_with_the_cells() {
+        let large = export_context(ImageExportOptions {
+            cell_size: 40.0,
+            ..ImageExportOptions::default()
+        });
+        let plain = export_context(ImageExportOptions {
+            grid_lines: false,
+            ..ImageExportOptions::default()
+        });
+
+        assert_eq!(large.backstitch_width, 6.0);
+        assert_eq!(large.border_width, 2.0);
+        assert_eq!(large.background.major_line_width, 4.0);
+        assert_eq!(plain.background.line_width, 0.0);
+        assert_eq!(plain.background.major_line_interval, 0);
+    }
+}
@@ -0,0 +1,634 @@
+use implicit_clone::ImplicitClone;
+use indexmap::IndexSet;
+
+use super::color::Lab;
+use super::palette::{Palette, PaletteEntryId};
+use super::{CellGrid, CellThreads, GridCell, GridSize, GridType, Rgb};
+
+// Rounds of k-means refinement at most. It usually settles well before this.
+const KMEANS_ITERATIONS: usize = 16;
+
+#[non_exhaustive]
+#[derive(thiserror::Error, Debug)]
+pub enum ImageImportError {
+    #[error("the image is empty")]
+    EmptyImage,
+    #[error("expected {expected} bytes of image data, got {actual}")]
+    InvalidData { expected: usize, actual: usize },
+    #[error("the pattern size is empty")]
+    EmptySize,
+}
+
+/// A decoded image, with four bytes per pixel in RGBA order, as a canvas' `ImageData` holds them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RgbaImage {
+    pub width: usize,
+    pub height: usize,
+    pub data: Vec<u8>,
+}
+
+impl RgbaImage {
+    // The colour and opacity of a pixel
+    fn pixel(&self, x: usize, y: usize) -> ([f64; 3], f64) {
+        let offset = (y * self.width + x) * 4;
+        let pixel = &self.data[offset..offset + 4];
+
+        (
+            [pixel[0] as f64, pixel[1] as f64, pixel[2] as f64],
+            pixel[3] as f64 / 255.0,
+        )
+    }
+}
+
+/// How the image's colours are reduced to the few a pattern uses.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ImplicitClone)]
+pub enum Quantizer {
+    // Splits the colours into boxes around the median of their widest channel. Fast and even.
+    #[default]
+    MedianCut,
+    // Refines the median cut colours towards the centres of the clusters they stand for. Slower,
+    // but closer to the colours that matter in photos.
+    KMeans,
+}
+
+impl Quantizer {
+    pub const ALL: [Quantizer; 2] = [Quantizer::MedianCut, Quantizer::KMeans];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Quantizer::MedianCut => "Median cut",
+            Quantizer::KMeans => "K-means",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ImplicitClone)]
+pub struct ImageImportOptions {
+    pub size: GridSize,
+    // The most thread colours the pattern ends up with
+    pub colors: usize,
+    pub quantizer: Quantizer,
+    // Swaps each colour for the closest floss in the palette
+    pub palette: Option<Palette>,
+    // Spreads the difference between each cell and its thread over the cells next to it, which
+    // keeps gradients smooth with few colours
+    pub dither: bool,
+}
+
+/// Turns an image into a pattern of full cross stitches. The image is scaled to the pattern size
+/// by averaging the pixels each cell covers, and mostly transparent cells are left empty.
+pub fn import_image(
+    image: &RgbaImage,
+    options: ImageImportOptions,
+) -> Result<GridType, ImageImportError> {
+    let expected = image.width * image.height * 4;
+
+    if image.width == 0 || image.height == 0 {
+        return Err(ImageImportError::EmptyImage);
+    }
+    if image.data.len() != expected {
+        return Err(ImageImportError::InvalidData {
+            expected,
+            actual: image.data.len(),
+        });
+    }
+    if options.size.area() == 0 {
+        return Err(ImageImportError::EmptySize);
+    }
+
+    let cells = resample(image, options.size);
+    let colors: Vec<Rgb> = cells.iter().flatten().map(|&color| to_rgb(color)).collect();
+    let quantized = match options.quantizer {
+        Quantizer::MedianCut => median_cut(&colors, options.colors.max(1)),
+        Quantizer::KMeans => kmeans(&colors, options.colors.max(1)),
+    };
+    let threads: Vec<(Rgb, Option<PaletteEntryId>)> = match options.palette {
+        Some(palette) => quantized
+            .into_iter()
+            .map(|color| palette.nearest(color))
+            .collect::<IndexSet<_>>()
+            .into_iter()
+            .map(|entry| (entry.color(), Some(entry)))
+            .collect(),
+        None => quantized.into_iter().map(|color| (color, None)).collect(),
+    };
+    let colors: Vec<Rgb> = threads.iter().map(|&(color, _)| color).collect();
+    let cols = options.size.0;
+    let mut grid = CellGrid::new(options.size);
+
+    for (index, thread) in assign_threads(cells, cols, &colors, options.dither)
+        .into_iter()
+        .enumerate()
+    {
+        if let Some(thread) = thread {
+            let (color, entry) = threads[thread];
+
+            grid.set(
+                GridCell(index % cols, index / cols),
+                CellThreads::full_cross(color).with_entry(entry),
+            );
+        }
+    }
+
+    Ok(grid)
+}
+
+// The index of the colour each cell is stitched with, in the same row-major order as the cells.
+// Dithering is Floyd-Steinberg, spreading the difference only into cells that get a thread.
+fn assign_threads(
+    mut cells: Vec<Option<[f64; 3]>>,
+    cols: usize,
+    colors: &[Rgb],
+    dither: bool,
+) -> Vec<Option<usize>> {
+    let labs: Vec<Lab> = colors.iter().map(|&color| Lab::from(color)).collect();
+    let rows = cells.len() / cols.max(1);
+    let mut assigned = vec![None; cells.len()];
+
+    for row in 0..rows {
+        for col in 0..cols {
+            let Some(color) = cells[row * cols + col] else {
+                continue;
+            };
+            let index = nearest_index(&labs, Lab::from(to_rgb(color)));
+
+            assigned[row * cols + col] = Some(index);
+
+            if !dither {
+                continue;
+            }
+
+            let thread = colors[index];
+            let error = [
+                color[0] - thread.r as f64,
+                color[1] - thread.g as f64,
+                color[2] - thread.b as f64,
+            ];
+
+            for (col_offset, row_offset, weight) in
+                [(1, 0, 7.0), (-1, 1, 3.0), (0, 1, 5.0), (1, 1, 1.0)]
+            {
+                let (Some(col), Some(row)) = (
+                    col.checked_add_signed(col_offset),
+                    row.checked_add_signed(row_offset),
+                ) else {
+                    continue;
+                };
+
+                if col < cols && row < rows {
+                    if let Some(neighbour) = &mut cells[row * cols + col] {
+                        for channel in 0..3 {
+                            neighbour[channel] += error[channel] * weight / 16.0;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    assigned
+}
+
+// The average colour of the pixels under each cell, in row-major order, weighted by how much of
+// each pixel the cell covers and how opaque it is. Cells that are mostly transparent are `None`.
+fn resample(image: &RgbaImage, size: GridSize) -> Vec<Option<[f64; 3]>> {
+    let GridSize(cols, rows) = size;
+    let x_scale = image.width as f64 / cols as f64;
+    let y_scale = image.height as f64 / rows as f64;
+    // The pixels a span of the cell covers, and by how much
+    let coverage = |start: f64, end: f64, len: usize| {
+        let first = start.floor() as usize;
+        let last = (end.ceil() as usize).min(len);
+
+        (first..last).map(move |pixel| {
+            let covered = end.min(pixel as f64 + 1.0) - start.max(pixel as f64);
+            (pixel, covered)
+        })
+    };
+
+    (0..rows)
+        .flat_map(|row| (0..cols).map(move |col| (col, row)))
+        .map(|(col, row)| {
+            let mut sum = [0.0; 3];
+            let mut opacity = 0.0;
+            let mut area = 0.0;
+
+            for (y, y_weight) in coverage(
+                row as f64 * y_scale,
+                (row + 1) as f64 * y_scale,
+                image.height,
+            ) {
+                for (x, x_weight) in coverage(
+                    col as f64 * x_scale,
+                    (col + 1) as f64 * x_scale,
+                    image.width,
+                ) {
+                    let (color, alpha) = image.pixel(x, y);
+                    let weight = x_weight * y_weight;
+
+                    for channel in 0..3 {
+                        sum[channel] += color[channel] * alpha * weight;
+                    }
+                    opacity += alpha * weight;
+                    area += weight;
+                }
+            }
+
+            (opacity >= area / 2.0).then(|| sum.map(|channel| channel / opacity))
+        })
+        .collect()
+}
+
+fn to_rgb(color: [f64; 3]) -> Rgb {
+    let [r, g, b] = color.map(|channel| channel.clamp(0.0, 255.0).round() as u8);
+
+    Rgb { r, g, b }
+}
+
+fn nearest_index(labs: &[Lab], target: Lab) -> usize {
+    labs.iter()
+        .map(|lab| lab_distance(*lab, target))
+        .enumerate()
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(index, _)| index)
+        .unwrap_or_default()
+}
+
+// Squared CIE76 distance. Much cheaper than CIEDE2000, which matters when every cell is compared
+// with every colour over and over.
+fn lab_distance(a: Lab, b: Lab) -> f64 {
+    (a.l - b.l).powi(2) + (a.a - b.a).powi(2) + (a.b - b.b).powi(2)
+}
+
+fn channel(color: Rgb, channel: usize) -> u8 {
+    match channel {
+        0 => color.r,
+        1 => color.g,
+        _ => color.b,
+    }
+}
+
+// The channel with the widest spread of values, and how wide it is
+fn widest_channel(colors: &[Rgb]) -> (usize, u8) {
+    (0..3)
+        .map(|index| {
+            let values = colors.iter().map(|&color| channel(color, index));
+            let min = values.clone().min().unwrap_or_default();
+            let max = values.max().unwrap_or_default();
+            (index, max - min)
+        })
+        .max_by_key(|&(_, range)| range)
+        .unwrap_or_default()
+}
+
+fn mean(colors: &[Rgb]) -> Rgb {
+    let count = colors.len().max(1) as f64;
+    let sum = colors.iter().fold([0.0; 3], |sum, color| {
+        [
+            sum[0] + color.r as f64,
+            sum[1] + color.g as f64,
+            sum[2] + color.b as f64,
+        ]
+    });
+
+    to_rgb(sum.map(|channel| channel / count))
+}
+
+// Up to `count` colours standing for `colors`, found by splitting the widest box of colours at
+// its median until there are enough boxes
+fn median_cut(colors: &[Rgb], count: usize) -> Vec<Rgb> {
+    if colors.is_empty() {
+        return vec![];
+    }
+
+    let mut boxes = vec![colors.to_vec()];
+
+    while boxes.len() < count {
+        let widest = boxes
+            .iter()
+            .enumerate()
+            .map(|(index, colors)| (index, widest_channel(colors)))
+            .filter(|&(_, (_, range))| range > 0)
+            .max_by_key(|&(_, (_, range))| range);
+        let Some((index, (widest_channel, _))) = widest else {
+            // Every box is a single colour
+            break;
+        };
+        let mut lower = boxes.swap_remove(index);
+
+        lower.sort_unstable_by_key(|&color| channel(color, widest_channel));
+        let upper = lower.split_off(lower.len() / 2);
+        boxes.push(lower);
+        boxes.push(upper);
+    }
+
+    boxes
+        .iter()
+        .map(|colors| mean(colors))
+        .collect::<IndexSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+// Starts from the median cut colours and moves each to the centre, in CIELAB, of the colours
+// closest to it
+fn kmeans(colors: &[Rgb], count: usize) -> Vec<Rgb> {
+    let labs: Vec<Lab> = colors.iter().map(|&color| Lab::from(color)).collect();
+    let mut centres: Vec<Lab> = median_cut(colors, count)
+        .into_iter()
+        .map(Lab::from)
+        .collect();
+    let mut assignments = vec![usize::MAX; labs.len()];
+
+    for _ in 0..KMEANS_ITERATIONS {
+        let mut is_changed = false;
+
+        for (assignment, &lab) in assignments.iter_mut().zip(labs.iter()) {
+            let nearest = nearest_index(&centres, lab);
+
+            is_changed |= *assignment != nearest;
+            *assignment = nearest;
+        }
+
+        if !is_changed {
+            break;
+        }
+
+        let mut sums = vec![(0.0, 0.0, 0.0, 0usize); centres.len()];
+
+        for (&assignment, lab) in assignments.iter().zip(labs.iter()) {
+            let sum = &mut sums[assignment];
+            *sum = (sum.0 + lab.l, sum.1 + lab.a, sum.2 + lab.b, sum.3 + 1);
+        }
+
+        // A centre nothing is closest to stays where it is
+        for (centre, (l, a, b, members)) in centres.iter_mut().zip(sums) {
+            if members > 0 {
+                let members = members as f64;
+                *centre = Lab {
+                    l: l / members,
+                    a: a / members,
+                    b: b / members,
+                };
+            }
+        }
+    }
+
+    centres
+        .into_iter()
+        .map(Rgb::from)
+        .collect::<IndexSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::pattern::test_fixtures::{BLUE, RED};
+
+    fn image(width: usize, height: usize, pixel: impl Fn(usize, usize) -> [u8; 4]) -> RgbaImage {
+        let data = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .flat_map(|(x, y)| pixel(x, y))
+            .collect();
+
+        RgbaImage {
+            width,
+            height,
+            data,
+        }
+    }
+
+    fn options(size: GridSize, colors: usize) -> ImageImportOptions {
+        ImageImportOptions {
+            size,
+            colors,
+            quantizer: Quantizer::MedianCut,
+            palette: None,
+            dither: false,
+        }
+    }
+
+    fn distinct_colors(grid: &GridType) -> IndexSet<Rgb> {
+        grid.iter()
+            .flat_map(|(_, threads)| {
+                threads
+                    .iter()
+                    .map(|thread| thread.color)
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rejects_bad_images() {
+        let empty = image(0, 0, |_, _| [0; 4]);
+        let short = RgbaImage {
+            width: 2,
+            height: 2,
+            data: vec![0; 15],
+        };
+        let tiny = image(1, 1, |_, _| [0; 4]);
+
+        assert!(matches!(
+            import_image(&empty, options(GridSize(2, 2), 4)),
+            Err(ImageImportError::EmptyImage)
+        ));
+        assert!(matches!(
+            import_image(&short, options(GridSize(2, 2), 4)),
+            Err(ImageImportError::InvalidData {
+                expected: 16,
+                actual: 15
+            })
+        ));
+        assert!(matches!(
+            import_image(&tiny, options(GridSize(0, 2), 4)),
+            Err(ImageImportError::EmptySize)
+        ));
+    }
+
+    #[test]
+    fn resample_averages_the_pixels_of_each_cell() {
+        // Left half red and right half blue, with a stripe of each in the middle cell
+        let image = image(6, 2, |x, _| {
+            if x < 3 {
+                [255, 0, 0, 255]
+            } else {
+                [0, 0, 255, 255]
+            }
+        });
+        let cells = resample(&image, GridSize(3, 1));
+
+        assert_eq!(cells[0].map(to_rgb), Some(RED));
+        assert_eq!(
+            cells[1].map(to_rgb),
+            Some(Rgb {
+                r: 128,
+                g: 0,
+                b: 128
+            })
+        );
+        assert_eq!(cells[2].map(to_rgb), Some(BLUE));
+    }
+
+    #[test]
+    fn resample_scales_up_too() {
+        let image = image(2, 1, |x, _| {
+            if x == 0 {
+                [255, 0, 0, 255]
+            } else {
+                [0, 0, 255, 255]
+            }
+        });
+        let cells = resample(&image, GridSize(4, 2));
+
+        assert_eq!(
+            cells
+                .iter()
+                .map(|cell| cell.map(to_rgb))
+                .collect::<Vec<_>>(),
+            [Some(RED), Some(RED), Some(BLUE), Some(BLUE)].repeat(2)
+        );
+    }
+
+    #[test]
+    fn transparent_cells_are_left_empty() {
+        // A red pixel on a transparent background
+        let image = image(4, 1, |x, _| {
+            if x == 0 {
+                [255, 0, 0, 255]
+            } else {
+                [0, 0, 0, 0]
+            }
+        });
+        let grid = import_image(&image, options(GridSize(4, 1), 4)).unwrap();
+
+        assert_eq!(grid.get(GridCell(0, 0)), Some(CellThreads::full_cross(RED)));
+        assert!(grid.get(GridCell(1, 0)).unwrap().is_empty());
+
+        // Half covered cells keep their colour rather than being darkened by the background
+        let cells = resample(&image, GridSize(2, 1));
+        assert_eq!(cells[0].map(to_rgb), Some(RED));
+        assert_eq!(cells[1], None);
+    }
+
+    #[test]
+    fn median_cut_keeps_distinct_colours_apart() {
+        let colors = [RED, BLUE, BLUE, RED];
+        let palette = median_cut(&colors, 2);
+
+        assert_eq!(palette.len(), 2);
+        assert!(palette.contains(&RED) && palette.contains(&BLUE));
+
+        // There's no point making up colours the image doesn't have
+        assert_eq!(median_cut(&[RED, RED, RED], 4), vec![RED]);
+        assert_eq!(median_cut(&[], 4), vec![]);
+    }
+
+    #[test]
+    fn kmeans_finds_cluster_centres() {
+        let colors: Vec<Rgb> = (0..10u8)
+            .flat_map(|offset| {
+                [
+                    Rgb {
+                        r: 200 + offset,
+                        g: 20,
+                        b: 20,
+                    },
+                    Rgb {
+                        r: 20,
+                        g: 20,
+                        b: 200 + offset,
+                    },
+                ]
+            })
+            .collect();
+        let palette = kmeans(&colors, 2);
+
+        assert_eq!(palette.len(), 2);
+        for color in palette {
+            let is_red = color.r > 190 && color.b < 40;
+            let is_blue = color.b > 190 && color.r < 40;
+            assert!(is_red || is_blue, "{color}");
+        }
+    }
+
+    #[test]
+    fn limits_the_number_of_colours() {
+        let gradient = image(16, 16, |x, y| [(x * 16) as u8, (y * 16) as u8, 128, 255]);
+
+        for quantizer in Quantizer::ALL {
+            let grid = import_image(
+                &gradient,
+                ImageImportOptions {
+                    quantizer,
+                    ..options(GridSize(8, 8), 5)
+                },
+            )
+            .unwrap();
+
+            assert_eq!(grid.size(), GridSize(8, 8));
+            assert!(distinct_colors(&grid).len() <= 5);
+            assert!(grid.iter().all(|(_, threads)| !threads.is_empty()));
+        }
+    }
+
+    #[test]
+    fn snaps_to_the_palette() {
+        let image = image(2, 1, |x, _| {
+            if x == 0 {
+                [1, 2, 3, 255]
+            } else {
+                [200, 40, 60, 255]
+            }
+        });
+        let grid = import_image(
+            &image,
+            ImageImportOptions {
+                palette: Some(Palette::Dmc),
+                ..options(GridSize(2, 1), 2)
+            },
+        )
+        .unwrap();
+        let black = Palette::Dmc.find("310").unwrap();
+        let threads = grid.get(GridCell(0, 0)).unwrap();
+
+        assert_eq!(
+            threads,
+            CellThreads::full_cross(black.color()).with_entry(Some(black))
+        );
+        assert!(grid
+            .get(GridCell(1, 0))
+            .unwrap()
+            .iter()
+            .all(|thread| thread.entry == Some(Palette::Dmc.nearest(thread.color))));
+    }
+
+    #[test]
+    fn dithering_mixes_colours_to_match_the_average() {
+        let grey = vec![Some([128.0; 3]); 100];
+        let colors = [Rgb::BLACK, Rgb::WHITE];
+
+        // Without dithering every cell gets the closest colour
+        let flat = assign_threads(grey.clone(), 10, &colors, false);
+        assert!(flat.iter().all(|&thread| thread == flat[0]));
+
+        let dithered = assign_threads(grey, 10, &colors, true);
+        let white_count = dithered.iter().filter(|&&thread| thread == Some(1)).count();
+        assert!(
+            (40..=60).contains(&white_count),
+            "{white_count} white cells"
+        );
+    }
+
+    #[test]
+    fn dithering_skips_empty_cells() {
+        let cells = vec![Some([128.0; 3]), None, Some([128.0; 3]), None];
+        let assigned = assign_threads(cells, 2, &[Rgb::BLACK, Rgb::WHITE], true);
+
+        assert_eq!(assigned[1], None);
+        assert_eq!(assigned[3], None);
+        assert!(assigned[0].is_some() && assigned[2].is_some());
+    }
+}
@@ -0,0 +1,117 @@
+use super::GridCell;
+
+/// The cells on a straight line from `start` to `end`, both included, using Bresenham's
+/// algorithm. Consecutive cells are always neighbours, diagonals included.
+pub fn line_cells(start: GridCell, end: GridCell) -> Vec<GridCell> {
+    let GridCell(start_col, start_row) = start;
+    let GridCell(end_col, end_row) = end;
+    let (mut col, mut row) = (start_col as isize, start_row as isize);
+    let (end_col, end_row) = (end_col as isize, end_row as isize);
+    let col_distance = (end_col - col).abs();
+    let row_distance = -(end_row - row).abs();
+    let col_step = if col < end_col { 1 } else { -1 };
+    let row_step = if row < end_row { 1 } else { -1 };
+    let mut error = col_distance + row_distance;
+    let mut cells = Vec::with_capacity(col_distance.max(-row_distance) as usize + 1);
+
+    loop {
+        cells.push(GridCell(col as usize, row as usize));
+
+        if col == end_col && row == end_row {
+            break;
+        }
+
+        let doubled_error = 2 * error;
+
+        if doubled_error >= row_distance {
+            error += row_distance;
+            col += col_step;
+        }
+        if doubled_error <= col_distance {
+            error += col_distance;
+            row += row_step;
+        }
+    }
+
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_connected(cells: &[GridCell]) {
+        for pair in cells.windows(2) {
+            let (GridCell(a_col, a_row), GridCell(b_col, b_row)) = (pair[0], pair[1]);
+
+            assert!(
+                a_col.abs_diff(b_col) <= 1 && a_row.abs_diff(b_row) <= 1 && pair[0] != pair[1],
+                "{:?} and {:?} aren't neighbours",
+                pair[0],
+                pair[1]
+            );
+        }
+    }
+
+    #[test]
+    fn single_cell() {
+        assert_eq!(
+            line_cells(GridCell(3, 4), GridCell(3, 4)),
+            vec![GridCell(3, 4)]
+        );
+    }
+
+    #[test]
+    fn diagonal() {
+        assert_eq!(
+            line_cells(GridCell(0, 0), GridCell(3, 3)),
+            vec![
+                GridCell(0, 0),
+                GridCell(1, 1),
+                GridCell(2, 2),
+                GridCell(3, 3)
+            ]
+        );
+        assert_eq!(
+            line_cells(GridCell(3, 0), GridCell(0, 3)),
+            vec![
+                GridCell(3, 0),
+                GridCell(2, 1),
+                GridCell(1, 2),
+                GridCell(0, 3)
+            ]
+        );
+    }
+
+    #[test]
+    fn long_horizontal_jump() {
+        let cells = line_cells(GridCell(10, 2), GridCell(0, 2));
+
+        assert_eq!(cells.len(), 11);
+        assert_eq!(cells.first(), Some(&GridCell(10, 2)));
+        assert_eq!(cells.last(), Some(&GridCell(0, 2)));
+        assert!(cells.iter().all(|cell| cell.1 == 2));
+        assert_connected(&cells);
+    }
+
+    #[test]
+    fn long_shallow_jump() {
+        let cells = line_cells(GridCell(0, 0), GridCell(40, 7));
+
+        // One cell per column, since the line is wider than it is tall
+        assert_eq!(cells.len(), 41);
+        assert_eq!(cells.first(), Some(&GridCell(0, 0)));
+        assert_eq!(cells.last(), Some(&GridCell(40, 7)));
+        assert_connected(&cells);
+    }
+
+    #[test]
+    fn long_steep_jump() {
+        let cells = line_cells(GridCell(5, 30), GridCell(0, 0));
+
+        assert_eq!(cells.len(), 31);
+        assert_eq!(cells.first(), Some(&GridCell(5, 30)));
+        assert_eq!(cells.last(), Some(&GridCell(0, 0)));
+        assert_connected(&cells);
+    }
+}
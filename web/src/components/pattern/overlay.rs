@@ -0,0 +1,466 @@
+use implicit_clone::sync::IArray;
+use implicit_clone::ImplicitClone;
+use indexmap::{IndexMap, IndexSet};
+use serde::{Deserialize, Serialize};
+
+use super::{GridCell, GridIntersection, GridPoint, GridSize, Rgb};
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, ImplicitClone, Deserialize, Serialize)]
+pub enum MarkerKind {
+    FrenchKnot,
+    Bead,
+}
+
+impl MarkerKind {
+    pub const ALL: [MarkerKind; 2] = [MarkerKind::FrenchKnot, MarkerKind::Bead];
+
+    pub fn as_str(&self) -> String {
+        match &self {
+            MarkerKind::FrenchKnot => "French knot".to_string(),
+            MarkerKind::Bead => "Bead".to_string(),
+        }
+    }
+}
+
+/// Where a marker sits: in the middle of a cell or on a grid intersection.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, ImplicitClone, Deserialize, Serialize)]
+pub enum MarkerPosition {
+    Centre(GridCell),
+    Corner(GridIntersection),
+}
+
+impl MarkerPosition {
+    // Pointers closer to the centre of a cell than this, as a fraction of the cell, snap to it
+    pub(crate) const CENTRE_RADIUS: f64 = 0.25;
+
+    /// The position closest to the pointer.
+    pub fn nearest(point: GridPoint) -> Self {
+        let (x, y) = point.offset;
+
+        if (x - 0.5).hypot(y - 0.5) < MarkerPosition::CENTRE_RADIUS {
+            MarkerPosition::Centre(point.grid_cell)
+        } else {
+            MarkerPosition::Corner(point.nearest_intersection())
+        }
+    }
+
+    /// The cells the marker is drawn over.
+    pub fn cells(self, size: GridSize) -> Vec<GridCell> {
+        match self {
+            MarkerPosition::Centre(grid_cell) if size.contains(grid_cell) => vec![grid_cell],
+            MarkerPosition::Centre(_) => vec![],
+            MarkerPosition::Corner(intersection) => intersection.cells(size).collect(),
+        }
+    }
+
+    pub fn is_within(self, GridSize(cols, rows): GridSize) -> bool {
+        match self {
+            MarkerPosition::Centre(GridCell(col, row)) => col < cols && row < rows,
+            MarkerPosition::Corner(GridIntersection(col, row)) => col <= cols && row <= rows,
+        }
+    }
+}
+
+/// A French knot or bead.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, ImplicitClone, Deserialize, Serialize)]
+pub struct Marker {
+    pub kind: MarkerKind,
+    pub color: Rgb,
+}
+
+/// A backstitch line running between grid intersections, in any direction.
+#[derive(Clone, Debug, PartialEq, ImplicitClone)]
+pub struct Backstitch {
+    pub points: IArray<GridIntersection>,
+    pub color: Rgb,
+}
+
+impl Backstitch {
+    pub fn segments(&self) -> impl Iterator<Item = (GridIntersection, GridIntersection)> + '_ {
+        self.points
+            .as_slice()
+            .windows(2)
+            .map(|pair| (pair[0], pair[1]))
+    }
+
+    /// The cells the line could be drawn over, including the ones its thickness reaches into.
+    pub fn cells(&self, GridSize(cols, rows): GridSize) -> Vec<GridCell> {
+        // A single point is drawn as a dot
+        let segments: Vec<_> = match self.points.as_slice() {
+            [point] => vec![(*point, *point)],
+            _ => self.segments().collect(),
+        };
+        let cells: IndexSet<GridCell> = segments
+            .into_iter()
+            .flat_map(
+                |(GridIntersection(a_col, a_row), GridIntersection(b_col, b_row))| {
+                    // Every cell with a corner inside the segment's bounding box
+                    let col_range = a_col.min(b_col).saturating_sub(1)..=a_col.max(b_col);
+                    let row_range = a_row.min(b_row).saturating_sub(1)..=a_row.max(b_row);
+
+                    row_range
+                        .flat_map(move |row| col_range.clone().map(move |col| GridCell(col, row)))
+                },
+            )
+            .filter(|&GridCell(col, row)| col < cols && row < rows)
+            .collect();
+
+        cells.into_iter().collect()
+    }
+
+    /// Distance from a point, in cells, to the closest part of the line.
+    pub fn distance_to(&self, (x, y): (f64, f64)) -> f64 {
+        let to_point = |GridIntersection(col, row): GridIntersection| (col as f64, row as f64);
+
+        self.segments()
+            .map(|(start, end)| {
+                let (start_x, start_y) = to_point(start);
+                let (end_x, end_y) = to_point(end);
+                let (dx, dy) = (end_x - start_x, end_y - start_y);
+                let length_squared = dx * dx + dy * dy;
+                // How far along the segment the closest point is, from 0.0 at the start to 1.0
+                // at the end
+                let t = if length_squared == 0.0 {
+                    0.0
+                } else {
+                    (((x - start_x) * dx + (y - start_y) * dy) / length_squared).clamp(0.0, 1.0)
+                };
+
+                (x - (start_x + t * dx)).hypot(y - (start_y + t * dy))
+            })
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    pub fn is_within(&self, size: GridSize) -> bool {
+        self.points
+            .iter()
+            .all(|point| MarkerPosition::Corner(point).is_within(size))
+    }
+}
+
+/// Embellishments drawn on top of a pattern's threads: backstitch lines, and knots and beads.
+/// There's at most one marker per position.
+#[derive(Clone, Debug, Default, PartialEq, ImplicitClone)]
+pub struct Overlay {
+    markers: IArray<(MarkerPosition, Marker)>,
+    backstitches: IArray<Backstitch>,
+}
+
+impl Overlay {
+    pub fn marker(&self, position: MarkerPosition) -> Option<Marker> {
+        self.markers
+            .iter()
+            .find(|(marker_position, _)| *marker_position == position)
+            .map(|(_, marker)| marker)
+    }
+
+    pub fn markers(&self) -> impl Iterator<Item = (MarkerPosition, Marker)> + '_ {
+        self.markers.iter()
+    }
+
+    /// Places a marker, or removes the one at `position` when `marker` is `None`. Returns
+    /// whether anything changed.
+    pub fn set_marker(&mut self, position: MarkerPosition, marker: Option<Marker>) -> bool {
+        if self.marker(position) == marker {
+            return false;
+        }
+
+        let mut markers: Vec<_> = self
+            .markers
+            .iter()
+            .filter(|(marker_position, _)| *marker_position != position)
+            .collect();
+
+        if let Some(marker) = marker {
+            markers.push((position, marker));
+        }
+
+        self.markers = IArray::from(markers);
+        true
+    }
+
+    pub fn backstitches(&self) -> impl Iterator<Item = Backstitch> + '_ {
+        self.backstitches.iter()
+    }
+
+    pub fn backstitch(&self, index: usize) -> Option<Backstitch> {
+        self.backstitches.get(index)
+    }
+
+    /// Adds a line at `index` in the drawing order, or at the end if it's past the end.
+    pub fn insert_backstitch(&mut self, index: usize, backstitch: Backstitch) {
+        let mut backstitches = self.backstitches.to_vec();
+
+        backstitches.insert(index.min(backstitches.len()), backstitch);
+        self.backstitches = IArray::from(backstitches);
+    }
+
+    pub fn remove_backstitch(&mut self, index: usize) -> Option<Backstitch> {
+        let mut backstitches = self.backstitches.to_vec();
+        let backstitch = (index < backstitches.len()).then(|| backstitches.remove(index));
+
+        self.backstitches = IArray::from(backstitches);
+        backstitch
+    }
+
+    /// The index of the topmost line within `tolerance` cells of the point.
+    pub fn backstitch_at(&self, point: GridPoint, tolerance: f64) -> Option<usize> {
+        let GridCell(col, row) = point.grid_cell;
+        let point = (col as f64 + point.offset.0, row as f64 + point.offset.1);
+
+        self.backstitches
+            .as_slice()
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, backstitch)| backstitch.distance_to(point) <= tolerance)
+            .map(|(index, _)| index)
+    }
+
+    /// Positions whose marker differs between the two overlays.
+    pub fn diff(&self, other: &Overlay) -> Vec<MarkerPosition> {
+        let positions: IndexSet<MarkerPosition> = self
+            .markers()
+            .chain(other.markers())
+            .map(|(position, _)| position)
+            .filter(|&position| self.marker(position) != other.marker(position))
+            .collect();
+
+        positions.into_iter().collect()
+    }
+
+    /// Lines that are in only one of the two overlays.
+    pub fn backstitch_diff(&self, other: &Overlay) -> Vec<Backstitch> {
+        let only_in = |a: &Overlay, b: &Overlay| -> Vec<Backstitch> {
+            a.backstitches()
+                .filter(|backstitch| !b.backstitches.contains(backstitch))
+                .collect()
+        };
+
+        if self.backstitches == other.backstitches {
+            return vec![];
+        }
+
+        [only_in(self, other), only_in(other, self)].concat()
+    }
+}
+
+/// The overlay items drawn over each cell, so a repaint of a few cells doesn't have to check
+/// every item for each of them.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OverlayCells {
+    // Indices into the backstitch lines, in drawing order
+    backstitches: IndexMap<GridCell, Vec<usize>>,
+    markers: IndexMap<GridCell, Vec<(MarkerPosition, Marker)>>,
+}
+
+impl OverlayCells {
+    pub fn new(overlay: &Overlay, size: GridSize) -> Self {
+        let mut cells = OverlayCells::default();
+
+        for (index, backstitch) in overlay.backstitches().enumerate() {
+            for grid_cell in backstitch.cells(size) {
+                cells.backstitches.entry(grid_cell).or_default().push(index);
+            }
+        }
+
+        for (position, marker) in overlay.markers() {
+            for grid_cell in position.cells(size) {
+                cells
+                    .markers
+                    .entry(grid_cell)
+                    .or_default()
+                    .push((position, marker));
+            }
+        }
+
+        cells
+    }
+
+    pub fn backstitches(&self, grid_cell: GridCell) -> &[usize] {
+        self.backstitches.get(&grid_cell).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn markers(&self, grid_cell: GridCell) -> &[(MarkerPosition, Marker)] {
+        self.markers.get(&grid_cell).map_or(&[], Vec::as_slice)
+    }
+}
+
+impl FromIterator<(MarkerPosition, Marker)> for Overlay {
+    fn from_iter<I: IntoIterator<Item = (MarkerPosition, Marker)>>(iter: I) -> Self {
+        let mut overlay = Overlay::default();
+        for (position, marker) in iter {
+            overlay.set_marker(position, Some(marker));
+        }
+        overlay
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::pattern::test_fixtures::{BLUE, RED};
+
+    const KNOT: Marker = Marker {
+        kind: MarkerKind::FrenchKnot,
+        color: RED,
+    };
+    const BEAD: Marker = Marker {
+        kind: MarkerKind::Bead,
+        color: BLUE,
+    };
+
+    fn point(col: usize, row: usize, offset: (f64, f64)) -> GridPoint {
+        GridPoint {
+            grid_cell: GridCell(col, row),
+            offset,
+        }
+    }
+
+    #[test]
+    fn set_marker_replaces_removes_and_reports_changes() {
+        let centre = MarkerPosition::Centre(GridCell(1, 1));
+        let corner = MarkerPosition::Corner(GridIntersection(1, 1));
+        let mut overlay: Overlay = [(centre, KNOT), (corner, KNOT)].into_iter().collect();
+
+        assert!(overlay.set_marker(centre, Some(BEAD)));
+        assert_eq!(overlay.marker(centre), Some(BEAD));
+        assert_eq!(overlay.markers().count(), 2);
+
+        assert!(!overlay.set_marker(centre, Some(BEAD)));
+        assert!(!overlay.set_marker(MarkerPosition::Centre(GridCell(4, 4)), None));
+
+        assert!(overlay.set_marker(corner, None));
+        assert_eq!(overlay.marker(corner), None);
+        assert_eq!(overlay.markers().collect::<Vec<_>>(), vec![(centre, BEAD)]);
+    }
+
+    #[test]
+    fn nearest_snaps_to_the_centre_only_close_to_it() {
+        assert_eq!(
+            MarkerPosition::nearest(point(2, 3, (0.5, 0.5))),
+            MarkerPosition::Centre(GridCell(2, 3))
+        );
+        assert_eq!(
+            MarkerPosition::nearest(point(2, 3, (0.5, 0.74))),
+            MarkerPosition::Centre(GridCell(2, 3))
+        );
+        assert_eq!(
+            MarkerPosition::nearest(point(2, 3, (0.4, 0.8))),
+            MarkerPosition::Corner(GridIntersection(2, 4))
+        );
+        assert_eq!(
+            MarkerPosition::nearest(point(2, 3, (0.9, 0.1))),
+            MarkerPosition::Corner(GridIntersection(3, 3))
+        );
+    }
+
+    #[test]
+    fn diff_lists_positions_whose_marker_changed() {
+        let centre = MarkerPosition::Centre(GridCell(0, 0));
+        let corner = MarkerPosition::Corner(GridIntersection(2, 2));
+        let moved = MarkerPosition::Corner(GridIntersection(3, 2));
+        let overlay: Overlay = [(centre, KNOT), (corner, KNOT)].into_iter().collect();
+        let edited: Overlay = [(centre, BEAD), (moved, KNOT)].into_iter().collect();
+
+        assert!(overlay.diff(&overlay.clone()).is_empty());
+        assert_eq!(overlay.diff(&edited), vec![centre, corner, moved]);
+    }
+
+    fn line(points: &[(usize, usize)], color: Rgb) -> Backstitch {
+        Backstitch {
+            points: points
+                .iter()
+                .map(|&(col, row)| GridIntersection(col, row))
+                .collect(),
+            color,
+        }
+    }
+
+    #[test]
+    fn distance_to_measures_to_the_closest_segment() {
+        let backstitch = line(&[(0, 0), (4, 0), (4, 4)], RED);
+
+        assert_eq!(backstitch.distance_to((2.0, 0.0)), 0.0);
+        assert_eq!(backstitch.distance_to((2.0, 1.5)), 1.5);
+        assert_eq!(backstitch.distance_to((5.0, 3.0)), 1.0);
+        // Past the end of the line the distance is to its last point
+        assert_eq!(backstitch.distance_to((4.0, 7.0)), 3.0);
+        assert_eq!(backstitch.distance_to((-3.0, -4.0)), 5.0);
+    }
+
+    #[test]
+    fn backstitch_at_finds_the_topmost_line_within_tolerance() {
+        let mut overlay = Overlay::default();
+
+        overlay.insert_backstitch(0, line(&[(0, 1), (4, 1)], RED));
+        overlay.insert_backstitch(1, line(&[(2, 0), (2, 4)], BLUE));
+
+        // Both lines cross at intersection (2, 1)
+        assert_eq!(overlay.backstitch_at(point(2, 1, (0.0, 0.0)), 0.2), Some(1));
+        assert_eq!(overlay.backstitch_at(point(0, 1, (0.5, 0.1)), 0.2), Some(0));
+        assert_eq!(overlay.backstitch_at(point(0, 1, (0.5, 0.5)), 0.2), None);
+        assert_eq!(overlay.backstitch_at(point(0, 1, (0.5, 0.5)), 0.5), Some(0));
+    }
+
+    #[test]
+    fn indices_past_the_end_append_or_remove_nothing() {
+        let first = line(&[(0, 0), (1, 1)], RED);
+        let second = line(&[(1, 1), (2, 2)], BLUE);
+        let mut overlay = Overlay::default();
+
+        overlay.insert_backstitch(5, first.clone());
+        overlay.insert_backstitch(5, second.clone());
+
+        assert_eq!(
+            overlay.backstitches().collect::<Vec<_>>(),
+            vec![first.clone(), second.clone()]
+        );
+        assert_eq!(overlay.remove_backstitch(2), None);
+        assert_eq!(overlay.backstitches().count(), 2);
+        assert_eq!(overlay.remove_backstitch(0), Some(first));
+        assert_eq!(overlay.backstitches().collect::<Vec<_>>(), vec![second]);
+    }
+
+    #[test]
+    fn backstitch_diff_lists_lines_in_only_one_overlay() {
+        let kept = line(&[(0, 0), (1, 1)], RED);
+        let removed = line(&[(1, 1), (2, 2)], RED);
+        let added = line(&[(1, 1), (2, 2)], BLUE);
+        let mut overlay = Overlay::default();
+
+        overlay.insert_backstitch(0, kept.clone());
+        overlay.insert_backstitch(1, removed.clone());
+
+        let mut edited = overlay.clone();
+
+        assert!(overlay.backstitch_diff(&edited).is_empty());
+
+        edited.remove_backstitch(1);
+        edited.insert_backstitch(0, added.clone());
+
+        assert_eq!(overlay.backstitch_diff(&edited), vec![removed, added]);
+    }
+
+    #[test]
+    fn overlay_cells_list_the_items_over_each_cell_in_drawing_order() {
+        let corner = MarkerPosition::Corner(GridIntersection(1, 1));
+        let centre = MarkerPosition::Centre(GridCell(0, 0));
+        let mut overlay: Overlay = [(corner, KNOT), (centre, BEAD)].into_iter().collect();
+
+        overlay.insert_backstitch(0, line(&[(0, 0), (1, 0)], RED));
+        overlay.insert_backstitch(1, line(&[(3, 3), (3, 4)], BLUE));
+
+        let cells = OverlayCells::new(&overlay, GridSize(4, 4));
+
+        assert_eq!(cells.backstitches(GridCell(0, 0)), &[0]);
+        assert_eq!(cells.backstitches(GridCell(3, 3)), &[1]);
+        assert_eq!(cells.backstitches(GridCell(1, 1)), &[] as &[usize]);
+        assert_eq!(
+            cells.markers(GridCell(0, 0)),
+            &[(corner, KNOT), (centre, BEAD)]
+        );
+        assert_eq!(cells.markers(GridCell(1, 1)), &[(corner, KNOT)]);
+        assert!(cells.markers(GridCell(2, 2)).is_empty());
+    }
+}
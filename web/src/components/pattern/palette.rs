@@ -0,0 +1,203 @@
+use std::fmt;
+use std::sync::OnceLock;
+
+use implicit_clone::ImplicitClone;
+use serde::{Deserialize, Serialize};
+
+use super::color::Lab;
+use super::Rgb;
+
+mod dmc;
+
+#[non_exhaustive]
+#[derive(thiserror::Error, Debug)]
+pub enum PaletteError {
+    #[error("unknown thread {0}")]
+    UnknownEntry(String),
+}
+
+/// A named thread sold by a manufacturer.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PaletteEntry {
+    pub brand: &'static str,
+    // The manufacturer's number, which is what stitchers shop by
+    pub code: &'static str,
+    pub name: &'static str,
+    pub color: Rgb,
+}
+
+impl fmt::Display for PaletteEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} {}", self.brand, self.code, self.name)
+    }
+}
+
+/// A thread catalogue compiled into the crate.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, ImplicitClone)]
+pub enum Palette {
+    #[default]
+    Dmc,
+}
+
+impl Palette {
+    pub const ALL: [Palette; 1] = [Palette::Dmc];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Palette::Dmc => "DMC",
+        }
+    }
+
+    pub fn entries(self) -> &'static [PaletteEntry] {
+        match self {
+            Palette::Dmc => dmc::ENTRIES,
+        }
+    }
+
+    pub fn ids(self) -> impl Iterator<Item = PaletteEntryId> {
+        (0..self.entries().len()).map(move |index| PaletteEntryId {
+            palette: self,
+            index: index as u16,
+        })
+    }
+
+    /// Looks a thread up by its manufacturer's number, ignoring case.
+    pub fn find(self, code: &str) -> Option<PaletteEntryId> {
+        self.ids()
+            .find(|id| id.entry().code.eq_ignore_ascii_case(code.trim()))
+    }
+
+    /// The thread that looks closest to `color`, by CIEDE2000 difference, so that the match
+    /// follows how different the colours look rather than how far apart their RGB values are.
+    pub fn nearest(self, color: Rgb) -> PaletteEntryId {
+        let target = Lab::from(color);
+        let (index, _) = self
+            .lab_entries()
+            .iter()
+            .enumerate()
+            .map(|(index, lab)| (index, lab.delta_e(target)))
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .expect("palettes are never empty");
+
+        PaletteEntryId {
+            palette: self,
+            index: index as u16,
+        }
+    }
+
+    // Converted once, as matching a whole image looks up every pixel
+    fn lab_entries(self) -> &'static [Lab] {
+        static DMC: OnceLock<Vec<Lab>> = OnceLock::new();
+
+        let cache = match self {
+            Palette::Dmc => &DMC,
+        };
+
+        cache.get_or_init(|| {
+            self.entries()
+                .iter()
+                .map(|entry| Lab::from(entry.color))
+                .collect()
+        })
+    }
+}
+
+/// A reference to an entry of a bundled palette.
+///
+/// Saved as the brand and code, e.g. `"DMC 310"`, so files don't depend on catalogue order.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, ImplicitClone, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct PaletteEntryId {
+    palette: Palette,
+    index: u16,
+}
+
+impl PaletteEntryId {
+    pub fn palette(self) -> Palette {
+        self.palette
+    }
+
+    pub fn entry(self) -> &'static PaletteEntry {
+        &self.palette.entries()[self.index as usize]
+    }
+
+    pub fn color(self) -> Rgb {
+        self.entry().color
+    }
+}
+
+impl TryFrom<String> for PaletteEntryId {
+    type Error = PaletteError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value
+            .split_once(' ')
+            .and_then(|(brand, code)| {
+                Palette::ALL
+                    .into_iter()
+                    .find(|palette| palette.as_str().eq_ignore_ascii_case(brand))
+                    .and_then(|palette| palette.find(code))
+            })
+            .ok_or(PaletteError::UnknownEntry(value))
+    }
+}
+
+impl From<PaletteEntryId> for String {
+    fn from(id: PaletteEntryId) -> Self {
+        format!("{} {}", id.palette.as_str(), id.entry().code)
+    }
+}
+
+impl fmt::Display for PaletteEntryId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.entry().fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_are_unique() {
+        for palette in Palette::ALL {
+            for (index, entry) in palette.entries().iter().enumerate() {
+                assert_eq!(
+                    palette.find(entry.code).map(|id| id.index as usize),
+                    Some(index),
+                    "{entry}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn exact_colours_match_their_own_entry() {
+        let black = Palette::Dmc.find("310").unwrap();
+
+        assert_eq!(black.entry().name, "Black");
+        assert_eq!(Palette::Dmc.nearest(Rgb { r: 0, g: 0, b: 0 }), black);
+    }
+
+    #[test]
+    fn nearest_colour_is_perceptual() {
+        let nearest = Palette::Dmc.nearest(Rgb {
+            r: 200,
+            g: 40,
+            b: 60,
+        });
+
+        assert_eq!(nearest.entry().code, "321");
+    }
+
+    #[test]
+    fn entry_ids_round_trip_through_their_code() {
+        let ecru = Palette::Dmc.find("ecru").unwrap();
+        let saved = String::from(ecru);
+
+        assert_eq!(saved, "DMC ECRU");
+        assert_eq!(PaletteEntryId::try_from(saved).unwrap(), ecru);
+        assert!(PaletteEntryId::try_from("DMC 9999".to_string()).is_err());
+        assert!(PaletteEntryId::try_from("Anchor 310".to_string()).is_err());
+    }
+}
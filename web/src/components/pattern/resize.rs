@@ -0,0 +1,379 @@
+use std::cmp::Ordering;
+
+use implicit_clone::ImplicitClone;
+
+use super::overlay::{Backstitch, MarkerPosition, Overlay};
+use super::{CellGrid, GridCell, GridIntersection, GridSize, GridType};
+
+/// The part of the pattern that stays where it is when the canvas is resized.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ImplicitClone)]
+pub enum Anchor {
+    #[default]
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Centre,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    // In rows of three, as they're laid out in the canvas form
+    pub const ALL: [Anchor; 9] = [
+        Anchor::TopLeft,
+        Anchor::Top,
+        Anchor::TopRight,
+        Anchor::Left,
+        Anchor::Centre,
+        Anchor::Right,
+        Anchor::BottomLeft,
+        Anchor::Bottom,
+        Anchor::BottomRight,
+    ];
+
+    pub fn as_str(&self) -> String {
+        match &self {
+            Anchor::TopLeft => "Top left".to_string(),
+            Anchor::Top => "Top".to_string(),
+            Anchor::TopRight => "Top right".to_string(),
+            Anchor::Left => "Left".to_string(),
+            Anchor::Centre => "Centre".to_string(),
+            Anchor::Right => "Right".to_string(),
+            Anchor::BottomLeft => "Bottom left".to_string(),
+            Anchor::Bottom => "Bottom".to_string(),
+            Anchor::BottomRight => "Bottom right".to_string(),
+        }
+    }
+
+    // How many cells the pattern moves right and down when the canvas goes from `from` to `to`.
+    // Centred patterns lean to the top left when the change is odd.
+    fn offset(
+        self,
+        GridSize(from_cols, from_rows): GridSize,
+        GridSize(to_cols, to_rows): GridSize,
+    ) -> (isize, isize) {
+        let (horizontal, vertical) = match self {
+            Anchor::TopLeft => (0, 0),
+            Anchor::Top => (1, 0),
+            Anchor::TopRight => (2, 0),
+            Anchor::Left => (0, 1),
+            Anchor::Centre => (1, 1),
+            Anchor::Right => (2, 1),
+            Anchor::BottomLeft => (0, 2),
+            Anchor::Bottom => (1, 2),
+            Anchor::BottomRight => (2, 2),
+        };
+        let col_change = to_cols as isize - from_cols as isize;
+        let row_change = to_rows as isize - from_rows as isize;
+
+        (
+            (col_change * horizontal).div_euclid(2),
+            (row_change * vertical).div_euclid(2),
+        )
+    }
+}
+
+/// What happens to a side of the canvas that's made smaller.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ImplicitClone)]
+pub enum ResizeMode {
+    // The cells that no longer fit are cut off
+    #[default]
+    Crop,
+    // The canvas only grows, so no stitches are lost
+    Pad,
+}
+
+impl ResizeMode {
+    pub const ALL: [ResizeMode; 2] = [ResizeMode::Crop, ResizeMode::Pad];
+
+    pub fn as_str(&self) -> String {
+        match &self {
+            ResizeMode::Crop => "Crop".to_string(),
+            ResizeMode::Pad => "Pad".to_string(),
+        }
+    }
+
+    /// The size the canvas ends up when `size` is asked for.
+    pub fn size(self, current: GridSize, size: GridSize) -> GridSize {
+        match self {
+            ResizeMode::Crop => size,
+            ResizeMode::Pad => GridSize(current.0.max(size.0), current.1.max(size.1)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ImplicitClone)]
+pub enum Axis {
+    Row,
+    Column,
+}
+
+/// A change to the size or layout of the whole canvas.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ImplicitClone)]
+pub enum CanvasEdit {
+    Resize {
+        size: GridSize,
+        anchor: Anchor,
+        mode: ResizeMode,
+    },
+    // Adds an empty row or column at the index, moving the ones from there on along
+    Insert(Axis, usize),
+    // Takes out the row or column at the index, moving the ones after it back
+    Delete(Axis, usize),
+}
+
+impl CanvasEdit {
+    /// The grid and overlay after the edit. Returns `None` when there's nothing at the index to
+    /// insert before or delete.
+    pub fn apply(self, grid: &GridType, overlay: &Overlay) -> Option<(GridType, Overlay)> {
+        let GridSize(cols, rows) = grid.size();
+
+        match self {
+            CanvasEdit::Resize { size, anchor, mode } => {
+                let size = mode.size(grid.size(), size);
+                let (col_offset, row_offset) = anchor.offset(grid.size(), size);
+                let moved = |col: usize, row: usize| {
+                    Some((
+                        usize::try_from(col as isize + col_offset).ok()?,
+                        usize::try_from(row as isize + row_offset).ok()?,
+                    ))
+                };
+
+                Some(remapped(grid, overlay, size, moved, moved))
+            }
+            CanvasEdit::Insert(Axis::Row, index) if index <= rows => Some(remapped(
+                grid,
+                overlay,
+                GridSize(cols, rows + 1),
+                |col, row| Some((col, if row >= index { row + 1 } else { row })),
+                // Points on the line the row is inserted at stay above it
+                |col, row| Some((col, if row > index { row + 1 } else { row })),
+            )),
+            CanvasEdit::Insert(Axis::Column, index) if index <= cols => Some(remapped(
+                grid,
+                overlay,
+                GridSize(cols + 1, rows),
+                |col, row| Some((if col >= index { col + 1 } else { col }, row)),
+                |col, row| Some((if col > index { col + 1 } else { col }, row)),
+            )),
+            CanvasEdit::Delete(Axis::Row, index) if index < rows => Some(remapped(
+                grid,
+                overlay,
+                GridSize(cols, rows - 1),
+                |col, row| match row.cmp(&index) {
+                    Ordering::Less => Some((col, row)),
+                    Ordering::Equal => None,
+                    Ordering::Greater => Some((col, row - 1)),
+                },
+                // The lines above and below the row become one
+                |col, row| Some((col, if row > index { row - 1 } else { row })),
+            )),
+            CanvasEdit::Delete(Axis::Column, index) if index < cols => Some(remapped(
+                grid,
+                overlay,
+                GridSize(cols - 1, rows),
+                |col, row| match col.cmp(&index) {
+                    Ordering::Less => Some((col, row)),
+                    Ordering::Equal => None,
+                    Ordering::Greater => Some((col - 1, row)),
+                },
+                |col, row| Some((if col > index { col - 1 } else { col }, row)),
+            )),
+            CanvasEdit::Insert(..) | CanvasEdit::Delete(..) => None,
+        }
+    }
+}
+
+// Moves every cell and overlay item to a grid of `size`. Whatever is moved off the grid, or
+// has nowhere to go, is dropped, and so is a backstitch line with any of its points dropped.
+fn remapped(
+    grid: &GridType,
+    overlay: &Overlay,
+    size: GridSize,
+    cell: impl Fn(usize, usize) -> Option<(usize, usize)>,
+    intersection: impl Fn(usize, usize) -> Option<(usize, usize)>,
+) -> (GridType, Overlay) {
+    let GridSize(cols, rows) = size;
+    let cell = |GridCell(col, row): GridCell| {
+        let (col, row) = cell(col, row)?;
+        let grid_cell = GridCell(col, row);
+
+        size.contains(grid_cell).then_some(grid_cell)
+    };
+    let intersection = |GridIntersection(col, row): GridIntersection| {
+        let (col, row) = intersection(col, row)?;
+
+        (col <= cols && row <= rows).then_some(GridIntersection(col, row))
+    };
+    let mut remapped_grid = CellGrid::new(size);
+
+    for (grid_cell, threads) in grid.iter() {
+        if threads.is_empty() {
+            continue;
+        }
+
+        if let Some(grid_cell) = cell(grid_cell) {
+            remapped_grid.set(grid_cell, threads);
+        }
+    }
+
+    let mut remapped_overlay: Overlay = overlay
+        .markers()
+        .filter_map(|(position, marker)| {
+            let position = match position {
+                MarkerPosition::Centre(grid_cell) => MarkerPosition::Centre(cell(grid_cell)?),
+                MarkerPosition::Corner(point) => MarkerPosition::Corner(intersection(point)?),
+            };
+
+            Some((position, marker))
+        })
+        .collect();
+    let backstitches = overlay.backstitches().filter_map(|backstitch| {
+        Some(Backstitch {
+            points: backstitch
+                .points
+                .iter()
+                .map(&intersection)
+                .collect::<Option<_>>()?,
+            color: backstitch.color,
+        })
+    });
+
+    for (index, backstitch) in backstitches.enumerate() {
+        remapped_overlay.insert_backstitch(index, backstitch);
+    }
+
+    (remapped_grid, remapped_overlay)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::pattern::overlay::{Marker, MarkerKind};
+    use crate::components::pattern::test_fixtures::{full_crosses, stitched_cells, RED};
+
+    #[test]
+    fn resizing_from_the_centre_keeps_the_middle() {
+        let grid = full_crosses(GridSize(3, 3), &[GridCell(1, 1)]);
+        let edit = CanvasEdit::Resize {
+            size: GridSize(5, 5),
+            anchor: Anchor::Centre,
+            mode: ResizeMode::Crop,
+        };
+        let (grid, _) = edit.apply(&grid, &Overlay::default()).unwrap();
+
+        assert_eq!(grid.size(), GridSize(5, 5));
+        assert_eq!(stitched_cells(&grid), vec![GridCell(2, 2)]);
+    }
+
+    #[test]
+    fn cropping_from_the_bottom_right_cuts_off_the_top_left() {
+        let grid = full_crosses(GridSize(4, 4), &[GridCell(0, 0), GridCell(3, 3)]);
+        let edit = CanvasEdit::Resize {
+            size: GridSize(2, 2),
+            anchor: Anchor::BottomRight,
+            mode: ResizeMode::Crop,
+        };
+        let (grid, _) = edit.apply(&grid, &Overlay::default()).unwrap();
+
+        assert_eq!(stitched_cells(&grid), vec![GridCell(1, 1)]);
+    }
+
+    #[test]
+    fn padding_never_shrinks() {
+        let grid = full_crosses(GridSize(4, 4), &[GridCell(3, 3)]);
+        let edit = CanvasEdit::Resize {
+            size: GridSize(2, 6),
+            anchor: Anchor::TopLeft,
+            mode: ResizeMode::Pad,
+        };
+        let (grid, _) = edit.apply(&grid, &Overlay::default()).unwrap();
+
+        assert_eq!(grid.size(), GridSize(4, 6));
+        assert_eq!(stitched_cells(&grid), vec![GridCell(3, 3)]);
+    }
+
+    #[test]
+    fn inserting_a_row_moves_the_rows_below() {
+        let grid = full_crosses(GridSize(2, 3), &[GridCell(0, 0), GridCell(1, 2)]);
+        let (grid, _) = CanvasEdit::Insert(Axis::Row, 1)
+            .apply(&grid, &Overlay::default())
+            .unwrap();
+
+        assert_eq!(grid.size(), GridSize(2, 4));
+        assert_eq!(stitched_cells(&grid), vec![GridCell(0, 0), GridCell(1, 3)]);
+    }
+
+    #[test]
+    fn deleting_a_column_drops_its_cells() {
+        let grid = full_crosses(
+            GridSize(3, 1),
+            &[GridCell(0, 0), GridCell(1, 0), GridCell(2, 0)],
+        );
+        let (grid, _) = CanvasEdit::Delete(Axis::Column, 1)
+            .apply(&grid, &Overlay::default())
+            .unwrap();
+
+        assert_eq!(grid.size(), GridSize(2, 1));
+        assert_eq!(stitched_cells(&grid), vec![GridCell(0, 0), GridCell(1, 0)]);
+    }
+
+    #[test]
+    fn overlay_moves_with_the_cells() {
+        let marker = Marker {
+            kind: MarkerKind::Bead,
+            color: RED,
+        };
+        let mut overlay: Overlay = [
+            (MarkerPosition::Centre(GridCell(0, 2)), marker),
+            (MarkerPosition::Centre(GridCell(0, 1)), marker),
+        ]
+        .into_iter()
+        .collect();
+
+        overlay.insert_backstitch(
+            0,
+            Backstitch {
+                points: [GridIntersection(0, 0), GridIntersection(0, 3)]
+                    .into_iter()
+                    .collect(),
+                color: RED,
+            },
+        );
+
+        let (_, overlay) = CanvasEdit::Delete(Axis::Row, 1)
+            .apply(&CellGrid::new(GridSize(1, 3)), &overlay)
+            .unwrap();
+
+        assert_eq!(
+            overlay.markers().collect::<Vec<_>>(),
+            vec![(MarkerPosition::Centre(GridCell(0, 1)), marker)]
+        );
+        assert_eq!(
+            overlay
+                .backstitch(0)
+                .map(|backstitch| backstitch.points.to_vec()),
+            Some(vec![GridIntersection(0, 0), GridIntersection(0, 2)])
+        );
+    }
+
+    #[test]
+    fn index_past_the_end_does_nothing() {
+        let grid = CellGrid::new(GridSize(2, 2));
+        let overlay = Overlay::default();
+
+        assert!(CanvasEdit::Delete(Axis::Row, 2)
+            .apply(&grid, &overlay)
+            .is_none());
+        assert!(CanvasEdit::Insert(Axis::Column, 3)
+            .apply(&grid, &overlay)
+            .is_none());
+        // Inserting at the end adds a column after the last one
+        assert!(CanvasEdit::Insert(Axis::Column, 2)
+            .apply(&grid, &overlay)
+            .is_some());
+    }
+}
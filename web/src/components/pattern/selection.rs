@@ -0,0 +1,121 @@
+use implicit_clone::sync::IArray;
+use indexmap::IndexSet;
+
+use super::GridCell;
+
+/// How a newly picked group of cells combines with the cells that are already selected.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SelectionMode {
+    #[default]
+    Replace,
+    Add,
+    // Selected cells are deselected and the others are selected
+    Toggle,
+}
+
+impl SelectionMode {
+    /// The selection after picking `cells` with `base` already selected. The order cells were
+    /// selected in is kept.
+    pub fn apply(
+        self,
+        base: &[GridCell],
+        cells: impl IntoIterator<Item = GridCell>,
+    ) -> IArray<GridCell> {
+        let selection: IndexSet<GridCell> = match self {
+            SelectionMode::Replace => cells.into_iter().collect(),
+            SelectionMode::Add => base.iter().copied().chain(cells).collect(),
+            SelectionMode::Toggle => {
+                let mut selection: IndexSet<GridCell> = base.iter().copied().collect();
+
+                for grid_cell in cells {
+                    if !selection.shift_remove(&grid_cell) {
+                        selection.insert(grid_cell);
+                    }
+                }
+
+                selection
+            }
+        };
+
+        selection.into_iter().collect()
+    }
+}
+
+/// Every cell in the rectangle with `start` and `end` as opposite corners, row by row.
+pub fn rect_cells(start: GridCell, end: GridCell) -> impl Iterator<Item = GridCell> {
+    let GridCell(start_col, start_row) = start;
+    let GridCell(end_col, end_row) = end;
+    let col_range = start_col.min(end_col)..=start_col.max(end_col);
+    let row_range = start_row.min(end_row)..=start_row.max(end_row);
+
+    row_range.flat_map(move |row| col_range.clone().map(move |col| GridCell(col, row)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: [GridCell; 2] = [GridCell(0, 0), GridCell(1, 0)];
+
+    fn picked() -> Vec<GridCell> {
+        vec![GridCell(1, 0), GridCell(2, 0), GridCell(2, 0)]
+    }
+
+    #[test]
+    fn replace_drops_the_previous_selection() {
+        let selection = SelectionMode::Replace.apply(&BASE, picked());
+
+        assert_eq!(selection.as_slice(), &[GridCell(1, 0), GridCell(2, 0)]);
+        assert!(SelectionMode::Replace.apply(&BASE, []).is_empty());
+    }
+
+    #[test]
+    fn add_keeps_the_previous_selection_first() {
+        let selection = SelectionMode::Add.apply(&BASE, picked());
+
+        assert_eq!(
+            selection.as_slice(),
+            &[GridCell(0, 0), GridCell(1, 0), GridCell(2, 0)]
+        );
+    }
+
+    #[test]
+    fn toggle_flips_each_picked_cell() {
+        let selection = SelectionMode::Toggle.apply(&BASE, [GridCell(1, 0), GridCell(2, 0)]);
+
+        assert_eq!(selection.as_slice(), &[GridCell(0, 0), GridCell(2, 0)]);
+        assert_eq!(
+            SelectionMode::Toggle.apply(&selection, selection.iter()),
+            IArray::default()
+        );
+    }
+
+    #[test]
+    fn rect_cells_accepts_corners_in_any_order() {
+        let expected = vec![
+            GridCell(1, 2),
+            GridCell(2, 2),
+            GridCell(3, 2),
+            GridCell(1, 3),
+            GridCell(2, 3),
+            GridCell(3, 3),
+        ];
+
+        assert_eq!(
+            rect_cells(GridCell(1, 2), GridCell(3, 3)).collect::<Vec<_>>(),
+            expected
+        );
+        assert_eq!(
+            rect_cells(GridCell(3, 3), GridCell(1, 2)).collect::<Vec<_>>(),
+            expected
+        );
+        assert_eq!(
+            rect_cells(GridCell(3, 2), GridCell(1, 3)).collect::<Vec<_>>(),
+            expected
+        );
+        assert_eq!(
+            rect_cells(GridCell(4, 4), GridCell(4, 4)).collect::<Vec<_>>(),
+            vec![GridCell(4, 4)]
+        );
+    }
+}
@@ -0,0 +1,196 @@
+use implicit_clone::ImplicitClone;
+use indexmap::{IndexMap, IndexSet};
+
+use super::line::line_cells;
+use super::selection::rect_cells;
+use super::GridCell;
+
+/// A shape dragged out between two cells.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ImplicitClone)]
+pub enum Shape {
+    Line,
+    // Fits the rectangle between the two cells
+    Rectangle { filled: bool },
+    // Fits inside the rectangle between the two cells
+    Ellipse { filled: bool },
+}
+
+impl Shape {
+    /// The cells the shape covers, each once.
+    pub fn cells(self, start: GridCell, end: GridCell) -> Vec<GridCell> {
+        match self {
+            Shape::Line => line_cells(start, end),
+            Shape::Rectangle { filled: true } => rect_cells(start, end).collect(),
+            Shape::Rectangle { filled: false } => rect_outline_cells(start, end),
+            Shape::Ellipse { filled } => {
+                let outline = ellipse_outline_cells(start, end);
+
+                if filled {
+                    filled_rows(&outline)
+                } else {
+                    outline
+                }
+            }
+        }
+    }
+}
+
+fn rect_outline_cells(start: GridCell, end: GridCell) -> Vec<GridCell> {
+    let (min_col, max_col) = (start.0.min(end.0), start.0.max(end.0));
+    let (min_row, max_row) = (start.1.min(end.1), start.1.max(end.1));
+
+    rect_cells(start, end)
+        .filter(|&GridCell(col, row)| {
+            col == min_col || col == max_col || row == min_row || row == max_row
+        })
+        .collect()
+}
+
+// The outline of the ellipse that fits the rectangle between the two cells, using Zingl's
+// midpoint algorithm. It works for even sizes too, where the centre is on a grid line.
+fn ellipse_outline_cells(start: GridCell, end: GridCell) -> Vec<GridCell> {
+    let (mut x0, mut x1) = (start.0.min(end.0) as i64, start.0.max(end.0) as i64);
+    let (min_row, max_row) = (start.1.min(end.1) as i64, start.1.max(end.1) as i64);
+    let a = x1 - x0;
+    let b = max_row - min_row;
+    let b1 = b & 1;
+    let mut dx = 4 * (1 - a) * b * b;
+    let mut dy = 4 * (b1 + 1) * a * a;
+    let mut error = dx + dy + b1 * a * a;
+    let mut y0 = min_row + (b + 1) / 2;
+    let mut y1 = y0 - b1;
+    let a_step = 8 * a * a;
+    let b_step = 8 * b * b;
+    let mut cells: IndexSet<GridCell> = IndexSet::new();
+    let mut push = |col: i64, row: i64| {
+        if let (Ok(col), Ok(row)) = (usize::try_from(col), usize::try_from(row)) {
+            cells.insert(GridCell(col, row));
+        }
+    };
+
+    loop {
+        push(x1, y0);
+        push(x0, y0);
+        push(x0, y1);
+        push(x1, y1);
+
+        let doubled_error = 2 * error;
+
+        if doubled_error <= dy {
+            y0 += 1;
+            y1 -= 1;
+            dy += a_step;
+            error += dy;
+        }
+        if doubled_error >= dx || 2 * error > dy {
+            x0 += 1;
+            x1 -= 1;
+            dx += b_step;
+            error += dx;
+        }
+        if x0 > x1 {
+            break;
+        }
+    }
+
+    // Very flat ellipses stop early, so their tips are finished off
+    while y0 - y1 <= b {
+        push(x0 - 1, y0);
+        push(x1 + 1, y0);
+        push(x0 - 1, y1);
+        push(x1 + 1, y1);
+        y0 += 1;
+        y1 -= 1;
+    }
+
+    cells.into_iter().collect()
+}
+
+// Every cell between the leftmost and rightmost cells of the outline on each row
+fn filled_rows(outline: &[GridCell]) -> Vec<GridCell> {
+    let mut rows: IndexMap<usize, (usize, usize)> = IndexMap::new();
+
+    for &GridCell(col, row) in outline {
+        let (min_col, max_col) = rows.entry(row).or_insert((col, col));
+
+        *min_col = (*min_col).min(col);
+        *max_col = (*max_col).max(col);
+    }
+
+    rows.sort_keys();
+    rows.into_iter()
+        .flat_map(|(row, (min_col, max_col))| {
+            (min_col..=max_col).map(move |col| GridCell(col, row))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::pattern::test_fixtures::sorted;
+
+    #[test]
+    fn rectangle_outline_leaves_the_middle_empty() {
+        let cells = Shape::Rectangle { filled: false }.cells(GridCell(3, 2), GridCell(1, 0));
+
+        assert_eq!(cells.len(), 8);
+        assert!(!cells.contains(&GridCell(2, 1)));
+    }
+
+    #[test]
+    fn filled_rectangle_covers_every_cell() {
+        let cells = Shape::Rectangle { filled: true }.cells(GridCell(0, 0), GridCell(3, 2));
+
+        assert_eq!(cells.len(), 12);
+    }
+
+    #[test]
+    fn small_ellipse_is_a_ring() {
+        let cells = Shape::Ellipse { filled: false }.cells(GridCell(0, 0), GridCell(2, 2));
+
+        assert_eq!(
+            sorted(cells),
+            vec![
+                GridCell(1, 0),
+                GridCell(0, 1),
+                GridCell(2, 1),
+                GridCell(1, 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn ellipse_fits_its_rectangle() {
+        for (end_col, end_row) in [(0, 0), (1, 0), (7, 0), (0, 5), (5, 3), (8, 8), (9, 4)] {
+            let outline =
+                Shape::Ellipse { filled: false }.cells(GridCell(0, 0), GridCell(end_col, end_row));
+
+            // Touches each side of the rectangle without going past it
+            assert_eq!(outline.iter().map(|cell| cell.0).min(), Some(0));
+            assert_eq!(outline.iter().map(|cell| cell.0).max(), Some(end_col));
+            assert_eq!(outline.iter().map(|cell| cell.1).min(), Some(0));
+            assert_eq!(outline.iter().map(|cell| cell.1).max(), Some(end_row));
+        }
+    }
+
+    #[test]
+    fn ellipse_is_symmetric() {
+        let outline = Shape::Ellipse { filled: false }.cells(GridCell(0, 0), GridCell(9, 5));
+
+        for &GridCell(col, row) in outline.iter() {
+            assert!(outline.contains(&GridCell(9 - col, row)));
+            assert!(outline.contains(&GridCell(col, 5 - row)));
+        }
+    }
+
+    #[test]
+    fn filled_ellipse_includes_its_outline() {
+        let outline = Shape::Ellipse { filled: false }.cells(GridCell(0, 0), GridCell(6, 4));
+        let filled = Shape::Ellipse { filled: true }.cells(GridCell(0, 0), GridCell(6, 4));
+
+        assert!(outline.iter().all(|cell| filled.contains(cell)));
+        assert!(filled.contains(&GridCell(3, 2)));
+        assert!(!filled.contains(&GridCell(0, 0)));
+    }
+}
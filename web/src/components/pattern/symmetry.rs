@@ -0,0 +1,443 @@
+use implicit_clone::ImplicitClone;
+use indexmap::{IndexMap, IndexSet};
+
+use super::overlay::MarkerPosition;
+use super::transform::Transform;
+use super::{CellThreads, GridCell, GridPoint, GridSize, ThreadType};
+
+/// How edits are mirrored while drawing.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ImplicitClone)]
+pub enum SymmetryMode {
+    #[default]
+    Off,
+    // Mirrored across a vertical axis, so left and right match
+    Vertical,
+    // Mirrored across a horizontal axis, so top and bottom match
+    Horizontal,
+    Both,
+    // Repeated at each quarter turn around the pivot
+    Rotational4,
+    // Each quarter turn, plus its mirror image
+    Rotational8,
+}
+
+impl SymmetryMode {
+    pub const ALL: [SymmetryMode; 6] = [
+        SymmetryMode::Off,
+        SymmetryMode::Vertical,
+        SymmetryMode::Horizontal,
+        SymmetryMode::Both,
+        SymmetryMode::Rotational4,
+        SymmetryMode::Rotational8,
+    ];
+
+    pub fn as_str(&self) -> String {
+        match &self {
+            SymmetryMode::Off => "Off".to_string(),
+            SymmetryMode::Vertical => "Vertical axis".to_string(),
+            SymmetryMode::Horizontal => "Horizontal axis".to_string(),
+            SymmetryMode::Both => "Both axes".to_string(),
+            SymmetryMode::Rotational4 => "4-way".to_string(),
+            SymmetryMode::Rotational8 => "8-way".to_string(),
+        }
+    }
+
+    // The transforms that take an edit to each of its copies, applied in order. The first copy
+    // is the edit itself.
+    fn images(self) -> &'static [&'static [Transform]] {
+        match self {
+            SymmetryMode::Off => &[&[]],
+            SymmetryMode::Vertical => &[&[], &[Transform::FlipHorizontal]],
+            SymmetryMode::Horizontal => &[&[], &[Transform::FlipVertical]],
+            SymmetryMode::Both => &[
+                &[],
+                &[Transform::FlipHorizontal],
+                &[Transform::FlipVertical],
+                &[Transform::Rotate180],
+            ],
+            SymmetryMode::Rotational4 => &[
+                &[],
+                &[Transform::RotateClockwise],
+                &[Transform::Rotate180],
+                &[Transform::RotateAnticlockwise],
+            ],
+            SymmetryMode::Rotational8 => &[
+                &[],
+                &[Transform::RotateClockwise],
+                &[Transform::Rotate180],
+                &[Transform::RotateAnticlockwise],
+                &[Transform::FlipHorizontal],
+                &[Transform::FlipVertical],
+                &[Transform::FlipHorizontal, Transform::RotateClockwise],
+                &[Transform::FlipHorizontal, Transform::RotateAnticlockwise],
+            ],
+        }
+    }
+}
+
+/// The point edits are mirrored around, in half cells from the top left of the grid. Odd
+/// coordinates are the middle of a cell and even ones are on a grid line.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ImplicitClone)]
+pub struct SymmetryPivot(pub usize, pub usize);
+
+impl SymmetryPivot {
+    /// The middle of the grid.
+    pub fn centre(GridSize(cols, rows): GridSize) -> Self {
+        SymmetryPivot(cols, rows)
+    }
+
+    // Cells only turn onto other cells around a cell centre or a grid intersection, so a pivot
+    // on the edge between two cells is moved up or left half a cell
+    fn turnable(self) -> Self {
+        let SymmetryPivot(x, y) = self;
+
+        if x % 2 == y % 2 {
+            self
+        } else if y > 0 {
+            SymmetryPivot(x, y - 1)
+        } else {
+            SymmetryPivot(x - 1, y)
+        }
+    }
+
+    /// The cell centre or grid intersection closest to the pointer.
+    pub fn nearest(point: GridPoint) -> Self {
+        let GridCell(col, row) = point.grid_cell;
+        let (x, y) = point.offset;
+
+        if (x - 0.5).hypot(y - 0.5) < MarkerPosition::CENTRE_RADIUS {
+            SymmetryPivot(col * 2 + 1, row * 2 + 1)
+        } else {
+            SymmetryPivot(
+                (col + x.round() as usize) * 2,
+                (row + y.round() as usize) * 2,
+            )
+        }
+    }
+
+    /// Where the pivot is, in cells.
+    pub fn position(self) -> (f64, f64) {
+        (self.0 as f64 / 2.0, self.1 as f64 / 2.0)
+    }
+
+    // Moves a point, in half cells, the way `transform` moves it around the pivot
+    fn transformed(self, transform: Transform, (x, y): (isize, isize)) -> (isize, isize) {
+        let (pivot_x, pivot_y) = (self.0 as isize, self.1 as isize);
+        let (dx, dy) = (x - pivot_x, y - pivot_y);
+
+        match transform {
+            Transform::FlipHorizontal => (pivot_x - dx, y),
+            Transform::FlipVertical => (x, pivot_y - dy),
+            Transform::RotateClockwise => (pivot_x - dy, pivot_y + dx),
+            Transform::RotateAnticlockwise => (pivot_x + dy, pivot_y - dx),
+            Transform::Rotate180 => (pivot_x - dx, pivot_y - dy),
+        }
+    }
+}
+
+/// A guide line's start and end points, in cells.
+pub type GuideLine = ((f64, f64), (f64, f64));
+
+/// A symmetry mode and the point it works around.
+#[derive(Clone, Copy, Debug, Default, PartialEq, ImplicitClone)]
+pub struct Symmetry {
+    pub mode: SymmetryMode,
+    // The middle of the grid when not set
+    pub pivot: Option<SymmetryPivot>,
+}
+
+impl Symmetry {
+    pub fn is_off(&self) -> bool {
+        self.mode == SymmetryMode::Off
+    }
+
+    pub fn pivot(&self, size: GridSize) -> SymmetryPivot {
+        let pivot = self.pivot.unwrap_or_else(|| SymmetryPivot::centre(size));
+
+        match self.mode {
+            SymmetryMode::Rotational4 | SymmetryMode::Rotational8 => pivot.turnable(),
+            _ => pivot,
+        }
+    }
+
+    /// The cell and each of its copies, with the transforms that take it there. Copies that
+    /// fall outside of the grid are left out.
+    pub fn images(
+        &self,
+        grid_cell: GridCell,
+        size: GridSize,
+    ) -> Vec<(GridCell, &'static [Transform])> {
+        let pivot = self.pivot(size);
+        let GridCell(col, row) = grid_cell;
+        // The centre of the cell, in half cells
+        let centre = (col as isize * 2 + 1, row as isize * 2 + 1);
+
+        self.mode
+            .images()
+            .iter()
+            .filter_map(|&transforms| {
+                let (x, y) = transforms.iter().fold(centre, |point, &transform| {
+                    pivot.transformed(transform, point)
+                });
+                let grid_cell = GridCell(
+                    usize::try_from((x - 1) / 2).ok()?,
+                    usize::try_from((y - 1) / 2).ok()?,
+                );
+
+                (x > 0 && y > 0 && size.contains(grid_cell)).then_some((grid_cell, transforms))
+            })
+            .collect()
+    }
+
+    /// The cells the edit lands on, each once.
+    pub fn cells(&self, grid_cell: GridCell, size: GridSize) -> Vec<GridCell> {
+        self.images(grid_cell, size)
+            .into_iter()
+            .map(|(grid_cell, _)| grid_cell)
+            .collect::<IndexSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// The threads stitched over a cell, copied to each of its images with their thread types
+    /// turned to match. Copies that land on the same cell are stitched over each other, so a
+    /// half stitch on a mirror axis becomes a full cross.
+    pub fn threads(
+        &self,
+        grid_cell: GridCell,
+        threads: CellThreads,
+        size: GridSize,
+    ) -> Vec<(GridCell, CellThreads)> {
+        let mut copies: IndexMap<GridCell, CellThreads> = IndexMap::new();
+
+        for (image, transforms) in self.images(grid_cell, size) {
+            let threads = transforms
+                .iter()
+                .fold(threads, |threads, transform| transform.threads(threads));
+            let copy = copies.entry(image).or_default();
+
+            *copy = copy.merged(threads);
+        }
+
+        copies.into_iter().collect()
+    }
+
+    /// The thread types taken out of a cell, as they're turned for each of its images, e.g. for
+    /// the eraser.
+    pub fn thread_types(
+        &self,
+        grid_cell: GridCell,
+        thread_types: &[ThreadType],
+        size: GridSize,
+    ) -> Vec<(GridCell, Vec<ThreadType>)> {
+        let mut copies: IndexMap<GridCell, IndexSet<ThreadType>> = IndexMap::new();
+
+        for (image, transforms) in self.images(grid_cell, size) {
+            copies
+                .entry(image)
+                .or_default()
+                .extend(thread_types.iter().map(|&thread_type| {
+                    transforms
+                        .iter()
+                        .fold(thread_type, |thread_type, transform| {
+                            transform.thread_type(thread_type)
+                        })
+                }));
+        }
+
+        copies
+            .into_iter()
+            .map(|(grid_cell, thread_types)| (grid_cell, thread_types.into_iter().collect()))
+            .collect()
+    }
+
+    /// The lines to draw as guides, as start and end points in cells.
+    pub fn axes(&self, GridSize(cols, rows): GridSize) -> Vec<GuideLine> {
+        let (x, y) = self.pivot(GridSize(cols, rows)).position();
+        let (width, height) = (cols as f64, rows as f64);
+        let vertical = ((x, 0.0), (x, height));
+        let horizontal = ((0.0, y), (width, y));
+        // Long enough to cross the whole grid from anywhere on it
+        let reach = width.max(height);
+
+        match self.mode {
+            SymmetryMode::Off => vec![],
+            SymmetryMode::Vertical => vec![vertical],
+            SymmetryMode::Horizontal => vec![horizontal],
+            SymmetryMode::Both | SymmetryMode::Rotational4 => vec![vertical, horizontal],
+            SymmetryMode::Rotational8 => vec![
+                vertical,
+                horizontal,
+                ((x - reach, y - reach), (x + reach, y + reach)),
+                ((x - reach, y + reach), (x + reach, y - reach)),
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::pattern::test_fixtures::RED;
+
+    #[test]
+    fn vertical_axis_mirrors_across_the_middle() {
+        let symmetry = Symmetry {
+            mode: SymmetryMode::Vertical,
+            pivot: None,
+        };
+        let threads = CellThreads::single(ThreadType::SlashForwards, RED);
+
+        assert_eq!(
+            symmetry.threads(GridCell(1, 2), threads, GridSize(6, 4)),
+            vec![
+                (GridCell(1, 2), threads),
+                (
+                    GridCell(4, 2),
+                    CellThreads::single(ThreadType::SlashBackwards, RED)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn cells_on_the_axis_are_not_copied() {
+        let symmetry = Symmetry {
+            mode: SymmetryMode::Both,
+            pivot: None,
+        };
+
+        // The middle column of an odd sized grid is its own mirror image
+        assert_eq!(
+            symmetry.cells(GridCell(2, 0), GridSize(5, 5)),
+            vec![GridCell(2, 0), GridCell(2, 4)]
+        );
+    }
+
+    #[test]
+    fn half_stitches_on_the_axis_become_crosses() {
+        let symmetry = Symmetry {
+            mode: SymmetryMode::Vertical,
+            pivot: None,
+        };
+
+        assert_eq!(
+            symmetry.threads(
+                GridCell(1, 0),
+                CellThreads::single(ThreadType::SlashForwards, RED),
+                GridSize(3, 1)
+            ),
+            vec![(GridCell(1, 0), CellThreads::full_cross(RED))]
+        );
+    }
+
+    #[test]
+    fn erased_thread_types_are_mirrored() {
+        let symmetry = Symmetry {
+            mode: SymmetryMode::Horizontal,
+            pivot: None,
+        };
+
+        assert_eq!(
+            symmetry.thread_types(GridCell(0, 0), &[ThreadType::BorderTop], GridSize(1, 2)),
+            vec![
+                (GridCell(0, 0), vec![ThreadType::BorderTop]),
+                (GridCell(0, 1), vec![ThreadType::BorderBottom]),
+            ]
+        );
+    }
+
+    #[test]
+    fn four_way_turns_thread_types() {
+        let symmetry = Symmetry {
+            mode: SymmetryMode::Rotational4,
+            pivot: Some(SymmetryPivot(4, 4)),
+        };
+        let copies = symmetry.threads(
+            GridCell(0, 0),
+            CellThreads::single(ThreadType::BorderTop, RED),
+            GridSize(4, 4),
+        );
+
+        assert_eq!(
+            copies,
+            vec![
+                (
+                    GridCell(0, 0),
+                    CellThreads::single(ThreadType::BorderTop, RED)
+                ),
+                (
+                    GridCell(3, 0),
+                    CellThreads::single(ThreadType::BorderRight, RED)
+                ),
+                (
+                    GridCell(3, 3),
+                    CellThreads::single(ThreadType::BorderBottom, RED)
+                ),
+                (
+                    GridCell(0, 3),
+                    CellThreads::single(ThreadType::BorderLeft, RED)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn eight_way_fills_every_octant() {
+        let symmetry = Symmetry {
+            mode: SymmetryMode::Rotational8,
+            pivot: None,
+        };
+        let mut cells = symmetry.cells(GridCell(1, 0), GridSize(5, 5));
+
+        cells.sort_by_key(|&GridCell(col, row)| (row, col));
+
+        assert_eq!(
+            cells,
+            vec![
+                GridCell(1, 0),
+                GridCell(3, 0),
+                GridCell(0, 1),
+                GridCell(4, 1),
+                GridCell(0, 3),
+                GridCell(4, 3),
+                GridCell(1, 4),
+                GridCell(3, 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn copies_off_the_grid_are_dropped() {
+        let symmetry = Symmetry {
+            mode: SymmetryMode::Vertical,
+            pivot: Some(SymmetryPivot(2, 0)),
+        };
+
+        assert_eq!(
+            symmetry.cells(GridCell(0, 0), GridSize(6, 1)),
+            vec![GridCell(0, 0), GridCell(1, 0)]
+        );
+        assert_eq!(
+            symmetry.cells(GridCell(4, 0), GridSize(6, 1)),
+            vec![GridCell(4, 0)]
+        );
+    }
+
+    #[test]
+    fn rotations_turn_around_a_cell_or_intersection() {
+        let rotational = Symmetry {
+            mode: SymmetryMode::Rotational4,
+            pivot: None,
+        };
+        let mirrored = Symmetry {
+            mode: SymmetryMode::Both,
+            pivot: None,
+        };
+
+        assert_eq!(rotational.pivot(GridSize(4, 6)), SymmetryPivot(4, 6));
+        assert_eq!(rotational.pivot(GridSize(5, 5)), SymmetryPivot(5, 5));
+        assert_eq!(rotational.pivot(GridSize(5, 6)), SymmetryPivot(5, 5));
+        assert_eq!(mirrored.pivot(GridSize(5, 6)), SymmetryPivot(5, 6));
+    }
+}
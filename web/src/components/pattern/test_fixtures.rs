@@ -0,0 +1,41 @@
+//! Colours and grids shared by the tests of the pattern modules.
+
+use super::{CellGrid, CellThreads, GridCell, GridSize, GridType, Rgb};
+
+pub const RED: Rgb = Rgb { r: 255, g: 0, b: 0 };
+pub const BLUE: Rgb = Rgb { r: 0, g: 0, b: 255 };
+
+/// A grid with `threads` in each of the listed cells and nothing anywhere else.
+pub fn grid_with(size: GridSize, cells: &[(GridCell, CellThreads)]) -> GridType {
+    let mut grid = CellGrid::new(size);
+
+    for &(grid_cell, threads) in cells {
+        grid.set(grid_cell, threads);
+    }
+
+    grid
+}
+
+/// `cells` in row order.
+pub fn sorted(mut cells: Vec<GridCell>) -> Vec<GridCell> {
+    cells.sort_by_key(|&GridCell(col, row)| (row, col));
+    cells
+}
+
+/// A grid with a red full cross in each of `cells`.
+pub fn full_crosses(size: GridSize, cells: &[GridCell]) -> GridType {
+    let threads: Vec<_> = cells
+        .iter()
+        .map(|&grid_cell| (grid_cell, CellThreads::full_cross(RED)))
+        .collect();
+
+    grid_with(size, &threads)
+}
+
+/// The cells with any thread in them, in row order.
+pub fn stitched_cells(grid: &GridType) -> Vec<GridCell> {
+    grid.iter()
+        .filter(|(_, threads)| !threads.is_empty())
+        .map(|(grid_cell, _)| grid_cell)
+        .collect()
+}
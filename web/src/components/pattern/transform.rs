@@ -0,0 +1,505 @@
+use implicit_clone::ImplicitClone;
+use indexmap::IndexMap;
+
+use super::clipboard::selection_origin;
+use super::overlay::{Backstitch, MarkerPosition, Overlay};
+use super::{
+    CellGrid, CellThreads, Corner, GridCell, GridIntersection, GridSize, GridType, Thread,
+    ThreadType,
+};
+
+/// A flip or rotation of a block of cells.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ImplicitClone)]
+pub enum Transform {
+    // Mirrors left and right
+    FlipHorizontal,
+    // Mirrors top and bottom
+    FlipVertical,
+    RotateClockwise,
+    RotateAnticlockwise,
+    Rotate180,
+}
+
+impl Transform {
+    pub const ALL: [Transform; 5] = [
+        Transform::FlipHorizontal,
+        Transform::FlipVertical,
+        Transform::RotateClockwise,
+        Transform::RotateAnticlockwise,
+        Transform::Rotate180,
+    ];
+
+    pub fn as_str(&self) -> String {
+        match &self {
+            Transform::FlipHorizontal => "Flip horizontally".to_string(),
+            Transform::FlipVertical => "Flip vertically".to_string(),
+            Transform::RotateClockwise => "Rotate 90° clockwise".to_string(),
+            Transform::RotateAnticlockwise => "Rotate 90° anticlockwise".to_string(),
+            Transform::Rotate180 => "Rotate 180°".to_string(),
+        }
+    }
+
+    /// Where a corner of a cell ends up.
+    pub fn corner(self, corner: Corner) -> Corner {
+        match (self, corner) {
+            (Transform::FlipHorizontal, Corner::TopLeft) => Corner::TopRight,
+            (Transform::FlipHorizontal, Corner::TopRight) => Corner::TopLeft,
+            (Transform::FlipHorizontal, Corner::BottomRight) => Corner::BottomLeft,
+            (Transform::FlipHorizontal, Corner::BottomLeft) => Corner::BottomRight,
+            (Transform::FlipVertical, Corner::TopLeft) => Corner::BottomLeft,
+            (Transform::FlipVertical, Corner::TopRight) => Corner::BottomRight,
+            (Transform::FlipVertical, Corner::BottomRight) => Corner::TopRight,
+            (Transform::FlipVertical, Corner::BottomLeft) => Corner::TopLeft,
+            (Transform::RotateClockwise, corner) => corner.clockwise(),
+            (Transform::RotateAnticlockwise, corner) => corner.anticlockwise(),
+            (Transform::Rotate180, corner) => corner.clockwise().clockwise(),
+        }
+    }
+
+    /// What a thread turns into once its cell is transformed. Diagonals swap whenever the cell
+    /// is flipped or turned a quarter, and borders and corner stitches move with the corners.
+    pub fn thread_type(self, thread_type: ThreadType) -> ThreadType {
+        let is_mirrored_diagonal = self != Transform::Rotate180;
+
+        match thread_type {
+            ThreadType::SlashBackwards if is_mirrored_diagonal => ThreadType::SlashForwards,
+            ThreadType::SlashForwards if is_mirrored_diagonal => ThreadType::SlashBackwards,
+            ThreadType::SlashBackwards | ThreadType::SlashForwards => thread_type,
+            ThreadType::BorderTop
+            | ThreadType::BorderRight
+            | ThreadType::BorderBottom
+            | ThreadType::BorderLeft => {
+                // A border is the edge between two corners, so it goes where they go
+                let (start, end) = border_corners(thread_type);
+
+                border_between(self.corner(start), self.corner(end))
+            }
+            ThreadType::QuarterTopLeft
+            | ThreadType::QuarterTopRight
+            | ThreadType::QuarterBottomRight
+            | ThreadType::QuarterBottomLeft => {
+                ThreadType::quarter(self.corner(thread_type.corner().unwrap()))
+            }
+            ThreadType::ThreeQuarterTopLeft
+            | ThreadType::ThreeQuarterTopRight
+            | ThreadType::ThreeQuarterBottomRight
+            | ThreadType::ThreeQuarterBottomLeft => {
+                ThreadType::three_quarter(self.corner(thread_type.corner().unwrap()))
+            }
+        }
+    }
+
+    pub fn threads(self, threads: CellThreads) -> CellThreads {
+        threads
+            .iter()
+            .map(|thread| Thread {
+                thread_type: self.thread_type(thread.thread_type),
+                ..thread
+            })
+            .collect()
+    }
+
+    /// The size of a block of cells once it's transformed.
+    pub fn size(self, size: GridSize) -> GridSize {
+        let GridSize(cols, rows) = size;
+
+        match self {
+            Transform::RotateClockwise | Transform::RotateAnticlockwise => GridSize(rows, cols),
+            _ => size,
+        }
+    }
+
+    /// Where a cell of a block of `size` cells ends up.
+    pub fn cell(self, GridCell(col, row): GridCell, GridSize(cols, rows): GridSize) -> GridCell {
+        match self {
+            Transform::FlipHorizontal => GridCell(cols - 1 - col, row),
+            Transform::FlipVertical => GridCell(col, rows - 1 - row),
+            Transform::RotateClockwise => GridCell(rows - 1 - row, col),
+            Transform::RotateAnticlockwise => GridCell(row, cols - 1 - col),
+            Transform::Rotate180 => GridCell(cols - 1 - col, rows - 1 - row),
+        }
+    }
+
+    /// Where a grid intersection of a block of `size` cells ends up.
+    pub fn intersection(
+        self,
+        GridIntersection(col, row): GridIntersection,
+        GridSize(cols, rows): GridSize,
+    ) -> GridIntersection {
+        // There's one more line than there are cells across and down
+        let GridCell(col, row) = self.cell(GridCell(col, row), GridSize(cols + 1, rows + 1));
+
+        GridIntersection(col, row)
+    }
+}
+
+// The corners at each end of a border, going clockwise
+fn border_corners(thread_type: ThreadType) -> (Corner, Corner) {
+    match thread_type {
+        ThreadType::BorderTop => (Corner::TopLeft, Corner::TopRight),
+        ThreadType::BorderRight => (Corner::TopRight, Corner::BottomRight),
+        ThreadType::BorderBottom => (Corner::BottomRight, Corner::BottomLeft),
+        _ => (Corner::BottomLeft, Corner::TopLeft),
+    }
+}
+
+fn border_between(a: Corner, b: Corner) -> ThreadType {
+    [
+        ThreadType::BorderTop,
+        ThreadType::BorderRight,
+        ThreadType::BorderBottom,
+        ThreadType::BorderLeft,
+    ]
+    .into_iter()
+    .find(|&border| {
+        let (start, end) = border_corners(border);
+
+        (start, end) == (a, b) || (start, end) == (b, a)
+    })
+    .unwrap()
+}
+
+/// The whole grid transformed. Rotations swap the number of columns and rows.
+pub fn transform_grid(grid: &GridType, transform: Transform) -> GridType {
+    let size = grid.size();
+    let mut transformed = CellGrid::new(transform.size(size));
+
+    for (grid_cell, threads) in grid.iter().filter(|(_, threads)| !threads.is_empty()) {
+        transformed.set(transform.cell(grid_cell, size), transform.threads(threads));
+    }
+
+    transformed
+}
+
+/// The overlay of a grid of `size` cells, transformed along with the grid.
+pub fn transform_overlay(overlay: &Overlay, size: GridSize, transform: Transform) -> Overlay {
+    let mut transformed: Overlay = overlay
+        .markers()
+        .map(|(position, marker)| {
+            let position = match position {
+                MarkerPosition::Centre(grid_cell) => {
+                    MarkerPosition::Centre(transform.cell(grid_cell, size))
+                }
+                MarkerPosition::Corner(intersection) => {
+                    MarkerPosition::Corner(transform.intersection(intersection, size))
+                }
+            };
+
+            (position, marker)
+        })
+        .collect();
+
+    for (index, backstitch) in overlay.backstitches().enumerate() {
+        transformed.insert_backstitch(
+            index,
+            Backstitch {
+                points: backstitch
+                    .points
+                    .iter()
+                    .map(|point| transform.intersection(point, size))
+                    .collect(),
+                color: backstitch.color,
+            },
+        );
+    }
+
+    transformed
+}
+
+/// Transforms the selected cells within the rectangle around them, which keeps its top left
+/// corner where it is. Returns the cells that change, with their new threads, and the cells the
+/// selection covers afterwards. Anything moved off the grid is dropped.
+pub fn transform_selection(
+    grid: &GridType,
+    selected_cells: &[GridCell],
+    transform: Transform,
+) -> (Vec<(GridCell, CellThreads)>, Vec<GridCell>) {
+    let Some(GridCell(min_col, min_row)) = selection_origin(selected_cells) else {
+        return (vec![], vec![]);
+    };
+    let bounds = selected_cells.iter().fold(
+        GridSize(1, 1),
+        |GridSize(cols, rows), &GridCell(col, row)| {
+            GridSize(cols.max(col - min_col + 1), rows.max(row - min_row + 1))
+        },
+    );
+    let size = grid.size();
+    let moved: Vec<(GridCell, CellThreads)> = selected_cells
+        .iter()
+        .filter_map(|&grid_cell| {
+            let threads = grid.get(grid_cell)?;
+            let GridCell(col, row) = transform.cell(
+                GridCell(grid_cell.0 - min_col, grid_cell.1 - min_row),
+                bounds,
+            );
+
+            Some((
+                GridCell(min_col + col, min_row + row),
+                transform.threads(threads),
+            ))
+        })
+        .filter(|&(grid_cell, _)| size.contains(grid_cell))
+        .collect();
+    // The cells are cleared first so the ones moved onto them win
+    let edits: IndexMap<GridCell, CellThreads> = selected_cells
+        .iter()
+        .map(|&grid_cell| (grid_cell, CellThreads::default()))
+        .chain(moved.iter().copied())
+        .collect();
+
+    (
+        edits.into_iter().collect(),
+        moved.into_iter().map(|(grid_cell, _)| grid_cell).collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::pattern::overlay::{Marker, MarkerKind};
+    use crate::components::pattern::test_fixtures::{BLUE, RED};
+
+    // The transform that undoes another
+    fn inverse(transform: Transform) -> Transform {
+        match transform {
+            Transform::RotateClockwise => Transform::RotateAnticlockwise,
+            Transform::RotateAnticlockwise => Transform::RotateClockwise,
+            transform => transform,
+        }
+    }
+
+    #[test]
+    fn flips_swap_diagonals() {
+        for transform in [Transform::FlipHorizontal, Transform::FlipVertical] {
+            assert_eq!(
+                transform.thread_type(ThreadType::SlashForwards),
+                ThreadType::SlashBackwards
+            );
+            assert_eq!(
+                transform.thread_type(ThreadType::SlashBackwards),
+                ThreadType::SlashForwards
+            );
+        }
+    }
+
+    #[test]
+    fn flips_swap_opposite_borders() {
+        let flip = Transform::FlipHorizontal;
+
+        assert_eq!(
+            flip.thread_type(ThreadType::BorderLeft),
+            ThreadType::BorderRight
+        );
+        assert_eq!(
+            flip.thread_type(ThreadType::BorderRight),
+            ThreadType::BorderLeft
+        );
+        assert_eq!(
+            flip.thread_type(ThreadType::BorderTop),
+            ThreadType::BorderTop
+        );
+
+        let flip = Transform::FlipVertical;
+
+        assert_eq!(
+            flip.thread_type(ThreadType::BorderTop),
+            ThreadType::BorderBottom
+        );
+        assert_eq!(
+            flip.thread_type(ThreadType::BorderLeft),
+            ThreadType::BorderLeft
+        );
+    }
+
+    #[test]
+    fn flips_mirror_corner_stitches() {
+        assert_eq!(
+            Transform::FlipHorizontal.thread_type(ThreadType::QuarterTopLeft),
+            ThreadType::QuarterTopRight
+        );
+        assert_eq!(
+            Transform::FlipVertical.thread_type(ThreadType::ThreeQuarterTopRight),
+            ThreadType::ThreeQuarterBottomRight
+        );
+    }
+
+    #[test]
+    fn rotations_turn_thread_types() {
+        let rotate = Transform::RotateClockwise;
+
+        assert_eq!(
+            rotate.thread_type(ThreadType::SlashForwards),
+            ThreadType::SlashBackwards
+        );
+        assert_eq!(
+            rotate.thread_type(ThreadType::BorderTop),
+            ThreadType::BorderRight
+        );
+        assert_eq!(
+            rotate.thread_type(ThreadType::BorderLeft),
+            ThreadType::BorderTop
+        );
+        assert_eq!(
+            rotate.thread_type(ThreadType::QuarterBottomLeft),
+            ThreadType::QuarterTopLeft
+        );
+        assert_eq!(
+            Transform::Rotate180.thread_type(ThreadType::SlashForwards),
+            ThreadType::SlashForwards
+        );
+        assert_eq!(
+            Transform::Rotate180.thread_type(ThreadType::BorderTop),
+            ThreadType::BorderBottom
+        );
+    }
+
+    #[test]
+    fn inverse_restores_every_thread_type() {
+        for transform in Transform::ALL {
+            for thread_type in ThreadType::ALL {
+                assert_eq!(
+                    inverse(transform).thread_type(transform.thread_type(thread_type)),
+                    thread_type,
+                    "{:?} of {:?}",
+                    transform,
+                    thread_type
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn four_quarter_turns_are_no_turn() {
+        for thread_type in ThreadType::ALL {
+            let turned = (0..4).fold(thread_type, |thread_type, _| {
+                Transform::RotateClockwise.thread_type(thread_type)
+            });
+
+            assert_eq!(turned, thread_type);
+        }
+    }
+
+    #[test]
+    fn rotating_a_grid_swaps_its_size() {
+        let mut grid = CellGrid::new(GridSize(3, 2));
+
+        grid.set(
+            GridCell(0, 0),
+            CellThreads::single(ThreadType::BorderTop, RED),
+        );
+        grid.set(GridCell(2, 1), CellThreads::full_cross(BLUE));
+
+        let rotated = transform_grid(&grid, Transform::RotateClockwise);
+
+        assert_eq!(rotated.size(), GridSize(2, 3));
+        assert_eq!(
+            rotated.get(GridCell(1, 0)),
+            Some(CellThreads::single(ThreadType::BorderRight, RED))
+        );
+        assert_eq!(
+            rotated.get(GridCell(0, 2)),
+            Some(CellThreads::full_cross(BLUE))
+        );
+        assert_eq!(
+            transform_grid(&rotated, Transform::RotateAnticlockwise),
+            grid
+        );
+    }
+
+    #[test]
+    fn flipping_moves_the_overlay() {
+        let size = GridSize(4, 3);
+        let marker = Marker {
+            kind: MarkerKind::FrenchKnot,
+            color: RED,
+        };
+        let mut overlay: Overlay = [
+            (MarkerPosition::Centre(GridCell(0, 1)), marker),
+            (MarkerPosition::Corner(GridIntersection(0, 0)), marker),
+        ]
+        .into_iter()
+        .collect();
+
+        overlay.insert_backstitch(
+            0,
+            Backstitch {
+                points: [GridIntersection(0, 0), GridIntersection(1, 3)]
+                    .into_iter()
+                    .collect(),
+                color: BLUE,
+            },
+        );
+
+        let flipped = transform_overlay(&overlay, size, Transform::FlipHorizontal);
+
+        assert_eq!(
+            flipped.marker(MarkerPosition::Centre(GridCell(3, 1))),
+            Some(marker)
+        );
+        assert_eq!(
+            flipped.marker(MarkerPosition::Corner(GridIntersection(4, 0))),
+            Some(marker)
+        );
+        assert_eq!(
+            flipped.backstitch(0).unwrap().points.to_vec(),
+            vec![GridIntersection(4, 0), GridIntersection(3, 3)]
+        );
+    }
+
+    #[test]
+    fn selection_is_flipped_in_place() {
+        let mut grid = CellGrid::new(GridSize(5, 5));
+
+        grid.set(
+            GridCell(1, 1),
+            CellThreads::single(ThreadType::SlashForwards, RED),
+        );
+
+        let selected_cells = [GridCell(1, 1), GridCell(2, 1), GridCell(3, 1)];
+        let (edits, selection) =
+            transform_selection(&grid, &selected_cells, Transform::FlipHorizontal);
+
+        assert_eq!(
+            edits,
+            vec![
+                (GridCell(1, 1), CellThreads::default()),
+                (GridCell(2, 1), CellThreads::default()),
+                (
+                    GridCell(3, 1),
+                    CellThreads::single(ThreadType::SlashBackwards, RED)
+                ),
+            ]
+        );
+        assert_eq!(
+            selection,
+            vec![GridCell(3, 1), GridCell(2, 1), GridCell(1, 1)]
+        );
+    }
+
+    #[test]
+    fn rotated_selection_keeps_its_top_left_corner() {
+        let mut grid = CellGrid::new(GridSize(5, 5));
+
+        grid.set(GridCell(3, 2), CellThreads::full_cross(RED));
+
+        // A row of three cells becomes a column of three
+        let selected_cells = [GridCell(1, 2), GridCell(2, 2), GridCell(3, 2)];
+        let (edits, selection) =
+            transform_selection(&grid, &selected_cells, Transform::RotateClockwise);
+        let mut rotated = grid.clone();
+
+        for (grid_cell, threads) in edits {
+            rotated.set(grid_cell, threads);
+        }
+
+        assert_eq!(
+            selection,
+            vec![GridCell(1, 2), GridCell(1, 3), GridCell(1, 4)]
+        );
+        assert_eq!(
+            rotated.get(GridCell(1, 4)),
+            Some(CellThreads::full_cross(RED))
+        );
+        assert_eq!(rotated.get(GridCell(3, 2)), Some(CellThreads::default()));
+    }
+}
@@ -1,6 +1,7 @@
 mod components;
 mod pages;
 mod router;
+mod store;
 
 use router::AppRouter;
 use yew::prelude::*;
@@ -1,17 +1,28 @@
 pub mod components;
 pub mod containers;
+pub mod history;
 pub mod reducer;
 
+use serde::{Deserialize, Serialize};
 use yew::prelude::*;
 
 use crate::components::header::Header;
 
 use containers::editor::PatternEditor;
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
 pub enum PatternEditorFeature {
     Brush,
+    Eraser,
     Fill,
+    // Shapes are dragged out from one cell to another
+    Line,
+    Rectangle,
+    Ellipse,
+    // Places French knots and beads
+    Marker,
+    // Draws backstitch lines between grid intersections
+    Backstitch,
     Pointer,
 }
 
@@ -1,2 +1,7 @@
+pub mod canvas_form;
+pub mod chart_export_form;
+pub mod form;
+pub mod image_export_form;
+pub mod image_import_form;
 pub mod pattern_cell_editor;
 pub mod toolbar;
@@ -0,0 +1,153 @@
+use log::error;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::components::pattern::resize::{Anchor, Axis, CanvasEdit, ResizeMode};
+use crate::components::pattern::GridSize;
+
+#[derive(Properties, PartialEq)]
+pub struct CanvasFormProps {
+    pub size: GridSize,
+    pub on_edit: Callback<CanvasEdit>,
+}
+
+/// Resizes the canvas and inserts or deletes rows and columns.
+#[function_component(CanvasForm)]
+pub fn canvas_form(props: &CanvasFormProps) -> Html {
+    let CanvasFormProps { size, on_edit } = props;
+    let GridSize(cols, rows) = *size;
+    let colnum_ref = use_node_ref();
+    let rownum_ref = use_node_ref();
+    // Counted from 1, as rows and columns are numbered on the chart
+    let index_ref = use_node_ref();
+    let anchor_handle = use_state(Anchor::default);
+    let mode_handle = use_state(ResizeMode::default);
+
+    let handle_submit = {
+        let colnum_ref = colnum_ref.clone();
+        let rownum_ref = rownum_ref.clone();
+        let on_edit = on_edit.clone();
+        let anchor = *anchor_handle;
+        let mode = *mode_handle;
+
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            if let (Some(col_input), Some(row_input)) = (
+                colnum_ref.cast::<HtmlInputElement>(),
+                rownum_ref.cast::<HtmlInputElement>(),
+            ) {
+                if let (Ok(col_num), Ok(row_num)) = (
+                    col_input.value().parse::<usize>(),
+                    row_input.value().parse::<usize>(),
+                ) {
+                    on_edit.emit(CanvasEdit::Resize {
+                        size: GridSize(col_num, row_num),
+                        anchor,
+                        mode,
+                    });
+                } else {
+                    error!("Unable to convert inputs values to usize");
+                }
+            } else {
+                error!("Unable to find input elements");
+            }
+        })
+    };
+
+    html! {<>
+        <form onsubmit={handle_submit}>
+            <label>
+                { "Rows" }
+                <input ref={rownum_ref} type="text" name="rows" value={rows.to_string()} />
+            </label>
+            <label>
+                { "Cols" }
+                <input ref={colnum_ref} type="text" name="cols" value={cols.to_string()} />
+            </label>
+            <div>
+                { "Anchor" }
+                {
+                    for Anchor::ALL.chunks(3).map(|anchors| html! {
+                        <div>
+                            {
+                                for anchors.iter().map(|&anchor| html! {
+                                    <input
+                                        type="radio"
+                                        name="resize_anchor"
+                                        title={anchor.as_str()}
+                                        checked={anchor == *anchor_handle}
+                                        onchange={create_handle_anchor_change(anchor, anchor_handle.clone())}
+                                    />
+                                })
+                            }
+                        </div>
+                    })
+                }
+            </div>
+            <div>
+                {
+                    for ResizeMode::ALL.iter().map(|&mode| html! {
+                        <label>
+                            <input
+                                type="radio"
+                                name="resize_mode"
+                                checked={mode == *mode_handle}
+                                onchange={create_handle_mode_change(mode, mode_handle.clone())}
+                            />
+                            { mode.as_str() }
+                        </label>
+                    })
+                }
+            </div>
+            <div>
+                <button type="submit">{ "Resize" }</button>
+            </div>
+        </form>
+        <div>
+            <label>
+                { "Row or column" }
+                <input ref={index_ref.clone()} type="number" name="line_index" min="1" value="1" />
+            </label>
+            <button onclick={create_handle_line_edit(CanvasEdit::Insert, Axis::Row, index_ref.clone(), on_edit.clone())}>{ "Insert row" }</button>
+            <button onclick={create_handle_line_edit(CanvasEdit::Delete, Axis::Row, index_ref.clone(), on_edit.clone())} disabled={rows == 0}>{ "Delete row" }</button>
+            <button onclick={create_handle_line_edit(CanvasEdit::Insert, Axis::Column, index_ref.clone(), on_edit.clone())}>{ "Insert column" }</button>
+            <button onclick={create_handle_line_edit(CanvasEdit::Delete, Axis::Column, index_ref.clone(), on_edit.clone())} disabled={cols == 0}>{ "Delete column" }</button>
+        </div>
+    </>}
+}
+
+fn create_handle_anchor_change(
+    anchor: Anchor,
+    anchor_handle: UseStateHandle<Anchor>,
+) -> Callback<Event> {
+    Callback::from(move |_: Event| {
+        anchor_handle.set(anchor);
+    })
+}
+
+fn create_handle_mode_change(
+    mode: ResizeMode,
+    mode_handle: UseStateHandle<ResizeMode>,
+) -> Callback<Event> {
+    Callback::from(move |_: Event| {
+        mode_handle.set(mode);
+    })
+}
+
+fn create_handle_line_edit(
+    edit: fn(Axis, usize) -> CanvasEdit,
+    axis: Axis,
+    index_ref: NodeRef,
+    callback: Callback<CanvasEdit>,
+) -> Callback<MouseEvent> {
+    Callback::from(move |_: MouseEvent| {
+        let index = index_ref
+            .cast::<HtmlInputElement>()
+            .and_then(|input| input.value().parse::<usize>().ok())
+            .and_then(|number| number.checked_sub(1));
+
+        if let Some(index) = index {
+            callback.emit(edit(axis, index));
+        }
+    })
+}
@@ -0,0 +1,59 @@
+use log::error;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::components::pattern::chart::ChartOptions;
+use crate::components::pattern::GridSize;
+
+#[derive(Properties, PartialEq)]
+pub struct ChartExportFormProps {
+    pub on_export: Callback<ChartOptions>,
+}
+
+/// Saves a printable symbol chart as one HTML file with an SVG drawing per page.
+#[function_component(ChartExportForm)]
+pub fn chart_export_form(props: &ChartExportFormProps) -> Html {
+    let ChartExportFormProps { on_export } = props;
+    let defaults = ChartOptions::default();
+    let GridSize(page_cols, page_rows) = defaults.page_size;
+    let colnum_ref = use_node_ref();
+    let rownum_ref = use_node_ref();
+
+    let handle_submit = {
+        let colnum_ref = colnum_ref.clone();
+        let rownum_ref = rownum_ref.clone();
+        let on_export = on_export.clone();
+
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+
+            let number = |node_ref: &NodeRef| {
+                node_ref
+                    .cast::<HtmlInputElement>()
+                    .and_then(|input| input.value().parse::<usize>().ok())
+            };
+
+            match (number(&colnum_ref), number(&rownum_ref)) {
+                (Some(cols), Some(rows)) => on_export.emit(ChartOptions {
+                    page_size: GridSize(cols, rows),
+                    ..defaults
+                }),
+                _ => error!("Unable to read the chart page size"),
+            }
+        })
+    };
+
+    html! {
+        <form onsubmit={handle_submit}>
+            <label>
+                { "Rows per page" }
+                <input ref={rownum_ref} type="number" name="chart_rows" min="1" value={page_rows.to_string()} />
+            </label>
+            <label>
+                { "Cols per page" }
+                <input ref={colnum_ref} type="number" name="chart_cols" min="1" value={page_cols.to_string()} />
+            </label>
+            <button type="submit">{ "Export chart" }</button>
+        </form>
+    }
+}
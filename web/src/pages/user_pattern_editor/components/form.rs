@@ -0,0 +1,11 @@
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+/// Keeps `handle` in step with a checkbox.
+pub fn create_handle_toggle(handle: UseStateHandle<bool>) -> Callback<Event> {
+    Callback::from(move |e: Event| {
+        if let Some(input) = e.target_dyn_into::<HtmlInputElement>() {
+            handle.set(input.checked());
+        }
+    })
+}
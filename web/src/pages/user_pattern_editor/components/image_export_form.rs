@@ -0,0 +1,74 @@
+use log::error;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::components::pattern::grid::ImageExportOptions;
+
+use super::form::create_handle_toggle;
+
+#[derive(Properties, PartialEq)]
+pub struct ImageExportFormProps {
+    pub on_export: Callback<ImageExportOptions>,
+}
+
+/// Saves the pattern as a PNG at a chosen cell size.
+#[function_component(ImageExportForm)]
+pub fn image_export_form(props: &ImageExportFormProps) -> Html {
+    let ImageExportFormProps { on_export } = props;
+    let defaults = ImageExportOptions::default();
+    let cell_size_ref = use_node_ref();
+    let grid_lines_handle = use_state(|| defaults.grid_lines);
+    let numbering_handle = use_state(|| defaults.numbering);
+
+    let handle_submit = {
+        let cell_size_ref = cell_size_ref.clone();
+        let on_export = on_export.clone();
+        let grid_lines = *grid_lines_handle;
+        let numbering = *numbering_handle;
+
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+
+            match cell_size_ref
+                .cast::<HtmlInputElement>()
+                .and_then(|input| input.value().parse::<f64>().ok())
+            {
+                Some(cell_size) => on_export.emit(ImageExportOptions {
+                    cell_size,
+                    grid_lines,
+                    numbering,
+                    ..defaults
+                }),
+                None => error!("Unable to read the cell size"),
+            }
+        })
+    };
+
+    html! {
+        <form onsubmit={handle_submit}>
+            <label>
+                { "Cell size (px)" }
+                <input ref={cell_size_ref} type="number" name="export_cell_size" min="1" max="100" value={defaults.cell_size.to_string()} />
+            </label>
+            <label>
+                <input
+                    type="checkbox"
+                    name="export_grid_lines"
+                    checked={*grid_lines_handle}
+                    onchange={create_handle_toggle(grid_lines_handle.clone())}
+                />
+                { "Grid lines" }
+            </label>
+            <label>
+                <input
+                    type="checkbox"
+                    name="export_numbering"
+                    checked={*numbering_handle}
+                    onchange={create_handle_toggle(numbering_handle.clone())}
+                />
+                { "Row and column numbers" }
+            </label>
+            <button type="submit">{ "Export PNG" }</button>
+        </form>
+    }
+}
@@ -0,0 +1,204 @@
+use gloo::events::EventListener;
+use gloo::file::{File, ObjectUrl};
+use log::error;
+use wasm_bindgen::JsCast;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, HtmlImageElement, HtmlInputElement};
+use yew::prelude::*;
+
+use crate::components::pattern::image_import::{
+    import_image, ImageImportOptions, Quantizer, RgbaImage,
+};
+use crate::components::pattern::palette::Palette;
+use crate::components::pattern::{GridSize, GridType};
+
+use super::form::create_handle_toggle;
+
+const DEFAULT_COLORS: usize = 16;
+
+#[derive(Properties, PartialEq)]
+pub struct ImageImportFormProps {
+    pub size: GridSize,
+    pub on_import: Callback<GridType>,
+}
+
+/// Turns a PNG or JPEG into a pattern of full cross stitches.
+#[function_component(ImageImportForm)]
+pub fn image_import_form(props: &ImageImportFormProps) -> Html {
+    let ImageImportFormProps { size, on_import } = props;
+    let GridSize(cols, rows) = *size;
+    let file_ref = use_node_ref();
+    let colnum_ref = use_node_ref();
+    let rownum_ref = use_node_ref();
+    let colors_ref = use_node_ref();
+    let quantizer_handle = use_state(Quantizer::default);
+    let snap_to_palette_handle = use_state(|| true);
+    let dither_handle = use_state(|| false);
+    // Kept alive until the next image is imported, as it can't be dropped from its own listener
+    let loading_ref = use_mut_ref(|| None::<(ObjectUrl, EventListener, EventListener)>);
+
+    let handle_submit = {
+        let file_ref = file_ref.clone();
+        let colnum_ref = colnum_ref.clone();
+        let rownum_ref = rownum_ref.clone();
+        let colors_ref = colors_ref.clone();
+        let on_import = on_import.clone();
+        let quantizer = *quantizer_handle;
+        let palette = (*snap_to_palette_handle).then_some(Palette::Dmc);
+        let dither = *dither_handle;
+
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+
+            let file = file_ref
+                .cast::<HtmlInputElement>()
+                .and_then(|input| input.files())
+                .and_then(|files| files.get(0));
+            let number = |node_ref: &NodeRef| {
+                node_ref
+                    .cast::<HtmlInputElement>()
+                    .and_then(|input| input.value().parse::<usize>().ok())
+            };
+            let (Some(file), Some(cols), Some(rows), Some(colors)) = (
+                file,
+                number(&colnum_ref),
+                number(&rownum_ref),
+                number(&colors_ref),
+            ) else {
+                error!("Unable to read the image import form");
+                return;
+            };
+            let options = ImageImportOptions {
+                size: GridSize(cols, rows),
+                colors,
+                quantizer,
+                palette,
+                dither,
+            };
+            let on_import = on_import.clone();
+
+            *loading_ref.borrow_mut() = load_image(File::from(file), move |image| {
+                match image.map(|image| import_image(&image, options)) {
+                    Some(Ok(grid)) => on_import.emit(grid),
+                    Some(Err(err)) => error!("{}", err),
+                    None => error!("Unable to read the image's pixels"),
+                }
+            });
+        })
+    };
+
+    html! {
+        <form onsubmit={handle_submit}>
+            <label>
+                { "Image" }
+                <input ref={file_ref} type="file" name="image" accept="image/png,image/jpeg" />
+            </label>
+            <label>
+                { "Rows" }
+                <input ref={rownum_ref} type="text" name="import_rows" value={rows.to_string()} />
+            </label>
+            <label>
+                { "Cols" }
+                <input ref={colnum_ref} type="text" name="import_cols" value={cols.to_string()} />
+            </label>
+            <label>
+                { "Colours" }
+                <input ref={colors_ref} type="number" name="import_colors" min="1" value={DEFAULT_COLORS.to_string()} />
+            </label>
+            {
+                for Quantizer::ALL.iter().map(|&quantizer| html! {
+                    <label>
+                        <input
+                            type="radio"
+                            name="import_quantizer"
+                            checked={quantizer == *quantizer_handle}
+                            onchange={create_handle_quantizer_change(quantizer, quantizer_handle.clone())}
+                        />
+                        { quantizer.as_str() }
+                    </label>
+                })
+            }
+            <label>
+                <input
+                    type="checkbox"
+                    name="import_snap_to_palette"
+                    checked={*snap_to_palette_handle}
+                    onchange={create_handle_toggle(snap_to_palette_handle.clone())}
+                />
+                { format!("Match {} floss", Palette::Dmc.as_str()) }
+            </label>
+            <label>
+                <input
+                    type="checkbox"
+                    name="import_dither"
+                    checked={*dither_handle}
+                    onchange={create_handle_toggle(dither_handle.clone())}
+                />
+                { "Dither" }
+            </label>
+            <button type="submit">{ "Import image" }</button>
+        </form>
+    }
+}
+
+fn create_handle_quantizer_change(
+    quantizer: Quantizer,
+    quantizer_handle: UseStateHandle<Quantizer>,
+) -> Callback<Event> {
+    Callback::from(move |_: Event| {
+        quantizer_handle.set(quantizer);
+    })
+}
+
+// Lets the browser decode the file, then reads its pixels back from an offscreen canvas. The
+// returned URL and listeners have to be kept alive until the image has loaded.
+fn load_image(
+    file: File,
+    on_load: impl FnOnce(Option<RgbaImage>) + 'static,
+) -> Option<(ObjectUrl, EventListener, EventListener)> {
+    let image = HtmlImageElement::new().ok()?;
+    let url = ObjectUrl::from(file);
+    let load_listener = {
+        let loaded = image.clone();
+
+        EventListener::once(&image, "load", move |_| on_load(read_pixels(&loaded)))
+    };
+    // Files the browser can't decode fire this instead of `load`
+    let error_listener = EventListener::once(&image, "error", |_| {
+        error!("Unable to decode the image");
+    });
+
+    image.set_src(&url);
+
+    Some((url, load_listener, error_listener))
+}
+
+fn read_pixels(image: &HtmlImageElement) -> Option<RgbaImage> {
+    let (width, height) = (image.natural_width(), image.natural_height());
+    let canvas = gloo::utils::document()
+        .create_element("canvas")
+        .ok()?
+        .dyn_into::<HtmlCanvasElement>()
+        .ok()?;
+
+    canvas.set_width(width);
+    canvas.set_height(height);
+
+    let ctx = canvas
+        .get_context("2d")
+        .ok()??
+        .dyn_into::<CanvasRenderingContext2d>()
+        .ok()?;
+
+    ctx.draw_image_with_html_image_element(image, 0.0, 0.0)
+        .ok()?;
+
+    let data = ctx
+        .get_image_data(0.0, 0.0, width as f64, height as f64)
+        .ok()?;
+
+    Some(RgbaImage {
+        width: width as usize,
+        height: height as usize,
+        data: data.data().0,
+    })
+}
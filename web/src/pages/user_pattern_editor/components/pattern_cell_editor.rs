@@ -1,160 +1,325 @@
-use implicit_clone::sync::IArray;
 use log::error;
-use web_sys::HtmlInputElement;
+use web_sys::HtmlSelectElement;
 use yew::prelude::*;
 
-use crate::components::pattern::{GridCell, Rgb, Thread, ThreadType};
+use crate::components::pattern::overlay::{Marker, MarkerKind, MarkerPosition, Overlay};
+use crate::components::pattern::palette::{Palette, PaletteEntryId};
+use crate::components::pattern::{
+    CellThreads, Corner, GridCell, GridIntersection, Rgb, Thread, ThreadType,
+};
 
 fn create_on_change_event(
     thread_type: ThreadType,
-    thread_cell: (GridCell, IArray<Thread>),
-    callback: &Callback<(GridCell, IArray<Thread>)>,
+    palette: Palette,
+    callback: &Callback<(ThreadType, PaletteEntryId)>,
 ) -> Callback<Event> {
     let callback = callback.clone();
-    let (GridCell(col_index, row_index), threads) = thread_cell;
 
     Callback::from(move |e: Event| {
-        let input = e.target_dyn_into::<HtmlInputElement>();
-
-        if let Some(input) = input {
-            let value = input.value();
-            let color = Rgb::from_hex(value.clone());
+        if let Some(select) = e.target_dyn_into::<HtmlSelectElement>() {
+            match palette.find(&select.value()) {
+                Some(entry) => callback.emit((thread_type, entry)),
+                None => error!("Unable to find thread {}", select.value()),
+            }
+        }
+    })
+}
 
-            match color {
-                Ok(color) => {
-                    if !value.is_empty() {
-                        let thread_type_exists =
-                            threads.iter().any(|t| t.thread_type == thread_type);
+fn create_on_marker_change_event(
+    position: MarkerPosition,
+    marker: Option<Marker>,
+    callback: &Callback<(MarkerPosition, Option<Marker>)>,
+) -> Callback<MouseEvent> {
+    let callback = callback.clone();
 
-                        if thread_type_exists {
-                            let threads: Vec<Thread> = threads
-                                .iter()
-                                .map(|t| {
-                                    if t.thread_type == thread_type {
-                                        Thread::new(color, thread_type)
-                                    } else {
-                                        t
-                                    }
-                                })
-                                .collect();
+    Callback::from(move |_: MouseEvent| {
+        callback.emit((position, marker));
+    })
+}
 
-                            let updated_thread_cell: (GridCell, IArray<Thread>) =
-                                (GridCell(col_index, row_index), IArray::from(threads));
-                            callback.emit(updated_thread_cell);
-                        } else {
-                            let mut threads: Vec<Thread> = vec![Thread::new(color, thread_type)];
-                            threads.extend(threads.to_vec());
+fn create_on_marker_color_change_event(
+    position: MarkerPosition,
+    marker: Marker,
+    palette: Palette,
+    callback: &Callback<(MarkerPosition, Option<Marker>)>,
+) -> Callback<Event> {
+    let callback = callback.clone();
 
-                            let updated_thread_cell: (GridCell, IArray<Thread>) =
-                                (GridCell(col_index, row_index), IArray::from(threads));
-                            callback.emit(updated_thread_cell);
-                        }
-                    }
-                }
-                Err(err) => error!("{}", err),
+    Callback::from(move |e: Event| {
+        if let Some(select) = e.target_dyn_into::<HtmlSelectElement>() {
+            match palette.find(&select.value()) {
+                Some(entry) => callback.emit((
+                    position,
+                    Some(Marker {
+                        color: entry.color(),
+                        ..marker
+                    }),
+                )),
+                None => error!("Unable to find thread {}", select.value()),
             }
         }
     })
 }
 
-fn find_thread_type_hex_value(
-    thread_type: ThreadType,
-    threads: &[Thread],
-    default_color: Rgb,
-) -> String {
+fn find_thread(thread_type: ThreadType, threads: &CellThreads) -> Option<Thread> {
     threads
         .iter()
-        .find_map(|thread| {
-            if thread.thread_type == thread_type {
-                Some(thread.color.as_hex())
-            } else {
-                None
+        .find(|thread| thread.thread_type == thread_type)
+}
+
+fn thread_select(
+    name: &'static str,
+    thread: Option<Thread>,
+    palette: Palette,
+    onchange: Callback<Event>,
+) -> Html {
+    palette_select(
+        name,
+        thread.and_then(|thread| thread.entry),
+        thread.map(|thread| thread.color),
+        palette,
+        onchange,
+    )
+}
+
+// Colours are picked from the palette so their floss is kept for the chart. A colour that isn't
+// a floss, or a missing thread, shows in the first option until a floss is picked.
+fn palette_select(
+    name: &'static str,
+    selected: Option<PaletteEntryId>,
+    current: Option<Rgb>,
+    palette: Palette,
+    onchange: Callback<Event>,
+) -> Html {
+    html! {
+        <select name={name} onchange={onchange}>
+            <option value="" selected={selected.is_none()} disabled=true>
+                { current.map_or("None".to_string(), |color| color.as_hex()) }
+            </option>
+            {
+                for palette.ids().map(|id| html! {
+                    <option value={id.entry().code} selected={Some(id) == selected}>
+                        { format!("{} {}", id.entry().code, id.entry().name) }
+                    </option>
+                })
             }
-        })
-        .unwrap_or(default_color.as_hex())
+        </select>
+    }
 }
 
 #[derive(Properties, PartialEq)]
 pub struct PatternCellEditorProps {
-    pub thread_cell: (GridCell, IArray<Thread>),
+    // The cell whose threads are shown
+    pub thread_cell: (GridCell, CellThreads),
+    // Number of selected cells a change applies to
+    #[prop_or(1)]
+    pub cell_count: usize,
+    // Emitted with the thread type whose floss changed
     #[prop_or_default]
-    pub onchange: Callback<(GridCell, IArray<Thread>)>,
-    pub default_color: Rgb,
+    pub onchange: Callback<(ThreadType, PaletteEntryId)>,
+    // The brush's floss. New markers take it, and its palette is offered for every thread.
+    pub thread_entry: PaletteEntryId,
     pub on_close: Callback<MouseEvent>,
+    #[prop_or_default]
+    pub overlay: Overlay,
+    #[prop_or_default]
+    pub on_marker_change: Callback<(MarkerPosition, Option<Marker>)>,
 }
 
 #[function_component(PatternCellEditor)]
 pub fn pattern_cell_editor(props: &PatternCellEditorProps) -> Html {
     let PatternCellEditorProps {
         thread_cell,
+        cell_count,
         onchange,
-        default_color,
+        thread_entry,
         on_close,
+        overlay,
+        on_marker_change,
     } = props;
-    let slash_backwards_value =
-        find_thread_type_hex_value(ThreadType::SlashBackwards, &thread_cell.1, *default_color);
-    let slash_forwards_value =
-        find_thread_type_hex_value(ThreadType::SlashForwards, &thread_cell.1, *default_color);
-    let border_top_value =
-        find_thread_type_hex_value(ThreadType::BorderTop, &thread_cell.1, *default_color);
-    let border_right_value =
-        find_thread_type_hex_value(ThreadType::BorderRight, &thread_cell.1, *default_color);
-    let border_bottom_value =
-        find_thread_type_hex_value(ThreadType::BorderBottom, &thread_cell.1, *default_color);
-    let border_left_value =
-        find_thread_type_hex_value(ThreadType::BorderLeft, &thread_cell.1, *default_color);
+    let palette = thread_entry.palette();
+    let marker_positions = [
+        ("Centre", MarkerPosition::Centre(thread_cell.0)),
+        (
+            "Top left corner",
+            MarkerPosition::Corner(GridIntersection::corner_of(thread_cell.0, Corner::TopLeft)),
+        ),
+        (
+            "Top right corner",
+            MarkerPosition::Corner(GridIntersection::corner_of(thread_cell.0, Corner::TopRight)),
+        ),
+        (
+            "Bottom right corner",
+            MarkerPosition::Corner(GridIntersection::corner_of(
+                thread_cell.0,
+                Corner::BottomRight,
+            )),
+        ),
+        (
+            "Bottom left corner",
+            MarkerPosition::Corner(GridIntersection::corner_of(
+                thread_cell.0,
+                Corner::BottomLeft,
+            )),
+        ),
+    ];
+    let slash_backwards_thread = find_thread(ThreadType::SlashBackwards, &thread_cell.1);
+    let slash_forwards_thread = find_thread(ThreadType::SlashForwards, &thread_cell.1);
+    let border_top_thread = find_thread(ThreadType::BorderTop, &thread_cell.1);
+    let border_right_thread = find_thread(ThreadType::BorderRight, &thread_cell.1);
+    let border_bottom_thread = find_thread(ThreadType::BorderBottom, &thread_cell.1);
+    let border_left_thread = find_thread(ThreadType::BorderLeft, &thread_cell.1);
+    let quarter_top_left_thread = find_thread(ThreadType::QuarterTopLeft, &thread_cell.1);
+    let quarter_top_right_thread = find_thread(ThreadType::QuarterTopRight, &thread_cell.1);
+    let quarter_bottom_right_thread = find_thread(ThreadType::QuarterBottomRight, &thread_cell.1);
+    let quarter_bottom_left_thread = find_thread(ThreadType::QuarterBottomLeft, &thread_cell.1);
+    let three_quarter_top_left_thread =
+        find_thread(ThreadType::ThreeQuarterTopLeft, &thread_cell.1);
+    let three_quarter_top_right_thread =
+        find_thread(ThreadType::ThreeQuarterTopRight, &thread_cell.1);
+    let three_quarter_bottom_right_thread =
+        find_thread(ThreadType::ThreeQuarterBottomRight, &thread_cell.1);
+    let three_quarter_bottom_left_thread =
+        find_thread(ThreadType::ThreeQuarterBottomLeft, &thread_cell.1);
 
     let on_slash_backwards_change =
-        create_on_change_event(ThreadType::SlashBackwards, thread_cell.clone(), onchange);
+        create_on_change_event(ThreadType::SlashBackwards, palette, onchange);
     let on_slash_forwards_change =
-        create_on_change_event(ThreadType::SlashForwards, thread_cell.clone(), onchange);
-    let on_border_top_change =
-        create_on_change_event(ThreadType::BorderTop, thread_cell.clone(), onchange);
-    let on_border_right_change =
-        create_on_change_event(ThreadType::BorderRight, thread_cell.clone(), onchange);
+        create_on_change_event(ThreadType::SlashForwards, palette, onchange);
+    let on_border_top_change = create_on_change_event(ThreadType::BorderTop, palette, onchange);
+    let on_border_right_change = create_on_change_event(ThreadType::BorderRight, palette, onchange);
     let on_border_bottom_change =
-        create_on_change_event(ThreadType::BorderBottom, thread_cell.clone(), onchange);
-    let on_border_left_change =
-        create_on_change_event(ThreadType::BorderLeft, thread_cell.clone(), onchange);
+        create_on_change_event(ThreadType::BorderBottom, palette, onchange);
+    let on_border_left_change = create_on_change_event(ThreadType::BorderLeft, palette, onchange);
+    let on_quarter_top_left_change =
+        create_on_change_event(ThreadType::QuarterTopLeft, palette, onchange);
+    let on_quarter_top_right_change =
+        create_on_change_event(ThreadType::QuarterTopRight, palette, onchange);
+    let on_quarter_bottom_right_change =
+        create_on_change_event(ThreadType::QuarterBottomRight, palette, onchange);
+    let on_quarter_bottom_left_change =
+        create_on_change_event(ThreadType::QuarterBottomLeft, palette, onchange);
+    let on_three_quarter_top_left_change =
+        create_on_change_event(ThreadType::ThreeQuarterTopLeft, palette, onchange);
+    let on_three_quarter_top_right_change =
+        create_on_change_event(ThreadType::ThreeQuarterTopRight, palette, onchange);
+    let on_three_quarter_bottom_right_change =
+        create_on_change_event(ThreadType::ThreeQuarterBottomRight, palette, onchange);
+    let on_three_quarter_bottom_left_change =
+        create_on_change_event(ThreadType::ThreeQuarterBottomLeft, palette, onchange);
 
     html! {<div>
         {"Cell editor"}
+        if *cell_count > 1 {
+            { format!(" ({} cells)", cell_count) }
+        }
         <button onclick={on_close}>{ "Close" }</button>
         <div>
             <label for="slash_forwards">
                 {"Slash Forwards"}
-                <input type="color" name="slash_forwards" value={slash_forwards_value.clone()} onchange={on_slash_forwards_change} />
+                { thread_select("slash_forwards", slash_forwards_thread, palette, on_slash_forwards_change) }
             </label>
         </div>
         <div>
             <label for="slash_backwards">
                 {"Slash Backwards"}
-                <input type="color" name="slash_backwards" value={slash_backwards_value.clone()} onchange={on_slash_backwards_change} />
+                { thread_select("slash_backwards", slash_backwards_thread, palette, on_slash_backwards_change) }
             </label>
         </div>
         <div>
             <label for="border_top">
                 {"Border Top"}
-                <input type="color" name="border_top" value={border_top_value.clone()} onchange={on_border_top_change} />
+                { thread_select("border_top", border_top_thread, palette, on_border_top_change) }
             </label>
         </div>
         <div>
             <label for="border_right">
                 {"Border Right"}
-                <input type="color" name="border_right" value={border_right_value.clone()} onchange={on_border_right_change} />
+                { thread_select("border_right", border_right_thread, palette, on_border_right_change) }
             </label>
         </div>
         <div>
             <label for="border_bottom">
                 {"Border Bottom"}
-                <input type="color" name="border_bottom" value={border_bottom_value.clone()} onchange={on_border_bottom_change} />
+                { thread_select("border_bottom", border_bottom_thread, palette, on_border_bottom_change) }
             </label>
         </div>
         <div>
             <label for="border_left">
                 {"Border Left"}
-                <input type="color" name="border_left" value={border_left_value.clone()} onchange={on_border_left_change} />
+                { thread_select("border_left", border_left_thread, palette, on_border_left_change) }
+            </label>
+        </div>
+        <div>
+            <label for="quarter_top_left">
+                {"Quarter Top Left"}
+                { thread_select("quarter_top_left", quarter_top_left_thread, palette, on_quarter_top_left_change) }
             </label>
         </div>
+        <div>
+            <label for="quarter_top_right">
+                {"Quarter Top Right"}
+                { thread_select("quarter_top_right", quarter_top_right_thread, palette, on_quarter_top_right_change) }
+            </label>
+        </div>
+        <div>
+            <label for="quarter_bottom_right">
+                {"Quarter Bottom Right"}
+                { thread_select("quarter_bottom_right", quarter_bottom_right_thread, palette, on_quarter_bottom_right_change) }
+            </label>
+        </div>
+        <div>
+            <label for="quarter_bottom_left">
+                {"Quarter Bottom Left"}
+                { thread_select("quarter_bottom_left", quarter_bottom_left_thread, palette, on_quarter_bottom_left_change) }
+            </label>
+        </div>
+        <div>
+            <label for="three_quarter_top_left">
+                {"Three Quarter Top Left"}
+                { thread_select("three_quarter_top_left", three_quarter_top_left_thread, palette, on_three_quarter_top_left_change) }
+            </label>
+        </div>
+        <div>
+            <label for="three_quarter_top_right">
+                {"Three Quarter Top Right"}
+                { thread_select("three_quarter_top_right", three_quarter_top_right_thread, palette, on_three_quarter_top_right_change) }
+            </label>
+        </div>
+        <div>
+            <label for="three_quarter_bottom_right">
+                {"Three Quarter Bottom Right"}
+                { thread_select("three_quarter_bottom_right", three_quarter_bottom_right_thread, palette, on_three_quarter_bottom_right_change) }
+            </label>
+        </div>
+        <div>
+            <label for="three_quarter_bottom_left">
+                {"Three Quarter Bottom Left"}
+                { thread_select("three_quarter_bottom_left", three_quarter_bottom_left_thread, palette, on_three_quarter_bottom_left_change) }
+            </label>
+        </div>
+        <div>
+            {"Knots & beads"}
+            {
+                for marker_positions.iter().map(|&(label, position)| html! {
+                    <div>
+                        { label }
+                        if let Some(marker) = overlay.marker(position) {
+                            { format!(" {}", marker.kind.as_str()) }
+                            { palette_select("marker_thread", palette.ids().find(|id| id.color() == marker.color), Some(marker.color), palette, create_on_marker_color_change_event(position, marker, palette, on_marker_change)) }
+                            <button onclick={create_on_marker_change_event(position, None, on_marker_change)}>{ "Remove" }</button>
+                        } else {
+                            {
+                                for MarkerKind::ALL.iter().map(|&kind| html! {
+                                    <button onclick={create_on_marker_change_event(position, Some(Marker { kind, color: thread_entry.color() }), on_marker_change)}>
+                                        { format!("Add {}", kind.as_str().to_lowercase()) }
+                                    </button>
+                                })
+                            }
+                        }
+                    </div>
+                })
+            }
+        </div>
     </div>}
 }
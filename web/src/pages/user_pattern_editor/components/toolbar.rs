@@ -1,61 +1,363 @@
-use web_sys::HtmlInputElement;
+use gloo::file::File;
+use implicit_clone::sync::IArray;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
 use yew::prelude::*;
 
-use crate::components::pattern::Rgb;
+use crate::components::pattern::fill::{Connectivity, FillOptions};
+use crate::components::pattern::overlay::MarkerKind;
+use crate::components::pattern::palette::PaletteEntryId;
+use crate::components::pattern::symmetry::{Symmetry, SymmetryMode};
+use crate::components::pattern::transform::Transform;
+use crate::components::pattern::{Rgb, StitchType, ThreadType};
 
 use super::super::PatternEditorFeature;
 
 #[derive(Properties, PartialEq)]
 pub struct ToolbarProps {
-    pub on_color_change: Callback<String>,
+    pub on_thread_entry_change: Callback<PaletteEntryId>,
     pub on_feature_change: Callback<PatternEditorFeature>,
     pub on_undo: Callback<MouseEvent>,
-    pub default_color: Rgb,
+    pub on_redo: Callback<MouseEvent>,
+    pub can_undo: bool,
+    pub can_redo: bool,
+    pub on_download: Callback<MouseEvent>,
+    pub on_open: Callback<File>,
+    pub thread_entry: PaletteEntryId,
+    pub stitch_type: StitchType,
+    pub on_stitch_type_change: Callback<StitchType>,
+    // Whether rectangles and ellipses are filled in
+    pub shape_filled: bool,
+    pub on_shape_filled_change: Callback<bool>,
+    pub marker_kind: MarkerKind,
+    pub on_marker_kind_change: Callback<MarkerKind>,
+    pub fill_options: FillOptions,
+    pub on_fill_options_change: Callback<FillOptions>,
+    pub erase_thread_types: IArray<ThreadType>,
+    pub on_erase_thread_types_change: Callback<IArray<ThreadType>>,
+    pub backstitch_width: f64,
+    pub on_backstitch_width_change: Callback<f64>,
+    pub on_copy: Callback<MouseEvent>,
+    pub on_cut: Callback<MouseEvent>,
+    pub on_paste: Callback<MouseEvent>,
+    pub can_copy: bool,
+    pub can_paste: bool,
+    // Applies to the selection, or the whole pattern when nothing is selected
+    pub on_transform: Callback<Transform>,
+    pub symmetry: Symmetry,
+    pub on_symmetry_change: Callback<Symmetry>,
+    // Whether the next click on the grid sets the symmetry pivot
+    pub picking_symmetry_pivot: bool,
+    pub on_pick_symmetry_pivot: Callback<MouseEvent>,
 }
 #[function_component(Toolbar)]
 pub fn toolbar(props: &ToolbarProps) -> Html {
     let ToolbarProps {
-        on_color_change,
+        on_thread_entry_change,
         on_feature_change,
         on_undo,
-        default_color,
+        on_redo,
+        can_undo,
+        can_redo,
+        on_download,
+        on_open,
+        thread_entry,
+        stitch_type,
+        on_stitch_type_change,
+        shape_filled,
+        on_shape_filled_change,
+        marker_kind,
+        on_marker_kind_change,
+        fill_options,
+        on_fill_options_change,
+        erase_thread_types,
+        on_erase_thread_types_change,
+        backstitch_width,
+        on_backstitch_width_change,
+        on_copy,
+        on_cut,
+        on_paste,
+        can_copy,
+        can_paste,
+        on_transform,
+        symmetry,
+        on_symmetry_change,
+        picking_symmetry_pivot,
+        on_pick_symmetry_pivot,
     } = props;
-    let color_handle = use_state(|| default_color.as_hex());
-    let color = (*color_handle).clone();
-    let on_color_change = on_color_change.clone();
+    let thread_entry = *thread_entry;
+    let palette = thread_entry.palette();
+    let fill_options = *fill_options;
 
-    let handle_color_change = {
-        let color_handle = color_handle.clone();
-        let on_color_change = on_color_change.clone();
+    let handle_thread_entry_change = {
+        let on_thread_entry_change = on_thread_entry_change.clone();
+
+        Callback::from(move |e: Event| {
+            if let Some(select) = e.target_dyn_into::<HtmlSelectElement>() {
+                if let Some(entry) = palette.find(&select.value()) {
+                    on_thread_entry_change.emit(entry);
+                }
+            }
+        })
+    };
+
+    // Picks the floss that looks closest to a free colour
+    let handle_match_color_change = {
+        let on_thread_entry_change = on_thread_entry_change.clone();
+
+        Callback::from(move |e: Event| {
+            if let Some(input) = e.target_dyn_into::<HtmlInputElement>() {
+                if let Ok(color) = Rgb::from_hex(input.value()) {
+                    on_thread_entry_change.emit(palette.nearest(color));
+                }
+            }
+        })
+    };
+
+    let handle_open = {
+        let on_open = on_open.clone();
 
         Callback::from(move |e: Event| {
             let input = e.target_dyn_into::<HtmlInputElement>();
 
             if let Some(input) = input {
-                let hex_value = input.value();
-                color_handle.set(hex_value.clone());
-                on_color_change.emit(hex_value);
+                if let Some(file) = input.files().and_then(|files| files.get(0)) {
+                    on_open.emit(File::from(file));
+                }
+                // Allow the same file to be opened again
+                input.set_value("");
             }
         })
     };
 
+    let handle_connectivity_change = {
+        let on_fill_options_change = on_fill_options_change.clone();
+
+        Callback::from(move |e: Event| {
+            if let Some(input) = e.target_dyn_into::<HtmlInputElement>() {
+                on_fill_options_change.emit(FillOptions {
+                    connectivity: if input.checked() {
+                        Connectivity::Eight
+                    } else {
+                        Connectivity::Four
+                    },
+                    ..fill_options
+                });
+            }
+        })
+    };
+
+    let handle_tolerance_change = {
+        let on_fill_options_change = on_fill_options_change.clone();
+
+        Callback::from(move |e: Event| {
+            if let Some(input) = e.target_dyn_into::<HtmlInputElement>() {
+                if let Ok(tolerance) = input.value().parse::<u8>() {
+                    on_fill_options_change.emit(FillOptions {
+                        tolerance,
+                        ..fill_options
+                    });
+                }
+            }
+        })
+    };
+
+    let handle_global_change = {
+        let on_fill_options_change = on_fill_options_change.clone();
+
+        Callback::from(move |e: Event| {
+            if let Some(input) = e.target_dyn_into::<HtmlInputElement>() {
+                on_fill_options_change.emit(FillOptions {
+                    global: input.checked(),
+                    ..fill_options
+                });
+            }
+        })
+    };
+
+    let handle_shape_filled_change = {
+        let on_shape_filled_change = on_shape_filled_change.clone();
+
+        Callback::from(move |e: Event| {
+            if let Some(input) = e.target_dyn_into::<HtmlInputElement>() {
+                on_shape_filled_change.emit(input.checked());
+            }
+        })
+    };
+
+    let handle_backstitch_width_change = {
+        let on_backstitch_width_change = on_backstitch_width_change.clone();
+
+        Callback::from(move |e: Event| {
+            if let Some(input) = e.target_dyn_into::<HtmlInputElement>() {
+                if let Ok(width) = input.value().parse::<f64>() {
+                    if width > 0.0 {
+                        on_backstitch_width_change.emit(width);
+                    }
+                }
+            }
+        })
+    };
+
+    let handle_centre_symmetry_pivot = {
+        let on_symmetry_change = on_symmetry_change.clone();
+        let symmetry = *symmetry;
+
+        Callback::from(move |_: MouseEvent| {
+            on_symmetry_change.emit(Symmetry {
+                pivot: None,
+                ..symmetry
+            });
+        })
+    };
+
     html! {<>
             <div>
                 <label>
-                    { "thread color" }
-                    <input type="color" name="brush_color" value={color} onchange={handle_color_change} />
+                    { "thread" }
+                    // The code is drawn in black or white, whichever reads better on the floss
+                    <span style={format!("padding: 0 0.25em; background: {}; color: {};", thread_entry.color().as_hex(), thread_entry.color().contrasting().as_hex())}>
+                        { thread_entry.entry().code }
+                    </span>
+                    <select name="brush_thread" onchange={handle_thread_entry_change}>
+                        {
+                            for palette.entries().iter().map(|entry| html! {
+                                <option value={entry.code} selected={entry == thread_entry.entry()}>
+                                    { format!("{} {}", entry.code, entry.name) }
+                                </option>
+                            })
+                        }
+                    </select>
+                </label>
+                <label>
+                    { "match color" }
+                    <input type="color" name="match_color" value={thread_entry.color().as_hex()} onchange={handle_match_color_change} />
                 </label>
                 <div>
                     <button onclick={create_handle_feature_change(PatternEditorFeature::Brush, on_feature_change.clone())}>{ "Brush" }</button>
+                    {
+                        for StitchType::ALL.iter().map(|&option| html! {
+                            <label>
+                                <input
+                                    type="radio"
+                                    name="stitch_type"
+                                    checked={option == *stitch_type}
+                                    onchange={create_handle_stitch_type_change(option, on_stitch_type_change.clone())}
+                                />
+                                { option.as_str() }
+                            </label>
+                        })
+                    }
+                </div>
+                <div>
+                    <button onclick={create_handle_feature_change(PatternEditorFeature::Eraser, on_feature_change.clone())}>{ "Eraser" }</button>
+                    {
+                        for ThreadType::ALL.iter().map(|&thread_type| html! {
+                            <label>
+                                <input
+                                    type="checkbox"
+                                    name={format!("erase_{}", thread_type.as_str())}
+                                    checked={erase_thread_types.contains(&thread_type)}
+                                    onchange={create_handle_erase_thread_type_change(thread_type, erase_thread_types.clone(), on_erase_thread_types_change.clone())}
+                                />
+                                { thread_type.as_str() }
+                            </label>
+                        })
+                    }
                 </div>
                 <div>
                     <button onclick={create_handle_feature_change(PatternEditorFeature::Fill, on_feature_change.clone())}>{ "Fill" }</button>
+                    <label>
+                        <input type="checkbox" name="fill_diagonal" checked={fill_options.connectivity == Connectivity::Eight} onchange={handle_connectivity_change} />
+                        { "Fill across diagonals" }
+                    </label>
+                    <label>
+                        { "Tolerance" }
+                        <input type="number" name="fill_tolerance" min="0" max="255" value={fill_options.tolerance.to_string()} onchange={handle_tolerance_change} />
+                    </label>
+                    <label>
+                        <input type="checkbox" name="fill_global" checked={fill_options.global} onchange={handle_global_change} />
+                        { "Fill all matching cells" }
+                    </label>
+                </div>
+                <div>
+                    <button onclick={create_handle_feature_change(PatternEditorFeature::Line, on_feature_change.clone())}>{ "Line" }</button>
+                    <button onclick={create_handle_feature_change(PatternEditorFeature::Rectangle, on_feature_change.clone())}>{ "Rectangle" }</button>
+                    <button onclick={create_handle_feature_change(PatternEditorFeature::Ellipse, on_feature_change.clone())}>{ "Ellipse" }</button>
+                    <label>
+                        <input type="checkbox" name="shape_filled" checked={*shape_filled} onchange={handle_shape_filled_change} />
+                        { "Filled" }
+                    </label>
+                </div>
+                <div>
+                    <button onclick={create_handle_feature_change(PatternEditorFeature::Marker, on_feature_change.clone())}>{ "Knots & beads" }</button>
+                    {
+                        for MarkerKind::ALL.iter().map(|&option| html! {
+                            <label>
+                                <input
+                                    type="radio"
+                                    name="marker_kind"
+                                    checked={option == *marker_kind}
+                                    onchange={create_handle_marker_kind_change(option, on_marker_kind_change.clone())}
+                                />
+                                { option.as_str() }
+                            </label>
+                        })
+                    }
+                </div>
+                <div>
+                    <button onclick={create_handle_feature_change(PatternEditorFeature::Backstitch, on_feature_change.clone())} title="Click to add points, click the last point again or press Enter to finish">{ "Backstitch" }</button>
+                    <label>
+                        { "Thickness" }
+                        <input type="number" name="backstitch_width" min="1" max="10" step="0.5" value={backstitch_width.to_string()} onchange={handle_backstitch_width_change} />
+                    </label>
                 </div>
                 <div>
                     <button onclick={create_handle_feature_change(PatternEditorFeature::Pointer, on_feature_change.clone())}>{ "Pointer" }</button>
                 </div>
                 <div>
-                    <button onclick={on_undo}>{ "Undo" }</button>
+                    <button onclick={on_copy} disabled={!can_copy} title="Ctrl+C">{ "Copy" }</button>
+                    <button onclick={on_cut} disabled={!can_copy} title="Ctrl+X">{ "Cut" }</button>
+                    <button onclick={on_paste} disabled={!can_paste} title="Ctrl+V">{ "Paste" }</button>
+                </div>
+                <div>
+                    {
+                        for Transform::ALL.iter().map(|&transform| html! {
+                            <button onclick={create_handle_transform(transform, on_transform.clone())}>{ transform.as_str() }</button>
+                        })
+                    }
+                </div>
+                <div>
+                    { "Symmetry" }
+                    {
+                        for SymmetryMode::ALL.iter().map(|&mode| html! {
+                            <label>
+                                <input
+                                    type="radio"
+                                    name="symmetry_mode"
+                                    checked={mode == symmetry.mode}
+                                    onchange={create_handle_symmetry_mode_change(mode, *symmetry, on_symmetry_change.clone())}
+                                />
+                                { mode.as_str() }
+                            </label>
+                        })
+                    }
+                    <button onclick={on_pick_symmetry_pivot} disabled={symmetry.is_off() || *picking_symmetry_pivot} title="Click a cell centre or grid intersection to mirror around">{ "Pick pivot" }</button>
+                    <button onclick={handle_centre_symmetry_pivot} disabled={symmetry.pivot.is_none()}>{ "Centre pivot" }</button>
+                </div>
+                <div>
+                    <button onclick={on_undo} disabled={!can_undo} title="Ctrl+Z">{ "Undo" }</button>
+                </div>
+                <div>
+                    <button onclick={on_redo} disabled={!can_redo} title="Ctrl+Shift+Z">{ "Redo" }</button>
+                </div>
+                <div>
+                    <button onclick={on_download}>{ "Download pattern" }</button>
+                </div>
+                <div>
+                    <label>
+                        { "Open pattern" }
+                        <input type="file" accept=".json,application/json" onchange={handle_open} />
+                    </label>
                 </div>
             </div>
     </>}
@@ -68,3 +370,65 @@ fn create_handle_feature_change(
         callback.emit(feature.clone());
     })
 }
+
+fn create_handle_transform(
+    transform: Transform,
+    callback: Callback<Transform>,
+) -> Callback<MouseEvent> {
+    Callback::from(move |_: MouseEvent| {
+        callback.emit(transform);
+    })
+}
+
+fn create_handle_erase_thread_type_change(
+    thread_type: ThreadType,
+    erase_thread_types: IArray<ThreadType>,
+    callback: Callback<IArray<ThreadType>>,
+) -> Callback<Event> {
+    Callback::from(move |e: Event| {
+        if let Some(input) = e.target_dyn_into::<HtmlInputElement>() {
+            // Rebuilt from `ThreadType::ALL` so the order stays the same
+            let thread_types = ThreadType::ALL
+                .iter()
+                .copied()
+                .filter(|&t| {
+                    if t == thread_type {
+                        input.checked()
+                    } else {
+                        erase_thread_types.contains(&t)
+                    }
+                })
+                .collect();
+
+            callback.emit(thread_types);
+        }
+    })
+}
+
+fn create_handle_stitch_type_change(
+    stitch_type: StitchType,
+    callback: Callback<StitchType>,
+) -> Callback<Event> {
+    Callback::from(move |_: Event| {
+        callback.emit(stitch_type);
+    })
+}
+
+fn create_handle_marker_kind_change(
+    marker_kind: MarkerKind,
+    callback: Callback<MarkerKind>,
+) -> Callback<Event> {
+    Callback::from(move |_: Event| {
+        callback.emit(marker_kind);
+    })
+}
+
+fn create_handle_symmetry_mode_change(
+    mode: SymmetryMode,
+    symmetry: Symmetry,
+    callback: Callback<Symmetry>,
+) -> Callback<Event> {
+    Callback::from(move |_: Event| {
+        callback.emit(Symmetry { mode, ..symmetry });
+    })
+}
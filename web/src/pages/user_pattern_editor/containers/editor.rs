@@ -1,121 +1,208 @@
-use std::collections::HashMap;
-use std::hash::Hash;
-use std::sync::Arc;
-
-use html::ImplicitClone;
-use implicit_clone::sync::{IArray, IMap};
-use indexmap::IndexMap;
-use log::{error, info};
-use web_sys::{HtmlInputElement, MouseEvent};
+use gloo::events::EventListener;
+use gloo::file::callbacks::{read_as_text, FileReader};
+use gloo::file::{Blob, File, ObjectUrl};
+use gloo::timers::callback::Timeout;
+use implicit_clone::sync::IArray;
+use indexmap::{IndexMap, IndexSet};
+use log::error;
+use wasm_bindgen::JsCast;
+use web_sys::{
+    ClipboardEvent, HtmlDocument, HtmlElement, HtmlInputElement, HtmlSelectElement,
+    HtmlTextAreaElement, KeyboardEvent, MouseEvent,
+};
 use yew::prelude::*;
 
-use crate::components::pattern::grid::PatternGrid;
+use crate::components::pattern::chart::{chart_document, symbol_chart, ChartOptions};
+use crate::components::pattern::clipboard::{selection_origin, ClipboardCells};
+use crate::components::pattern::document::{load_pattern, save_pattern, PatternMetadata};
+use crate::components::pattern::fill::{fill_region, FillOptions};
+use crate::components::pattern::grid::{
+    render_png, GridMouseEvent, ImageExportOptions, PatternGrid,
+};
+use crate::components::pattern::line::line_cells;
+use crate::components::pattern::overlay::{
+    Backstitch, Marker, MarkerKind, MarkerPosition, Overlay,
+};
+use crate::components::pattern::palette::{Palette, PaletteEntryId};
+use crate::components::pattern::resize::CanvasEdit;
+use crate::components::pattern::selection::{rect_cells, SelectionMode};
+use crate::components::pattern::shape::Shape;
+use crate::components::pattern::symmetry::{Symmetry, SymmetryPivot};
+use crate::components::pattern::transform::{
+    transform_grid, transform_overlay, transform_selection, Transform,
+};
 use crate::components::pattern::{
-    GridCell, GridSize, GridType, RawGridType, Rgb, Thread, ThreadType,
+    CellThreads, GridCell, GridIntersection, GridPoint, GridSize, GridType, Rgb, StitchType,
+    Thread, ThreadType,
 };
+use crate::store::{clear_draft, load_draft, save_draft, Draft, DRAFT_SAVE_DELAY_MS};
 
+use super::super::components::canvas_form::CanvasForm;
+use super::super::components::chart_export_form::ChartExportForm;
+use super::super::components::image_export_form::ImageExportForm;
+use super::super::components::image_import_form::ImageImportForm;
 use super::super::components::pattern_cell_editor::PatternCellEditor;
 use super::super::components::toolbar::Toolbar;
 use super::super::reducer::{PatternEditorAction, PatternEditorState};
 use super::super::PatternEditorFeature;
 
 const DEFAULT_COLOR: Rgb = Rgb { r: 255, g: 0, b: 0 };
+const DEFAULT_BACKSTITCH_WIDTH: f64 = 3.0;
+// How close, in cells, the pointer has to be to a backstitch line to select it
+const BACKSTITCH_HIT_TOLERANCE: f64 = 0.2;
 
 #[function_component(PatternEditor)]
 pub fn pattern_editor() -> Html {
     let history_reducer = use_reducer(PatternEditorState::default);
     let grid = history_reducer.grid.clone();
-    let mousedown_elements_handle: UseStateHandle<GridType> = use_state(IMap::default);
-    let colnum_ref = use_node_ref();
-    let rownum_ref = use_node_ref();
+    let overlay = history_reducer.overlay.clone();
+    // The grid being drawn on while the mouse is held down. It's committed to the history on
+    // mouseup.
+    let stroke_handle: UseStateHandle<Option<GridType>> = use_state(|| None);
+    // Cells the current stroke has passed through, in order, with the threads the brush stitched
+    // over each. Mirrored cells are included, ahead of the cell they mirror. The eraser only uses
+    // the cells.
+    let stroke_path_ref = use_mut_ref(Vec::<(GridCell, CellThreads)>::new);
+    // The shape being dragged out, the cell it started from and the threads it's stitched with
+    let shape_drag_ref = use_mut_ref(|| None::<(Shape, GridCell, CellThreads)>);
     let active_feature_handle = use_state(|| PatternEditorFeature::Brush);
-    let is_mousedown_handle = use_state(|| false);
-    let grid_size_handle: UseStateHandle<Option<GridSize>> = use_state(|| None);
-    let thread_color_handle = use_state(|| DEFAULT_COLOR);
-    let active_thread_cell_handle: UseStateHandle<(GridCell, IArray<Thread>)> =
-        use_state(|| (GridCell(0, 0), IArray::default()));
+    // The floss being stitched with
+    let thread_entry_handle = use_state(|| Palette::Dmc.nearest(DEFAULT_COLOR));
+    let fill_options_handle = use_state(FillOptions::default);
+    let stitch_type_handle = use_state(StitchType::default);
+    let marker_kind_handle = use_state(|| MarkerKind::FrenchKnot);
+    // Whether rectangles and ellipses are filled in or only outlined
+    let shape_filled_handle = use_state(|| false);
+    // Thread types the eraser removes. All of them unless some are unticked in the toolbar.
+    let erase_thread_types_handle: UseStateHandle<IArray<ThreadType>> =
+        use_state(|| IArray::from(ThreadType::ALL.to_vec()));
+    let active_thread_cell_handle: UseStateHandle<(GridCell, CellThreads)> =
+        use_state(|| (GridCell(0, 0), CellThreads::default()));
     let selected_cells_handle: UseStateHandle<IArray<GridCell>> =
         use_state(|| IArray::from(vec![]));
-    let grid_size = *grid_size_handle;
-    let active_thread_cell = (*active_thread_cell_handle).clone();
-    let mousedown_elements = (*mousedown_elements_handle).clone();
+    // Where a drag selection started, how it combines with the earlier selection and what was
+    // selected before it
+    let selection_drag_ref = use_mut_ref(|| None::<(GridCell, SelectionMode, IArray<GridCell>)>);
+    // Points of the backstitch line being drawn. It's committed to the history once finished.
+    let backstitch_points_handle: UseStateHandle<IArray<GridIntersection>> =
+        use_state(|| IArray::EMPTY);
+    // Index of the backstitch line picked with the pointer
+    let selected_backstitch_handle: UseStateHandle<Option<usize>> = use_state(|| None);
+    let backstitch_width_handle = use_state(|| DEFAULT_BACKSTITCH_WIDTH);
+    // The cells last copied or cut in this tab
+    let clipboard_handle: UseStateHandle<Option<ClipboardCells>> = use_state(|| None);
+    // Cells being pasted or moved
+    let floating_handle: UseStateHandle<Option<FloatingCells>> = use_state(|| None);
+    // Mirrors each brush, eraser and fill edit while it's on
+    let symmetry_handle = use_state(Symmetry::default);
+    // Set while the next click on the grid picks the symmetry pivot
+    let picking_symmetry_pivot_handle = use_state(|| false);
+    let grid_size = grid.size();
+    let active_thread_cell = *active_thread_cell_handle;
     let selected_cells = (*selected_cells_handle).clone();
-    let thread_color = *thread_color_handle;
-    let clear_handle = use_state(|| true);
+    let thread_entry = *thread_entry_handle;
+    let thread_color = thread_entry.color();
+    let fill_options = *fill_options_handle;
+    let stitch_type = *stitch_type_handle;
+    let marker_kind = *marker_kind_handle;
+    let shape_filled = *shape_filled_handle;
+    let erase_thread_types = (*erase_thread_types_handle).clone();
+    let backstitch_points = (*backstitch_points_handle).clone();
+    let selected_backstitch = *selected_backstitch_handle;
+    let backstitch_width = *backstitch_width_handle;
+    let floating = (*floating_handle).clone();
+    let symmetry = *symmetry_handle;
+    let metadata_handle = use_state(PatternMetadata::default);
+    // Kept alive until the file has been read or the next download has started
+    let file_reader_ref = use_mut_ref(|| None::<FileReader>);
+    let download_url_ref = use_mut_ref(|| None::<ObjectUrl>);
+    // A draft left over from a previous session, offered for recovery until it's restored or
+    // discarded
+    let draft_handle: UseStateHandle<Option<Draft>> = use_state(load_draft);
+    let draft_timeout_ref = use_mut_ref(|| None::<Timeout>);
 
-    let handle_dimensions_submit = use_callback(
+    use_effect_with(
         (
-            colnum_ref.clone(),
-            rownum_ref.clone(),
-            grid_size_handle.clone(),
+            grid.clone(),
+            overlay.clone(),
+            (*metadata_handle).clone(),
+            thread_entry,
+            (*active_feature_handle).clone(),
+            draft_handle.is_some(),
         ),
-        |e: SubmitEvent, (colnum_ref, rownum_ref, grid_size_handle)| {
-            e.prevent_default();
-            if let (Some(col_input), Some(row_input)) = (
-                colnum_ref.cast::<HtmlInputElement>(),
-                rownum_ref.cast::<HtmlInputElement>(),
-            ) {
-                if let (Ok(col_num), Ok(row_num)) = (
-                    col_input.value().parse::<usize>(),
-                    row_input.value().parse::<usize>(),
-                ) {
-                    grid_size_handle.set(Some(GridSize(col_num, row_num)));
-                } else {
-                    error!("Unable to convert inputs values to usize");
-                }
-            } else {
-                error!("Unable to find input elements");
+        move |(grid, overlay, metadata, thread_entry, active_feature, is_draft_pending)| {
+            // Don't overwrite the previous draft before the user has decided what to do with it
+            if !*is_draft_pending && grid.size().area() > 0 {
+                let draft = (
+                    grid.clone(),
+                    overlay.clone(),
+                    metadata.clone(),
+                    *thread_entry,
+                    active_feature.clone(),
+                );
+
+                // Replacing the timeout cancels the previous one
+                *draft_timeout_ref.borrow_mut() =
+                    Some(Timeout::new(DRAFT_SAVE_DELAY_MS, move || {
+                        let (grid, overlay, metadata, thread_entry, active_feature) = draft;
+                        let draft =
+                            Draft::new(&grid, &overlay, metadata, thread_entry, active_feature);
+
+                        if let Err(err) = save_draft(&draft) {
+                            error!("{}", err);
+                        }
+                    }));
+            }
+        },
+    );
+
+    // Resizing and row or column changes are undone like any other edit
+    let handle_canvas_edit = use_callback(
+        (
+            grid.clone(),
+            overlay.clone(),
+            history_reducer.clone(),
+            selected_cells_handle.clone(),
+        ),
+        |edit: CanvasEdit, (grid, overlay, history_reducer, selected_cells_handle)| {
+            if let Some((grid, overlay)) = edit.apply(grid, overlay) {
+                // Selected cells may have moved or gone
+                selected_cells_handle.set(IArray::from(vec![]));
+                history_reducer.dispatch(PatternEditorAction::Replace(grid, overlay));
             }
         },
     );
+    // The imported image replaces the whole pattern, and can be undone like any other edit
+    let handle_image_import = use_callback(
+        (history_reducer.clone(), selected_cells_handle.clone()),
+        |grid: GridType, (history_reducer, selected_cells_handle)| {
+            selected_cells_handle.set(IArray::from(vec![]));
+            history_reducer.dispatch(PatternEditorAction::Replace(grid, Overlay::default()));
+        },
+    );
+    // Colour changes apply to every selected cell
     let handle_thread_change = use_callback(
         (
             active_thread_cell_handle.clone(),
-            grid.clone(),
+            selected_cells_handle.clone(),
             history_reducer.clone(),
-            mousedown_elements_handle.clone(),
         ),
-        |(GridCell(col_index, row_index), updated_threads): (GridCell, IArray<Thread>),
-         (active_thread_cell_handle, grid, history_reducer, mousedown_elements_handle)| {
-            let mut grid: RawGridType = grid.iter().collect();
-            let mut updated_mousedown_elements = HashMap::new();
-            // If the cell exists, copy and mutate the grid content otherwise add a new cell_tuple
-            if let Some(cell) = grid
-                .iter()
-                .find(|(&GridCell(col_id, row_id), _)| col_id == col_index && row_id == row_index)
-            {
-                let previous_threads = cell.1;
-                let mut new_threads = updated_threads.clone().to_vec();
-
-                // Don't add previous Thread if updated ThreadType exists
-                for thread in previous_threads.iter() {
-                    if !new_threads
-                        .iter()
-                        .any(|Thread { thread_type, .. }| *thread_type == thread.thread_type)
-                    {
-                        new_threads.push(thread);
-                    }
-                }
-
-                let updated_cell_tuple =
-                    (GridCell(col_index, row_index), IArray::from(new_threads));
-
-                updated_mousedown_elements.insert(updated_cell_tuple.0, updated_cell_tuple.1);
-            // Otherwise add a new cell
-            } else {
-                let updated_cell_tuple = (GridCell(col_index, row_index), updated_threads.clone());
-                grid.insert(updated_cell_tuple.0, updated_cell_tuple.1.clone());
-
-                updated_mousedown_elements.insert(updated_cell_tuple.0, updated_cell_tuple.1);
+        |(thread_type, entry): (ThreadType, PaletteEntryId),
+         (active_thread_cell_handle, selected_cells_handle, history_reducer)| {
+            let (grid_cell, mut threads) = **active_thread_cell_handle;
+            let thread = Thread {
+                color: entry.color(),
+                thread_type,
+                entry: Some(entry),
             };
 
-            // There should only be 1 item in this iterator so .last() is always the only item
-            if let Some((k, v)) = updated_mousedown_elements.iter().last() {
-                active_thread_cell_handle.set((*k, v.clone()));
-            }
-
-            history_reducer.dispatch(PatternEditorAction::AddHistory(grid));
-            mousedown_elements_handle.set(hashmap_to_imap(updated_mousedown_elements));
+            history_reducer.dispatch(PatternEditorAction::SetThread(
+                (**selected_cells_handle).clone(),
+                thread_type,
+                entry,
+            ));
+            threads.set_thread(thread);
+            active_thread_cell_handle.set((grid_cell, threads));
         },
     );
 
@@ -123,92 +210,191 @@ pub fn pattern_editor() -> Html {
         (
             (*active_feature_handle).clone(),
             active_thread_cell_handle.clone(),
-            grid.clone(),
-            grid_size,
-            is_mousedown_handle.clone(),
-            mousedown_elements_handle.clone(),
-            selected_cells_handle.clone(),
-            thread_color,
+            (grid.clone(), overlay.clone()),
+            history_reducer.clone(),
+            (
+                stroke_handle.clone(),
+                stroke_path_ref.clone(),
+                shape_drag_ref.clone(),
+                floating_handle.clone(),
+            ),
+            (selected_cells_handle.clone(), selection_drag_ref.clone()),
+            (
+                backstitch_points_handle.clone(),
+                selected_backstitch_handle.clone(),
+            ),
+            (thread_entry, stitch_type, shape_filled),
+            marker_kind,
+            fill_options,
+            erase_thread_types.clone(),
+            (
+                symmetry_handle.clone(),
+                picking_symmetry_pivot_handle.clone(),
+            ),
         ),
-        |GridCell(col_index, row_index): GridCell,
+        |event: GridMouseEvent,
          (
             active_feature,
             active_thread_cell_handle,
-            grid,
-            grid_size,
-            is_mousedown_handle,
-            mousedown_elements_handle,
-            selected_cells_handle,
-            thread_color,
+            (grid, overlay),
+            history_reducer,
+            (stroke_handle, stroke_path_ref, shape_drag_ref, floating_handle),
+            (selected_cells_handle, selection_drag_ref),
+            (backstitch_points_handle, selected_backstitch_handle),
+            (thread_entry, stitch_type, shape_filled),
+            marker_kind,
+            fill_options,
+            erase_thread_types,
+            (symmetry_handle, picking_symmetry_pivot_handle),
         )| {
-            match active_feature {
-                PatternEditorFeature::Brush => {
-                    let mut mousedown_elements: HashMap<GridCell, IArray<Thread>> = HashMap::new();
-
-                    mousedown_elements.insert(
-                        GridCell(col_index, row_index),
-                        IArray::from(vec![
-                            Thread::new(*thread_color, ThreadType::SlashForwards),
-                            Thread::new(*thread_color, ThreadType::SlashBackwards),
-                        ]),
-                    );
+            let point = event.point;
+            let grid_cell = point.grid_cell;
+            let symmetry = **symmetry_handle;
 
-                    info!("mousedownel: {:?}", mousedown_elements);
+            if **picking_symmetry_pivot_handle {
+                symmetry_handle.set(Symmetry {
+                    pivot: Some(SymmetryPivot::nearest(point)),
+                    ..symmetry
+                });
+                picking_symmetry_pivot_handle.set(false);
+                return;
+            }
+
+            // Pasted cells are dropped where they're clicked
+            if let Some(floating) = &**floating_handle {
+                let floating = floating.moved_to(grid_cell, grid.size());
+
+                history_reducer
+                    .dispatch(PatternEditorAction::EditCells(floating.edits(grid.size())));
+                selected_cells_handle.set(floating.placed_cells(grid.size()));
+                floating_handle.set(None);
+                return;
+            }
+
+            match active_feature {
+                PatternEditorFeature::Brush | PatternEditorFeature::Eraser => {
+                    let threads = entry_threads(*thread_entry, *stitch_type, point);
+                    let mut stroke = grid.clone();
+                    let mut stroke_path = vec![];
 
-                    is_mousedown_handle.set(true);
-                    mousedown_elements_handle.set(hashmap_to_imap(mousedown_elements));
+                    paint_symmetric_stroke_cell(
+                        &mut stroke,
+                        &mut stroke_path,
+                        symmetry,
+                        grid_cell,
+                        active_feature,
+                        threads,
+                        erase_thread_types,
+                    );
+                    stroke_handle.set(Some(stroke));
+                    *stroke_path_ref.borrow_mut() = stroke_path;
                 }
                 PatternEditorFeature::Fill => {
-                    let mut mousedown_elements: HashMap<GridCell, IArray<Thread>> = HashMap::new();
-                    let GridSize(col_count, row_count) =
-                        grid_size.unwrap_or(GridSize(0_usize, 0_usize));
-
-                    for row_index in 0..row_count {
-                        for col_index in 0..col_count {
-                            mousedown_elements.insert(
-                                GridCell(col_index, row_index),
-                                IArray::from(vec![
-                                    Thread::new(*thread_color, ThreadType::SlashForwards),
-                                    Thread::new(*thread_color, ThreadType::SlashBackwards),
-                                ]),
-                            );
-                        }
+                    let threads = CellThreads::full_cross(thread_entry.color())
+                        .with_entry(Some(*thread_entry));
+
+                    if symmetry.is_off() {
+                        history_reducer.dispatch(PatternEditorAction::Fill(
+                            grid_cell,
+                            *fill_options,
+                            threads,
+                        ));
+                    } else {
+                        // Each mirrored cell fills its own region, and they're all undone
+                        // together
+                        let cells: IndexSet<GridCell> = symmetry
+                            .cells(grid_cell, grid.size())
+                            .into_iter()
+                            .flat_map(|seed| fill_region(grid, seed, *fill_options))
+                            .collect();
+
+                        history_reducer.dispatch(PatternEditorAction::EditCells(
+                            cells
+                                .into_iter()
+                                .map(|grid_cell| (grid_cell, threads))
+                                .collect(),
+                        ));
                     }
+                }
+                PatternEditorFeature::Line
+                | PatternEditorFeature::Rectangle
+                | PatternEditorFeature::Ellipse => {
+                    let Some(shape) = feature_shape(active_feature, *shape_filled) else {
+                        return;
+                    };
+                    let threads = entry_threads(*thread_entry, *stitch_type, point);
+                    let (stroke, path) = draw_shape(grid, shape, grid_cell, grid_cell, threads);
+
+                    stroke_handle.set(Some(stroke));
+                    *stroke_path_ref.borrow_mut() = path;
+                    *shape_drag_ref.borrow_mut() = Some((shape, grid_cell, threads));
+                }
+                PatternEditorFeature::Marker => {
+                    let position = MarkerPosition::nearest(point);
+                    let marker = Marker {
+                        kind: *marker_kind,
+                        color: thread_entry.color(),
+                    };
 
-                    is_mousedown_handle.set(true);
-                    mousedown_elements_handle.set(hashmap_to_imap(mousedown_elements));
+                    // Clicking a marker that's already there takes it away again
+                    history_reducer.dispatch(PatternEditorAction::SetMarker(
+                        position,
+                        (overlay.marker(position) != Some(marker)).then_some(marker),
+                    ));
+                }
+                PatternEditorFeature::Backstitch => {
+                    let intersection = point.nearest_intersection();
+
+                    // Clicking the last point again finishes the line
+                    if backstitch_points_handle.last() == Some(&intersection) {
+                        finish_backstitch(
+                            backstitch_points_handle,
+                            &history_reducer.dispatcher(),
+                            thread_entry.color(),
+                        );
+                    } else {
+                        let mut points = backstitch_points_handle.to_vec();
+
+                        points.push(intersection);
+                        backstitch_points_handle.set(IArray::from(points));
+                    }
                 }
                 PatternEditorFeature::Pointer => {
-                    let selected_cells = (*selected_cells_handle).clone();
-                    let GridSize(col_count, row_count) =
-                        grid_size.unwrap_or(GridSize(0_usize, 0_usize));
-
-                    if col_count > col_index && row_count > row_index {
-                        if let Some(cell) = grid
-                            .iter()
-                            .find(|(GridCell(col, row), _)| col_index == *col && row_index == *row)
-                        {
-                            active_thread_cell_handle.set((GridCell(cell.0 .0, cell.0 .1), cell.1));
-                        } else {
-                            active_thread_cell_handle
-                                .set((GridCell(col_index, row_index), IArray::default()));
-                        }
+                    // Lines are drawn above the cells, so they're picked first
+                    if let Some(index) = overlay.backstitch_at(point, BACKSTITCH_HIT_TOLERANCE) {
+                        selected_backstitch_handle.set(Some(index));
+                        selected_cells_handle.set(IArray::from(vec![]));
+                        return;
                     }
 
-                    if let Some(selected_cell) = selected_cells.first() {
-                        // Delesect if a cell is clicked twice
-                        if selected_cells.len() == 1
-                            && *selected_cell == GridCell(col_index, row_index)
+                    let selected_cells = (**selected_cells_handle).clone();
+                    // Ctrl toggles cells and Shift adds them to the selection
+                    let mode = if event.ctrl_key {
+                        SelectionMode::Toggle
+                    } else if event.shift_key {
+                        SelectionMode::Add
+                    } else {
+                        SelectionMode::Replace
+                    };
+
+                    selected_backstitch_handle.set(None);
+
+                    if let Some(threads) = grid.get(grid_cell) {
+                        active_thread_cell_handle.set((grid_cell, threads));
+                    }
+
+                    // Dragging a selected cell moves the whole selection
+                    if mode == SelectionMode::Replace && selected_cells.contains(&grid_cell) {
+                        if let Some(floating) =
+                            FloatingCells::lifted(grid, selected_cells, grid_cell)
                         {
-                            selected_cells_handle.set(IArray::from(vec![]));
-                        } else {
-                            selected_cells_handle
-                                .set(IArray::from(vec![GridCell(col_index, row_index)]));
+                            floating_handle.set(Some(floating));
                         }
-                    } else {
-                        selected_cells_handle
-                            .set(IArray::from(vec![GridCell(col_index, row_index)]));
+                        return;
                     }
+
+                    selected_cells_handle.set(mode.apply(&selected_cells, [grid_cell]));
+                    *selection_drag_ref.borrow_mut() = Some((grid_cell, mode, selected_cells));
                 }
             };
         },
@@ -217,38 +403,110 @@ pub fn pattern_editor() -> Html {
     let handle_mousemove = use_callback(
         (
             (*active_feature_handle).clone(),
-            *is_mousedown_handle,
-            mousedown_elements_handle.clone(),
-            thread_color,
+            grid.clone(),
+            stroke_handle.clone(),
+            (stroke_path_ref.clone(), shape_drag_ref.clone()),
+            floating_handle.clone(),
+            (selected_cells_handle.clone(), selection_drag_ref.clone()),
+            grid_size,
+            thread_entry,
+            stitch_type,
+            erase_thread_types.clone(),
+            symmetry,
         ),
-        |GridCell(col_index, row_index): GridCell,
-         (active_feature, is_mousedown, mousedown_elements_handle, thread_color)| {
+        |event: GridMouseEvent,
+         (
+            active_feature,
+            grid,
+            stroke_handle,
+            (stroke_path_ref, shape_drag_ref),
+            floating_handle,
+            (selected_cells_handle, selection_drag_ref),
+            grid_size,
+            thread_entry,
+            stitch_type,
+            erase_thread_types,
+            symmetry,
+        )| {
+            let point = event.point;
+            let grid_cell = point.grid_cell;
+
+            // Cells being pasted or moved follow the pointer
+            if let Some(floating) = &**floating_handle {
+                let floating = floating.moved_to(grid_cell, *grid_size);
+
+                if grid_size.contains(grid_cell) && Some(&floating) != floating_handle.as_ref() {
+                    floating_handle.set(Some(floating));
+                }
+                return;
+            }
+
             match active_feature {
-                PatternEditorFeature::Brush => {
-                    if *is_mousedown {
-                        let mousedown_elements = (*mousedown_elements_handle).clone();
+                PatternEditorFeature::Brush | PatternEditorFeature::Eraser => {
+                    if let Some(stroke) = &**stroke_handle {
+                        let mut stroke_path = stroke_path_ref.borrow_mut();
+                        // Mouse events can be several cells apart on a fast drag, so fill in
+                        // the cells in between
+                        let cells = match stroke_path.last() {
+                            Some(&(last_cell, _)) if last_cell == grid_cell => return,
+                            Some(&(last_cell, _)) => line_cells(last_cell, grid_cell).split_off(1),
+                            None => vec![grid_cell],
+                        };
+                        let threads = entry_threads(*thread_entry, *stitch_type, point);
+                        let mut stroke = stroke.clone();
+                        let mut is_changed = false;
 
-                        if !mousedown_elements
-                            .iter()
-                            .any(|(k, _)| k.0 == col_index && k.1 == row_index)
-                        {
-                            let mut mousedown_elements: HashMap<GridCell, IArray<Thread>> =
-                                mousedown_elements.iter().collect();
-
-                            mousedown_elements.insert(
-                                GridCell(col_index, row_index),
-                                IArray::from(vec![
-                                    Thread::new(*thread_color, ThreadType::SlashForwards),
-                                    Thread::new(*thread_color, ThreadType::SlashBackwards),
-                                ]),
+                        for grid_cell in cells {
+                            is_changed |= paint_symmetric_stroke_cell(
+                                &mut stroke,
+                                &mut stroke_path,
+                                *symmetry,
+                                grid_cell,
+                                active_feature,
+                                threads,
+                                erase_thread_types,
                             );
+                        }
 
-                            mousedown_elements_handle.set(hashmap_to_imap(mousedown_elements));
-                        };
+                        if is_changed {
+                            stroke_handle.set(Some(stroke));
+                        }
                     }
                 }
                 PatternEditorFeature::Fill => {}
-                PatternEditorFeature::Pointer => {}
+                PatternEditorFeature::Line
+                | PatternEditorFeature::Rectangle
+                | PatternEditorFeature::Ellipse => {
+                    if let Some((shape, start, threads)) = *shape_drag_ref.borrow() {
+                        if !grid_size.contains(grid_cell) {
+                            return;
+                        }
+
+                        let (stroke, path) = draw_shape(grid, shape, start, grid_cell, threads);
+
+                        if path != *stroke_path_ref.borrow() {
+                            stroke_handle.set(Some(stroke));
+                            *stroke_path_ref.borrow_mut() = path;
+                        }
+                    }
+                }
+                PatternEditorFeature::Marker => {}
+                PatternEditorFeature::Backstitch => {}
+                PatternEditorFeature::Pointer => {
+                    // Dragging selects the rectangle between the cell the drag started on and
+                    // the one under the pointer
+                    if let Some((start, mode, selected_cells)) = &*selection_drag_ref.borrow() {
+                        if !grid_size.contains(grid_cell) {
+                            return;
+                        }
+
+                        let selection = mode.apply(selected_cells, rect_cells(*start, grid_cell));
+
+                        if selection != **selected_cells_handle {
+                            selected_cells_handle.set(selection);
+                        }
+                    }
+                }
             };
         },
     );
@@ -256,88 +514,619 @@ pub fn pattern_editor() -> Html {
     let handle_mouseup = use_callback(
         (
             (*active_feature_handle).clone(),
-            grid.clone(),
             history_reducer.clone(),
-            is_mousedown_handle.clone(),
-            mousedown_elements_handle.clone(),
+            stroke_handle.clone(),
+            (stroke_path_ref.clone(), shape_drag_ref.clone()),
+            selection_drag_ref.clone(),
+            (floating_handle.clone(), selected_cells_handle.clone()),
+            erase_thread_types.clone(),
+            symmetry,
         ),
         |_: MouseEvent,
          (
             active_feature,
-            grid,
             history_reducer,
-            is_mousedown_handle,
-            mousedown_elements_handle,
+            stroke_handle,
+            (stroke_path_ref, shape_drag_ref),
+            selection_drag_ref,
+            (floating_handle, selected_cells_handle),
+            erase_thread_types,
+            symmetry,
         )| {
-            if **is_mousedown_handle {
-                match active_feature {
-                    PatternEditorFeature::Brush | PatternEditorFeature::Fill => {
-                        let mousedown_elements: HashMap<GridCell, IArray<Thread>> =
-                            (*mousedown_elements_handle).iter().collect();
-                        let mut grid: RawGridType = grid.iter().collect();
+            shape_drag_ref.borrow_mut().take();
+            selection_drag_ref.borrow_mut().take();
+
+            // A move is dropped when the mouse is let go. Pasted cells wait for a click.
+            if let Some(floating) = floating_handle.as_ref().filter(|f| f.is_move()) {
+                let size = history_reducer.grid.size();
+
+                if floating.origin != floating.start {
+                    history_reducer.dispatch(PatternEditorAction::EditCells(floating.edits(size)));
+                    selected_cells_handle.set(floating.placed_cells(size));
+                } else if floating.lifted.len() == 1 {
+                    // Deselect if a cell is clicked twice
+                    selected_cells_handle.set(IArray::from(vec![]));
+                } else {
+                    selected_cells_handle.set(IArray::from(vec![floating.grabbed_cell()]));
+                }
+
+                floating_handle.set(None);
+            }
+
+            if let Some(stroke) = &**stroke_handle {
+                let path = stroke_path_ref.take();
+
+                history_reducer.dispatch(match active_feature {
+                    // Mirrored cells lose mirrored thread types, so the erased cells are saved
+                    // as they were left
+                    PatternEditorFeature::Eraser if !symmetry.is_off() => {
+                        let cells: IndexSet<GridCell> =
+                            path.into_iter().map(|(grid_cell, _)| grid_cell).collect();
+
+                        PatternEditorAction::EditCells(
+                            cells
+                                .into_iter()
+                                .filter_map(|grid_cell| Some((grid_cell, stroke.get(grid_cell)?)))
+                                .collect(),
+                        )
+                    }
+                    PatternEditorFeature::Eraser => PatternEditorAction::Erase(
+                        path.into_iter().map(|(grid_cell, _)| grid_cell).collect(),
+                        erase_thread_types.clone(),
+                    ),
+                    _ => PatternEditorAction::Stroke(path),
+                });
+                stroke_handle.set(None);
+            }
+        },
+    );
 
-                        grid.extend(mousedown_elements.clone());
+    let handle_undo = use_callback(history_reducer.clone(), |_: MouseEvent, history_reducer| {
+        history_reducer.dispatch(PatternEditorAction::Undo);
+    });
 
-                        mousedown_elements_handle.set(IMap::default());
-                        is_mousedown_handle.set(false);
+    let handle_redo = use_callback(history_reducer.clone(), |_: MouseEvent, history_reducer| {
+        history_reducer.dispatch(PatternEditorAction::Redo);
+    });
 
-                        history_reducer.dispatch(PatternEditorAction::AddHistory(grid));
+    // Ctrl+Z undoes and Ctrl+Shift+Z redoes. Cmd works in place of Ctrl on macOS.
+    {
+        let history_dispatcher = history_reducer.dispatcher();
+
+        use_effect_with((), move |_| {
+            let listener = EventListener::new(&gloo::utils::document(), "keydown", move |event| {
+                if let Some(event) = event.dyn_ref::<KeyboardEvent>() {
+                    // Leave text inputs to handle their own undo
+                    if is_input_target(event)
+                        || !(event.ctrl_key() || event.meta_key())
+                        || !event.key().eq_ignore_ascii_case("z")
+                    {
+                        return;
                     }
-                    PatternEditorFeature::Pointer => {}
+
+                    event.prevent_default();
+                    history_dispatcher.dispatch(if event.shift_key() {
+                        PatternEditorAction::Redo
+                    } else {
+                        PatternEditorAction::Undo
+                    });
+                }
+            });
+
+            move || drop(listener)
+        });
+    }
+
+    // Enter finishes the backstitch line being drawn and Escape drops it along with any
+    // selection, or puts back cells being pasted or moved, or stops picking a symmetry pivot.
+    // Delete or Backspace removes the selected line.
+    {
+        let history_dispatcher = history_reducer.dispatcher();
+
+        use_effect_with(
+            (
+                backstitch_points_handle.clone(),
+                selected_backstitch_handle.clone(),
+                selected_cells_handle.clone(),
+                floating_handle.clone(),
+                picking_symmetry_pivot_handle.clone(),
+                thread_color,
+            ),
+            move |(
+                backstitch_points_handle,
+                selected_backstitch_handle,
+                selected_cells_handle,
+                floating_handle,
+                picking_symmetry_pivot_handle,
+                thread_color,
+            )| {
+                let backstitch_points_handle = backstitch_points_handle.clone();
+                let selected_backstitch_handle = selected_backstitch_handle.clone();
+                let selected_cells_handle = selected_cells_handle.clone();
+                let floating_handle = floating_handle.clone();
+                let picking_symmetry_pivot_handle = picking_symmetry_pivot_handle.clone();
+                let thread_color = *thread_color;
+                let listener =
+                    EventListener::new(&gloo::utils::document(), "keydown", move |event| {
+                        if let Some(event) = event.dyn_ref::<KeyboardEvent>() {
+                            if is_input_target(event) {
+                                return;
+                            }
+
+                            match event.key().as_str() {
+                                "Enter" if !backstitch_points_handle.is_empty() => {
+                                    finish_backstitch(
+                                        &backstitch_points_handle,
+                                        &history_dispatcher,
+                                        thread_color,
+                                    );
+                                }
+                                "Escape" if floating_handle.is_some() => {
+                                    floating_handle.set(None);
+                                }
+                                "Escape" if *picking_symmetry_pivot_handle => {
+                                    picking_symmetry_pivot_handle.set(false);
+                                }
+                                "Escape" => {
+                                    backstitch_points_handle.set(IArray::EMPTY);
+                                    selected_backstitch_handle.set(None);
+                                    selected_cells_handle.set(IArray::from(vec![]));
+                                }
+                                "Delete" | "Backspace" => {
+                                    if let Some(index) = *selected_backstitch_handle {
+                                        event.prevent_default();
+                                        history_dispatcher
+                                            .dispatch(PatternEditorAction::RemoveBackstitch(index));
+                                    }
+                                }
+                                _ => {}
+                            }
+                        }
+                    });
+
+                move || drop(listener)
+            },
+        );
+    }
+
+    // Copied cells go on the system clipboard as a pattern file, so they can be pasted into
+    // another tab. Pasting something that isn't a pattern falls back to the last cells copied
+    // here.
+    {
+        let history_dispatcher = history_reducer.dispatcher();
+
+        use_effect_with(
+            (
+                grid.clone(),
+                selected_cells.clone(),
+                clipboard_handle.clone(),
+                floating_handle.clone(),
+            ),
+            move |(grid, selected_cells, clipboard_handle, floating_handle)| {
+                let document = gloo::utils::document();
+                let copy_listener = {
+                    let grid = grid.clone();
+                    let selected_cells = selected_cells.clone();
+                    let clipboard_handle = clipboard_handle.clone();
+
+                    EventListener::new(&document, "copy", move |event| {
+                        copy_selection(event, &grid, &selected_cells, &clipboard_handle);
+                    })
                 };
+                let cut_listener = {
+                    let grid = grid.clone();
+                    let selected_cells = selected_cells.clone();
+                    let clipboard_handle = clipboard_handle.clone();
+
+                    EventListener::new(&document, "cut", move |event| {
+                        if copy_selection(event, &grid, &selected_cells, &clipboard_handle) {
+                            history_dispatcher
+                                .dispatch(PatternEditorAction::EditCells(cleared(&selected_cells)));
+                        }
+                    })
+                };
+                let paste_listener = {
+                    let clipboard_handle = clipboard_handle.clone();
+                    let floating_handle = floating_handle.clone();
+
+                    EventListener::new(&document, "paste", move |event| {
+                        if is_input_target(event) {
+                            return;
+                        }
+
+                        let pasted = event
+                            .dyn_ref::<ClipboardEvent>()
+                            .and_then(|event| event.clipboard_data())
+                            .and_then(|data| data.get_data("text/plain").ok())
+                            .and_then(|json| ClipboardCells::from_json(&json).ok());
+
+                        if let Some(cells) = pasted.or_else(|| (*clipboard_handle).clone()) {
+                            event.prevent_default();
+                            floating_handle.set(Some(FloatingCells::pasted(cells)));
+                        }
+                    })
+                };
+
+                move || drop((copy_listener, cut_listener, paste_listener))
+            },
+        );
+    }
+
+    // Indices shift when lines are added or removed, so the selection can't be kept
+    {
+        let selected_backstitch_handle = selected_backstitch_handle.clone();
+
+        use_effect_with(overlay.clone(), move |_| {
+            selected_backstitch_handle.set(None);
+        });
+    }
+
+    let handle_chart_export = {
+        let download_url_ref = download_url_ref.clone();
+
+        use_callback(
+            (grid.clone(), overlay.clone(), metadata_handle.clone()),
+            move |options: ChartOptions, (grid, overlay, metadata_handle)| {
+                let name = if metadata_handle.name.is_empty() {
+                    "pattern"
+                } else {
+                    metadata_handle.name.as_str()
+                };
+
+                match symbol_chart(grid, overlay, &metadata_handle.name, options) {
+                    // Browsers block a page that starts several downloads at once, so every page
+                    // goes into one file
+                    Ok(pages) => {
+                        let document = chart_document(&pages, &metadata_handle.name);
+                        let blob = Blob::new_with_options(document.as_str(), Some("text/html"));
+
+                        *download_url_ref.borrow_mut() =
+                            download_blob(blob, &format!("{}-chart.html", name));
+                    }
+                    Err(err) => error!("{}", err),
+                }
+            },
+        )
+    };
+
+    let handle_image_export = {
+        let download_url_ref = download_url_ref.clone();
+
+        use_callback(
+            (
+                grid.clone(),
+                overlay.clone(),
+                metadata_handle.clone(),
+                backstitch_width,
+            ),
+            move |options: ImageExportOptions,
+                  (grid, overlay, metadata_handle, backstitch_width)| {
+                let download_url_ref = download_url_ref.clone();
+                let filename = if metadata_handle.name.is_empty() {
+                    "pattern.png".to_string()
+                } else {
+                    format!("{}.png", metadata_handle.name)
+                };
+                let options = ImageExportOptions {
+                    backstitch_width: *backstitch_width,
+                    ..options
+                };
+
+                let rendered = render_png(grid, overlay, options, move |blob| match blob {
+                    Some(blob) => *download_url_ref.borrow_mut() = download_blob(blob, &filename),
+                    None => error!("Unable to encode the image"),
+                });
+
+                if let Err(err) = rendered {
+                    error!("{}", err);
+                }
+            },
+        )
+    };
+
+    let handle_download = use_callback(
+        (grid.clone(), overlay.clone(), metadata_handle.clone()),
+        move |_: MouseEvent, (grid, overlay, metadata_handle)| match save_pattern(
+            grid,
+            overlay,
+            (**metadata_handle).clone(),
+        ) {
+            Ok(json) => {
+                let filename = if metadata_handle.name.is_empty() {
+                    "pattern.json".to_string()
+                } else {
+                    format!("{}.json", metadata_handle.name)
+                };
+                let blob = Blob::new_with_options(json.as_str(), Some("application/json"));
+
+                *download_url_ref.borrow_mut() = download_blob(blob, &filename);
             }
+            Err(err) => error!("{}", err),
         },
     );
 
-    let handle_undo = use_callback(
+    let handle_open = use_callback(
+        (history_reducer.clone(), metadata_handle.clone()),
+        move |file: File, (history_reducer, metadata_handle)| {
+            let history_dispatcher = history_reducer.dispatcher();
+            let metadata_handle = metadata_handle.clone();
+
+            let file_reader = read_as_text(&file, move |result| match result {
+                Ok(json) => match load_pattern(&json) {
+                    Ok((grid, overlay, metadata)) => {
+                        history_dispatcher.dispatch(PatternEditorAction::Replace(grid, overlay));
+                        metadata_handle.set(metadata);
+                    }
+                    Err(err) => error!("{}", err),
+                },
+                Err(err) => error!("{}", err),
+            });
+
+            *file_reader_ref.borrow_mut() = Some(file_reader);
+        },
+    );
+
+    let handle_draft_restore = use_callback(
         (
+            draft_handle.clone(),
             history_reducer.clone(),
-            mousedown_elements_handle.clone(),
-            clear_handle.clone(),
+            metadata_handle.clone(),
+            thread_entry_handle.clone(),
+            active_feature_handle.clone(),
         ),
-        |_: MouseEvent, (history_reducer, mousedown_elements_handle, clear_handle)| {
-            let history = &history_reducer.history;
-
-            if history.len() >= 2 {
-                history_reducer.dispatch(PatternEditorAction::RemoveHistory);
-
-                if let Some(second_last) = history.get(history.len() - 2) {
-                    mousedown_elements_handle.set(second_last.clone());
-                    clear_handle.set(true);
+        |_: MouseEvent,
+         (
+            draft_handle,
+            history_reducer,
+            metadata_handle,
+            thread_entry_handle,
+            active_feature_handle,
+        )| {
+            if let Some(draft) = &**draft_handle {
+                match (draft.pattern.to_grid(), draft.pattern.to_overlay()) {
+                    (Ok(grid), Ok(overlay)) => {
+                        history_reducer.dispatch(PatternEditorAction::Replace(grid, overlay));
+                        metadata_handle.set(draft.pattern.metadata.clone());
+                        thread_entry_handle.set(
+                            draft
+                                .thread_entry
+                                .unwrap_or_else(|| Palette::Dmc.nearest(draft.thread_color)),
+                        );
+                        active_feature_handle.set(draft.active_feature.clone());
+                    }
+                    (Err(err), _) | (_, Err(err)) => error!("{}", err),
                 }
-            } else {
-                history_reducer.dispatch(PatternEditorAction::RemoveHistory);
-                mousedown_elements_handle.set(IMap::default());
-                clear_handle.set(true);
             }
+
+            draft_handle.set(None);
         },
     );
 
-    let handle_render = use_callback(clear_handle.clone(), |(), clear_handle| {
-        clear_handle.set(false);
+    let handle_draft_discard = use_callback(draft_handle.clone(), |_: MouseEvent, draft_handle| {
+        clear_draft();
+        draft_handle.set(None);
     });
 
     let handle_set_feature: Callback<PatternEditorFeature> = use_callback(
-        (active_feature_handle.clone(), selected_cells_handle.clone()),
-        move |feature: PatternEditorFeature, (active_feature_handle, selected_cells_handle)| {
-            // Deselect all cells
+        (
+            active_feature_handle.clone(),
+            selected_cells_handle.clone(),
+            backstitch_points_handle.clone(),
+            selected_backstitch_handle.clone(),
+        ),
+        move |feature: PatternEditorFeature,
+              (
+            active_feature_handle,
+            selected_cells_handle,
+            backstitch_points_handle,
+            selected_backstitch_handle,
+        )| {
+            // Deselect all cells and lines
             if feature != PatternEditorFeature::Pointer {
                 selected_cells_handle.set(IArray::from(vec![]));
+                selected_backstitch_handle.set(None);
+            }
+
+            // Drop an unfinished line
+            if feature != PatternEditorFeature::Backstitch {
+                backstitch_points_handle.set(IArray::EMPTY);
             }
 
             active_feature_handle.set(feature);
         },
     );
 
-    let handle_on_color_change = use_callback(
-        thread_color_handle.clone(),
-        move |hex_value: String, thread_color_handle| {
-            if let Ok(color) = Rgb::from_hex(hex_value) {
-                thread_color_handle.set(color);
+    let handle_thread_entry_change = use_callback(
+        thread_entry_handle.clone(),
+        move |thread_entry: PaletteEntryId, thread_entry_handle| {
+            thread_entry_handle.set(thread_entry);
+        },
+    );
+
+    let handle_fill_options_change = use_callback(
+        fill_options_handle.clone(),
+        |fill_options: FillOptions, fill_options_handle| {
+            fill_options_handle.set(fill_options);
+        },
+    );
+
+    let handle_erase_thread_types_change = use_callback(
+        erase_thread_types_handle.clone(),
+        |thread_types: IArray<ThreadType>, erase_thread_types_handle| {
+            erase_thread_types_handle.set(thread_types);
+        },
+    );
+
+    let handle_stitch_type_change = use_callback(
+        stitch_type_handle.clone(),
+        |stitch_type: StitchType, stitch_type_handle| {
+            stitch_type_handle.set(stitch_type);
+        },
+    );
+
+    let handle_shape_filled_change = use_callback(
+        shape_filled_handle.clone(),
+        |filled: bool, shape_filled_handle| {
+            shape_filled_handle.set(filled);
+        },
+    );
+
+    let handle_marker_kind_change = use_callback(
+        marker_kind_handle.clone(),
+        |marker_kind: MarkerKind, marker_kind_handle| {
+            marker_kind_handle.set(marker_kind);
+        },
+    );
+
+    let handle_marker_change = use_callback(
+        history_reducer.clone(),
+        |(position, marker): (MarkerPosition, Option<Marker>), history_reducer| {
+            history_reducer.dispatch(PatternEditorAction::SetMarker(position, marker));
+        },
+    );
+
+    let handle_copy = use_callback(
+        (
+            grid.clone(),
+            selected_cells.clone(),
+            clipboard_handle.clone(),
+        ),
+        |_: MouseEvent, (grid, selected_cells, clipboard_handle)| {
+            // The copy listener fills the clipboard, unless the browser won't run the command
+            if !exec_clipboard_command("copy") {
+                clipboard_handle.set(ClipboardCells::from_selection(grid, selected_cells));
+            }
+        },
+    );
+
+    let handle_cut = use_callback(
+        (
+            grid.clone(),
+            selected_cells.clone(),
+            clipboard_handle.clone(),
+            history_reducer.clone(),
+        ),
+        |_: MouseEvent, (grid, selected_cells, clipboard_handle, history_reducer)| {
+            // Copied rather than cut, so the cells are cleared here whether or not the browser
+            // runs the command
+            if !exec_clipboard_command("copy") {
+                clipboard_handle.set(ClipboardCells::from_selection(grid, selected_cells));
             }
+
+            history_reducer.dispatch(PatternEditorAction::EditCells(cleared(selected_cells)));
         },
     );
 
+    // Browsers only let pages read the system clipboard from a paste event, so the button
+    // pastes the last cells copied here
+    let handle_paste = use_callback(
+        (clipboard_handle.clone(), floating_handle.clone()),
+        |_: MouseEvent, (clipboard_handle, floating_handle)| {
+            if let Some(cells) = &**clipboard_handle {
+                floating_handle.set(Some(FloatingCells::pasted(cells.clone())));
+            }
+        },
+    );
+
+    // Flips or turns the selection, or the whole pattern when nothing is selected
+    let handle_transform = use_callback(
+        (
+            (grid.clone(), overlay.clone()),
+            selected_cells_handle.clone(),
+            history_reducer.clone(),
+        ),
+        |transform: Transform, ((grid, overlay), selected_cells_handle, history_reducer)| {
+            if selected_cells_handle.is_empty() {
+                history_reducer.dispatch(PatternEditorAction::Replace(
+                    transform_grid(grid, transform),
+                    transform_overlay(overlay, grid.size(), transform),
+                ));
+            } else {
+                let (edits, selection) =
+                    transform_selection(grid, selected_cells_handle, transform);
+
+                history_reducer.dispatch(PatternEditorAction::EditCells(edits));
+                selected_cells_handle.set(IArray::from(selection));
+            }
+        },
+    );
+
+    let handle_backstitch_width_change = use_callback(
+        backstitch_width_handle.clone(),
+        |width: f64, backstitch_width_handle| {
+            backstitch_width_handle.set(width);
+        },
+    );
+
+    let handle_backstitch_finish = use_callback(
+        (
+            backstitch_points_handle.clone(),
+            history_reducer.clone(),
+            thread_color,
+        ),
+        |_: MouseEvent, (backstitch_points_handle, history_reducer, thread_color)| {
+            finish_backstitch(
+                backstitch_points_handle,
+                &history_reducer.dispatcher(),
+                *thread_color,
+            );
+        },
+    );
+
+    let handle_backstitch_cancel = use_callback(
+        backstitch_points_handle.clone(),
+        |_: MouseEvent, backstitch_points_handle| {
+            backstitch_points_handle.set(IArray::EMPTY);
+        },
+    );
+
+    let handle_backstitch_delete = use_callback(
+        (selected_backstitch_handle.clone(), history_reducer.clone()),
+        |_: MouseEvent, (selected_backstitch_handle, history_reducer)| {
+            if let Some(index) = **selected_backstitch_handle {
+                history_reducer.dispatch(PatternEditorAction::RemoveBackstitch(index));
+            }
+        },
+    );
+
+    let handle_symmetry_change = use_callback(
+        (
+            symmetry_handle.clone(),
+            picking_symmetry_pivot_handle.clone(),
+        ),
+        |symmetry: Symmetry, (symmetry_handle, picking_symmetry_pivot_handle)| {
+            if symmetry.is_off() {
+                picking_symmetry_pivot_handle.set(false);
+            }
+
+            symmetry_handle.set(symmetry);
+        },
+    );
+
+    let handle_pick_symmetry_pivot = use_callback(
+        picking_symmetry_pivot_handle.clone(),
+        |_: MouseEvent, picking_symmetry_pivot_handle| {
+            picking_symmetry_pivot_handle.set(true);
+        },
+    );
+
+    // The line being drawn is shown on top of the finished ones
+    let display_overlay = if backstitch_points.is_empty() {
+        overlay.clone()
+    } else {
+        let mut display_overlay = overlay.clone();
+        let index = display_overlay.backstitches().count();
+
+        display_overlay.insert_backstitch(
+            index,
+            Backstitch {
+                points: backstitch_points.clone(),
+                color: thread_color,
+            },
+        );
+        display_overlay
+    };
+
     let handle_cell_editor_close = use_callback(
         selected_cells_handle.clone(),
         |_: MouseEvent, selected_cells_handle| {
@@ -347,56 +1136,393 @@ pub fn pattern_editor() -> Html {
 
     html! {
         <div onmouseup={handle_mouseup}>
-            <form onsubmit={handle_dimensions_submit}>
-                <label>
-                    { "Rows" }
-                    <input ref={rownum_ref} type="text" name="rows" />
-                </label>
-                <label>
-                    { "Cols" }
-                    <input ref={colnum_ref} type="text" name="cols" />
-                </label>
+            <CanvasForm size={grid_size} on_edit={handle_canvas_edit} />
+            <ImageImportForm size={grid_size} on_import={handle_image_import} />
+            <ImageExportForm on_export={handle_image_export} />
+            <ChartExportForm on_export={handle_chart_export} />
+
+            if draft_handle.is_some() {
                 <div>
-                    <button type="submit">{ "Save input" }</button>
+                    { "An unsaved draft from your last session was found." }
+                    <button onclick={handle_draft_restore}>{ "Restore draft" }</button>
+                    <button onclick={handle_draft_discard}>{ "Discard draft" }</button>
                 </div>
-            </form>
-
-            if let Some(GridSize(col_count, row_count)) = grid_size {
-                <Toolbar
-                    default_color={DEFAULT_COLOR}
-                    on_color_change={handle_on_color_change}
-                    on_feature_change={handle_set_feature}
-                    on_undo={handle_undo}
-                />
+            }
 
+            <Toolbar
+                thread_entry={thread_entry}
+                on_thread_entry_change={handle_thread_entry_change}
+                on_feature_change={handle_set_feature}
+                on_undo={handle_undo}
+                on_redo={handle_redo}
+                can_undo={history_reducer.can_undo()}
+                can_redo={history_reducer.can_redo()}
+                on_download={handle_download}
+                on_open={handle_open}
+                stitch_type={stitch_type}
+                on_stitch_type_change={handle_stitch_type_change}
+                shape_filled={shape_filled}
+                on_shape_filled_change={handle_shape_filled_change}
+                marker_kind={marker_kind}
+                on_marker_kind_change={handle_marker_kind_change}
+                fill_options={fill_options}
+                on_fill_options_change={handle_fill_options_change}
+                erase_thread_types={erase_thread_types}
+                on_erase_thread_types_change={handle_erase_thread_types_change}
+                backstitch_width={backstitch_width}
+                on_backstitch_width_change={handle_backstitch_width_change}
+                on_copy={handle_copy}
+                on_cut={handle_cut}
+                on_paste={handle_paste}
+                can_copy={!selected_cells.is_empty()}
+                can_paste={clipboard_handle.is_some()}
+                on_transform={handle_transform}
+                symmetry={symmetry}
+                on_symmetry_change={handle_symmetry_change}
+                picking_symmetry_pivot={*picking_symmetry_pivot_handle}
+                on_pick_symmetry_pivot={handle_pick_symmetry_pivot}
+            />
+
+            if grid_size.area() > 0 {
                 <h3>{ "This is a pattern editor" }</h3>
 
                 <PatternGrid
-                    onrender={handle_render}
                     onmousedown={handle_mousedown}
                     onmousemove={handle_mousemove}
-                    selected_cells={selected_cells.clone()}
-                    overwrite={mousedown_elements}
-                    cols={col_count}
-                    rows={row_count}
-                    default_color={Rgb { r: 204, g: 204, b: 204 }}
-                    clear={*clear_handle}
+                    selected_cells={floating.as_ref().map(|floating| floating.placed_cells(grid_size)).unwrap_or_else(|| selected_cells.clone())}
+                    grid={floating.as_ref().map(|floating| floating.preview(&grid)).or_else(|| (*stroke_handle).clone()).unwrap_or(grid)}
+                    overlay={display_overlay}
+                    selected_backstitch={selected_backstitch}
+                    backstitch_width={backstitch_width}
+                    symmetry={symmetry}
                 />
 
+                if !backstitch_points.is_empty() {
+                    <div>
+                        <button onclick={handle_backstitch_finish} disabled={backstitch_points.len() < 2} title="Enter">{ "Finish line" }</button>
+                        <button onclick={handle_backstitch_cancel} title="Escape">{ "Cancel line" }</button>
+                    </div>
+                }
+
+                if selected_backstitch.is_some() {
+                    <div>
+                        <button onclick={handle_backstitch_delete} title="Delete">{ "Delete line" }</button>
+                    </div>
+                }
+
                 if !selected_cells.is_empty() {
                     {"PatternCellEditor"}
-                    <PatternCellEditor on_close={handle_cell_editor_close} default_color={DEFAULT_COLOR} onchange={handle_thread_change} thread_cell={active_thread_cell} />
+                    <PatternCellEditor on_close={handle_cell_editor_close} thread_entry={thread_entry} onchange={handle_thread_change} thread_cell={active_thread_cell} cell_count={selected_cells.len()} overlay={overlay} on_marker_change={handle_marker_change} />
                 }
             }
         </div>
     }
 }
 
-pub fn hashmap_to_imap<K, V>(hash_map: HashMap<K, V>) -> IMap<K, V>
-where
-    K: Eq + Hash + ImplicitClone + 'static,
-    V: PartialEq + ImplicitClone + 'static,
-{
-    let index_map: IndexMap<K, V> = hash_map.into_iter().collect();
-    IMap::Rc(Arc::new(index_map))
+// Paints a cell the way the brush or eraser leaves it. Returns whether the cell changed.
+fn paint_stroke_cell(
+    grid: &mut GridType,
+    grid_cell: GridCell,
+    feature: &PatternEditorFeature,
+    brush_threads: CellThreads,
+    erase_thread_types: &[ThreadType],
+) -> bool {
+    let Some(threads) = grid.get(grid_cell) else {
+        return false;
+    };
+    let threads = match feature {
+        PatternEditorFeature::Eraser => threads.without(erase_thread_types),
+        _ => threads.merged(brush_threads),
+    };
+
+    grid.set(grid_cell, threads)
+}
+
+// Paints a cell and its mirror images, adding each to the stroke's path. The cell itself goes
+// last, so the stroke carries on from it. Returns whether any cell changed.
+fn paint_symmetric_stroke_cell(
+    stroke: &mut GridType,
+    stroke_path: &mut Vec<(GridCell, CellThreads)>,
+    symmetry: Symmetry,
+    grid_cell: GridCell,
+    feature: &PatternEditorFeature,
+    brush_threads: CellThreads,
+    erase_thread_types: &[ThreadType],
+) -> bool {
+    let size = stroke.size();
+    let mut is_changed = false;
+
+    if *feature == PatternEditorFeature::Eraser {
+        for (image, thread_types) in symmetry
+            .thread_types(grid_cell, erase_thread_types, size)
+            .into_iter()
+            .rev()
+        {
+            stroke_path.push((image, CellThreads::default()));
+            is_changed |= paint_stroke_cell(
+                stroke,
+                image,
+                feature,
+                CellThreads::default(),
+                &thread_types,
+            );
+        }
+    } else {
+        for (image, threads) in symmetry
+            .threads(grid_cell, brush_threads, size)
+            .into_iter()
+            .rev()
+        {
+            stroke_path.push((image, threads));
+            is_changed |= paint_stroke_cell(stroke, image, feature, threads, erase_thread_types);
+        }
+    }
+
+    is_changed
+}
+
+// The threads the brush stitches at a point, referring to the floss they're stitched with
+fn entry_threads(
+    thread_entry: PaletteEntryId,
+    stitch_type: StitchType,
+    point: GridPoint,
+) -> CellThreads {
+    stitch_type
+        .threads(thread_entry.color(), point)
+        .with_entry(Some(thread_entry))
+}
+
+// The shape a shape tool draws
+fn feature_shape(feature: &PatternEditorFeature, filled: bool) -> Option<Shape> {
+    match feature {
+        PatternEditorFeature::Line => Some(Shape::Line),
+        PatternEditorFeature::Rectangle => Some(Shape::Rectangle { filled }),
+        PatternEditorFeature::Ellipse => Some(Shape::Ellipse { filled }),
+        _ => None,
+    }
+}
+
+// Stitches a shape over a copy of the grid, returning it along with the stroke path that
+// commits it. Cells outside of the grid are left out.
+fn draw_shape(
+    grid: &GridType,
+    shape: Shape,
+    start: GridCell,
+    end: GridCell,
+    threads: CellThreads,
+) -> (GridType, Vec<(GridCell, CellThreads)>) {
+    let mut stroke = grid.clone();
+    let path: Vec<(GridCell, CellThreads)> = shape
+        .cells(start, end)
+        .into_iter()
+        .filter(|&grid_cell| grid.size().contains(grid_cell))
+        .map(|grid_cell| (grid_cell, threads))
+        .collect();
+
+    for &(grid_cell, threads) in path.iter() {
+        if let Some(previous) = stroke.get(grid_cell) {
+            stroke.set(grid_cell, previous.merged(threads));
+        }
+    }
+
+    (stroke, path)
+}
+
+// Commits the line being drawn to the history and starts a new one. A single point isn't a
+// line, so it's dropped.
+fn finish_backstitch(
+    backstitch_points_handle: &UseStateHandle<IArray<GridIntersection>>,
+    history_dispatcher: &UseReducerDispatcher<PatternEditorState>,
+    color: Rgb,
+) {
+    if backstitch_points_handle.len() >= 2 {
+        history_dispatcher.dispatch(PatternEditorAction::AddBackstitch(Backstitch {
+            points: (**backstitch_points_handle).clone(),
+            color,
+        }));
+    }
+
+    backstitch_points_handle.set(IArray::EMPTY);
+}
+
+// Cells being pasted or moved. They follow the pointer until they're dropped.
+#[derive(Clone, Debug, PartialEq)]
+struct FloatingCells {
+    cells: ClipboardCells,
+    // Offset within `cells` of the cell under the pointer
+    grab: GridCell,
+    // Where the top left of `cells` is now, and where it started
+    origin: GridCell,
+    start: GridCell,
+    // The selection a move takes the cells from. Empty when pasting.
+    lifted: IArray<GridCell>,
+}
+
+impl FloatingCells {
+    fn pasted(cells: ClipboardCells) -> Self {
+        FloatingCells {
+            cells,
+            grab: GridCell(0, 0),
+            origin: GridCell(0, 0),
+            start: GridCell(0, 0),
+            lifted: IArray::EMPTY,
+        }
+    }
+
+    // Picks up the selection by the cell under the pointer
+    fn lifted(
+        grid: &GridType,
+        selected_cells: IArray<GridCell>,
+        grid_cell: GridCell,
+    ) -> Option<Self> {
+        let origin = selection_origin(&selected_cells)?;
+
+        Some(FloatingCells {
+            cells: ClipboardCells::from_selection(grid, &selected_cells)?,
+            grab: GridCell(grid_cell.0 - origin.0, grid_cell.1 - origin.1),
+            origin,
+            start: origin,
+            lifted: selected_cells,
+        })
+    }
+
+    fn is_move(&self) -> bool {
+        !self.lifted.is_empty()
+    }
+
+    fn grabbed_cell(&self) -> GridCell {
+        GridCell(self.origin.0 + self.grab.0, self.origin.1 + self.grab.1)
+    }
+
+    // Kept inside the grid, so no cells are dropped off its edges
+    fn moved_to(&self, grid_cell: GridCell, size: GridSize) -> Self {
+        let origin = GridCell(
+            grid_cell.0.saturating_sub(self.grab.0),
+            grid_cell.1.saturating_sub(self.grab.1),
+        );
+
+        FloatingCells {
+            origin: self.cells.fitted_origin(origin, size),
+            ..self.clone()
+        }
+    }
+
+    // Each cell dropping the cells here changes, with its new threads. A move clears the cells
+    // it was taken from first.
+    fn edits(&self, size: GridSize) -> Vec<(GridCell, CellThreads)> {
+        let edits: IndexMap<GridCell, CellThreads> = cleared(&self.lifted)
+            .into_iter()
+            .chain(self.cells.placed(self.origin, size))
+            .collect();
+
+        edits.into_iter().collect()
+    }
+
+    fn preview(&self, grid: &GridType) -> GridType {
+        let mut preview = grid.clone();
+
+        for (grid_cell, threads) in self.edits(grid.size()) {
+            preview.set(grid_cell, threads);
+        }
+
+        preview
+    }
+
+    // The selection once the cells are dropped. A moved selection keeps its shape.
+    fn placed_cells(&self, size: GridSize) -> IArray<GridCell> {
+        if self.is_move() {
+            self.lifted
+                .iter()
+                .map(|GridCell(col, row)| {
+                    GridCell(
+                        col - self.start.0 + self.origin.0,
+                        row - self.start.1 + self.origin.1,
+                    )
+                })
+                .filter(|&grid_cell| size.contains(grid_cell))
+                .collect()
+        } else {
+            self.cells
+                .placed(self.origin, size)
+                .into_iter()
+                .map(|(grid_cell, _)| grid_cell)
+                .collect()
+        }
+    }
+}
+
+fn cleared(grid_cells: &[GridCell]) -> Vec<(GridCell, CellThreads)> {
+    grid_cells
+        .iter()
+        .map(|&grid_cell| (grid_cell, CellThreads::default()))
+        .collect()
+}
+
+// Form fields keep their own keyboard shortcuts and clipboard
+fn is_input_target(event: &Event) -> bool {
+    event.target().is_some_and(|target| {
+        target.has_type::<HtmlInputElement>()
+            || target.has_type::<HtmlTextAreaElement>()
+            || target.has_type::<HtmlSelectElement>()
+    })
+}
+
+// Puts the selected cells on the clipboard from a copy or cut event. Returns whether there was
+// anything to copy.
+fn copy_selection(
+    event: &Event,
+    grid: &GridType,
+    selected_cells: &[GridCell],
+    clipboard_handle: &UseStateHandle<Option<ClipboardCells>>,
+) -> bool {
+    if is_input_target(event) {
+        return false;
+    }
+
+    let Some(cells) = ClipboardCells::from_selection(grid, selected_cells) else {
+        return false;
+    };
+
+    match (cells.to_json(), event.dyn_ref::<ClipboardEvent>()) {
+        (Ok(json), Some(event)) => {
+            if let Some(data) = event.clipboard_data() {
+                // The browser only keeps the data if its own copy is cancelled
+                if data.set_data("text/plain", &json).is_ok() {
+                    event.prevent_default();
+                }
+            }
+        }
+        (Err(err), _) => error!("{}", err),
+        _ => {}
+    }
+
+    clipboard_handle.set(Some(cells));
+    true
+}
+
+// Runs a copy or cut the same way the keyboard shortcut would, which fires the matching event.
+// Returns false when the browser refuses.
+fn exec_clipboard_command(command: &str) -> bool {
+    gloo::utils::document()
+        .dyn_into::<HtmlDocument>()
+        .ok()
+        .and_then(|document| document.exec_command(command).ok())
+        .unwrap_or(false)
+}
+
+// Starts a browser download of the blob. The returned URL has to be kept alive until the
+// download has started, dropping it revokes the URL.
+fn download_blob(blob: Blob, filename: &str) -> Option<ObjectUrl> {
+    let url = ObjectUrl::from(blob);
+    let anchor = gloo::utils::document()
+        .create_element("a")
+        .ok()?
+        .dyn_into::<HtmlElement>()
+        .ok()?;
+
+    anchor.set_attribute("href", &url).ok()?;
+    anchor.set_attribute("download", filename).ok()?;
+    anchor.click();
+
+    Some(url)
 }
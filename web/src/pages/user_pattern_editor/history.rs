@@ -2,7 +2,9 @@ use std::{cmp::Ordering, mem::discriminant};
 
 use implicit_clone::{sync::IArray, ImplicitClone};
 
-use crate::components::pattern::GridCell;
+use crate::components::pattern::fill::{fill_region, FillOptions};
+use crate::components::pattern::overlay::{Backstitch, Marker, MarkerPosition, Overlay};
+use crate::components::pattern::{CellThreads, GridCell, GridType, ThreadType};
 
 #[derive(Clone, Debug, PartialEq, ImplicitClone)]
 pub enum ActionType {
@@ -29,7 +31,38 @@ pub enum Direction {
     UpLeft(GridCell),
 }
 
-#[derive(Debug)]
+impl Direction {
+    pub fn grid_cell(&self) -> GridCell {
+        match self {
+            Direction::Start(grid_cell)
+            | Direction::Up(grid_cell)
+            | Direction::Right(grid_cell)
+            | Direction::Down(grid_cell)
+            | Direction::Left(grid_cell)
+            | Direction::UpRight(grid_cell)
+            | Direction::DownRight(grid_cell)
+            | Direction::DownLeft(grid_cell)
+            | Direction::UpLeft(grid_cell) => *grid_cell,
+        }
+    }
+
+    // (column, row) change of a single step in this direction
+    fn offset(&self) -> (isize, isize) {
+        match self {
+            Direction::Start(_) => (0, 0),
+            Direction::Up(_) => (0, -1),
+            Direction::Right(_) => (1, 0),
+            Direction::Down(_) => (0, 1),
+            Direction::Left(_) => (-1, 0),
+            Direction::UpRight(_) => (1, -1),
+            Direction::DownRight(_) => (1, 1),
+            Direction::DownLeft(_) => (-1, 1),
+            Direction::UpLeft(_) => (-1, -1),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, ImplicitClone)]
 pub struct History {
     pub path: IArray<Direction>,
     pub action_type: ActionType,
@@ -84,7 +117,7 @@ impl History {
             ActionType::Brush | ActionType::Erase => {
                 let mut final_path: Vec<Direction> = vec![];
                 let mut prev_direction_option: Option<Direction> = None;
-                let last_item_index = raw_path.len() - 1;
+                let last_item_index = raw_path.len().saturating_sub(1);
 
                 for (i, grid_cell) in raw_path.iter().enumerate() {
                     let prev_path_option = if i > 0 { raw_path.get(i - 1) } else { None };
@@ -95,12 +128,9 @@ impl History {
                     {
                         let direction = History::get_direction(*prev_grid_cell, *grid_cell)?;
 
-                        // Push current direction if final item
-                        if i == last_item_index {
-                            final_path.push(direction.clone());
-                        // Otherwise push the previous item if the current direction is not equal
-                        // to the previous direction.
-                        } else if discriminant(&direction) != discriminant(&prev_direction) {
+                        // Push the previous item if the current direction is not equal to the
+                        // previous direction.
+                        if discriminant(&direction) != discriminant(&prev_direction) {
                             match prev_direction {
                                 // Don't push the previous direction if it's Start since that
                                 // has already been pushed
@@ -111,6 +141,11 @@ impl History {
                             }
                         }
 
+                        // Push current direction if final item
+                        if i == last_item_index {
+                            final_path.push(direction.clone());
+                        }
+
                         prev_direction_option = Some(direction);
                     // Otherwise push the starting direction if first item
                     } else {
@@ -128,4 +163,701 @@ impl History {
 
         Ok(History { action_type, path })
     }
+
+    // Expands the path back into every GridCell it passes through
+    pub fn to_raw_path(&self) -> Vec<GridCell> {
+        let mut raw_path: Vec<GridCell> = vec![];
+
+        for direction in self.path.iter() {
+            let GridCell(end_col, end_row) = direction.grid_cell();
+
+            match raw_path.last() {
+                Some(&GridCell(col, row)) => {
+                    let (col_offset, row_offset) = direction.offset();
+                    let steps = col.abs_diff(end_col).max(row.abs_diff(end_row));
+
+                    for step in 1..=steps as isize {
+                        raw_path.push(GridCell(
+                            (col as isize + col_offset * step) as usize,
+                            (row as isize + row_offset * step) as usize,
+                        ));
+                    }
+                }
+                None => raw_path.push(GridCell(end_col, end_row)),
+            }
+        }
+
+        raw_path
+    }
+}
+
+/// A single undoable edit. Entries only hold what they changed, so undoing and redoing replays
+/// them on the current grid rather than swapping whole grids.
+#[derive(Clone, Debug, PartialEq, ImplicitClone)]
+pub enum Operation {
+    // `previous` holds the threads of each cell on the path before the stroke, in path order
+    Stroke {
+        history: History,
+        threads: CellThreads,
+        previous: IArray<CellThreads>,
+    },
+    // Takes `thread_types` out of each cell on the path. `previous` is stored as for `Stroke`.
+    Erase {
+        history: History,
+        thread_types: IArray<ThreadType>,
+        previous: IArray<CellThreads>,
+    },
+    // `previous` holds the cells the fill changed
+    Fill {
+        seed: GridCell,
+        options: FillOptions,
+        threads: CellThreads,
+        previous: IArray<(GridCell, CellThreads)>,
+    },
+    CellEdit {
+        grid_cell: GridCell,
+        threads: CellThreads,
+        previous: CellThreads,
+    },
+    // Any other set of cell changes
+    Cells {
+        cells: IArray<(GridCell, CellThreads)>,
+        previous: IArray<(GridCell, CellThreads)>,
+    },
+    // Places, changes or removes the overlay marker at `position`
+    Marker {
+        position: MarkerPosition,
+        marker: Option<Marker>,
+        previous: Option<Marker>,
+    },
+    // Adds, changes or removes the backstitch line at `index` in the drawing order
+    Backstitch {
+        index: usize,
+        backstitch: Option<Backstitch>,
+        previous: Option<Backstitch>,
+    },
+    // Swaps out the whole grid and overlay, e.g. when a pattern is opened
+    Replace {
+        grid: GridType,
+        overlay: Overlay,
+        previous_grid: GridType,
+        previous_overlay: Overlay,
+    },
+}
+
+impl Operation {
+    /// A brush stroke. `path` holds each cell the brush passed over, in order, with the threads
+    /// it stitched over that cell.
+    pub fn stroke(
+        grid: &GridType,
+        path: Vec<(GridCell, CellThreads)>,
+        action_type: ActionType,
+    ) -> Self {
+        let raw_path: Vec<GridCell> = path.iter().map(|(grid_cell, _)| *grid_cell).collect();
+        let previous: Vec<CellThreads> = raw_path
+            .iter()
+            .map(|grid_cell| grid.get(*grid_cell).unwrap_or_default())
+            .collect();
+        let uniform_threads = match path.split_first() {
+            Some(((_, threads), rest)) if rest.iter().all(|(_, t)| t == threads) => Some(*threads),
+            _ => None,
+        };
+
+        match (
+            uniform_threads,
+            History::from_raw_path(raw_path.clone(), action_type),
+        ) {
+            (Some(threads), Ok(history)) if history.to_raw_path() == raw_path => {
+                Operation::Stroke {
+                    history,
+                    threads,
+                    previous: IArray::from(previous),
+                }
+            }
+            // Paths with gaps can't be encoded as directions, as replaying them would fill the
+            // gaps in, and strokes that stitch different threads along the way need them
+            // stored per cell
+            _ => {
+                let mut stroke = grid.clone();
+                let cells = path
+                    .into_iter()
+                    .filter_map(|(grid_cell, threads)| {
+                        let threads = stroke.get(grid_cell)?.merged(threads);
+                        stroke.set(grid_cell, threads);
+                        Some((grid_cell, threads))
+                    })
+                    .collect();
+
+                Operation::Cells {
+                    cells,
+                    previous: raw_path.into_iter().zip(previous).collect(),
+                }
+            }
+        }
+    }
+
+    pub fn erase(
+        grid: &GridType,
+        raw_path: Vec<GridCell>,
+        thread_types: IArray<ThreadType>,
+    ) -> Self {
+        let previous: Vec<CellThreads> = raw_path
+            .iter()
+            .map(|grid_cell| grid.get(*grid_cell).unwrap_or_default())
+            .collect();
+
+        match History::from_raw_path(raw_path.clone(), ActionType::Erase) {
+            Ok(history) if history.to_raw_path() == raw_path => Operation::Erase {
+                history,
+                thread_types,
+                previous: IArray::from(previous),
+            },
+            _ => Operation::Cells {
+                cells: raw_path
+                    .iter()
+                    .zip(previous.iter())
+                    .map(|(&grid_cell, threads)| (grid_cell, threads.without(&thread_types)))
+                    .collect(),
+                previous: raw_path.into_iter().zip(previous).collect(),
+            },
+        }
+    }
+
+    pub fn fill(
+        grid: &GridType,
+        seed: GridCell,
+        options: FillOptions,
+        threads: CellThreads,
+    ) -> Self {
+        let previous = fill_region(grid, seed, options)
+            .into_iter()
+            .filter_map(|grid_cell| Some((grid_cell, grid.get(grid_cell)?)))
+            .filter(|(_, previous)| *previous != threads)
+            .collect();
+
+        Operation::Fill {
+            seed,
+            options,
+            threads,
+            previous,
+        }
+    }
+
+    /// Sets the threads of several cells at once. Cells outside of the grid are skipped.
+    pub fn cell_edits(grid: &GridType, cells: Vec<(GridCell, CellThreads)>) -> Self {
+        let cells: Vec<_> = cells
+            .into_iter()
+            .filter_map(|(grid_cell, threads)| {
+                grid.get(grid_cell)
+                    .map(|previous| (grid_cell, threads, previous))
+            })
+            .collect();
+
+        match cells.as_slice() {
+            &[(grid_cell, threads, previous)] => Operation::CellEdit {
+                grid_cell,
+                threads,
+                previous,
+            },
+            _ => Operation::Cells {
+                cells: cells
+                    .iter()
+                    .map(|&(grid_cell, threads, _)| (grid_cell, threads))
+                    .collect(),
+                previous: cells
+                    .iter()
+                    .map(|&(grid_cell, _, previous)| (grid_cell, previous))
+                    .collect(),
+            },
+        }
+    }
+
+    pub fn marker(overlay: &Overlay, position: MarkerPosition, marker: Option<Marker>) -> Self {
+        Operation::Marker {
+            position,
+            marker,
+            previous: overlay.marker(position),
+        }
+    }
+
+    /// Inserts a backstitch line at `index`, or removes the one there when `backstitch` is
+    /// `None`.
+    pub fn backstitch(overlay: &Overlay, index: usize, backstitch: Option<Backstitch>) -> Self {
+        Operation::Backstitch {
+            index,
+            previous: match backstitch {
+                Some(_) => None,
+                None => overlay.backstitch(index),
+            },
+            backstitch,
+        }
+    }
+
+    pub fn replace(
+        (grid, overlay): (&GridType, &Overlay),
+        next_grid: GridType,
+        next_overlay: Overlay,
+    ) -> Self {
+        Operation::Replace {
+            grid: next_grid,
+            overlay: next_overlay,
+            previous_grid: grid.clone(),
+            previous_overlay: overlay.clone(),
+        }
+    }
+
+    pub fn apply(&self, grid: &mut GridType, overlay: &mut Overlay) {
+        match self {
+            Operation::Stroke {
+                history, threads, ..
+            } => {
+                for grid_cell in history.to_raw_path() {
+                    if let Some(previous) = grid.get(grid_cell) {
+                        grid.set(grid_cell, previous.merged(*threads));
+                    }
+                }
+            }
+            Operation::Erase {
+                history,
+                thread_types,
+                ..
+            } => {
+                for grid_cell in history.to_raw_path() {
+                    if let Some(threads) = grid.get(grid_cell) {
+                        grid.set(grid_cell, threads.without(thread_types));
+                    }
+                }
+            }
+            Operation::Fill {
+                threads, previous, ..
+            } => {
+                for (grid_cell, _) in previous.iter() {
+                    grid.set(grid_cell, *threads);
+                }
+            }
+            Operation::CellEdit {
+                grid_cell, threads, ..
+            } => {
+                grid.set(*grid_cell, *threads);
+            }
+            Operation::Cells { cells, .. } => {
+                for (grid_cell, threads) in cells.iter() {
+                    grid.set(grid_cell, threads);
+                }
+            }
+            Operation::Marker {
+                position, marker, ..
+            } => {
+                overlay.set_marker(*position, *marker);
+            }
+            Operation::Backstitch {
+                index,
+                backstitch,
+                previous,
+            } => {
+                if previous.is_some() {
+                    overlay.remove_backstitch(*index);
+                }
+                if let Some(backstitch) = backstitch {
+                    overlay.insert_backstitch(*index, backstitch.clone());
+                }
+            }
+            Operation::Replace {
+                grid: next_grid,
+                overlay: next_overlay,
+                ..
+            } => {
+                *grid = next_grid.clone();
+                *overlay = next_overlay.clone();
+            }
+        }
+    }
+
+    /// The operation that undoes this one.
+    pub fn invert(&self) -> Self {
+        match self {
+            Operation::Stroke {
+                history,
+                threads,
+                previous,
+            } => {
+                let raw_path = history.to_raw_path();
+
+                Operation::Cells {
+                    cells: raw_path.iter().copied().zip(previous.iter()).collect(),
+                    previous: raw_path
+                        .into_iter()
+                        .zip(previous.iter())
+                        .map(|(grid_cell, previous)| (grid_cell, previous.merged(*threads)))
+                        .collect(),
+                }
+            }
+            Operation::Erase {
+                history,
+                thread_types,
+                previous,
+            } => {
+                let raw_path = history.to_raw_path();
+
+                Operation::Cells {
+                    cells: raw_path.iter().copied().zip(previous.iter()).collect(),
+                    previous: raw_path
+                        .into_iter()
+                        .zip(previous.iter())
+                        .map(|(grid_cell, threads)| (grid_cell, threads.without(thread_types)))
+                        .collect(),
+                }
+            }
+            Operation::Fill {
+                threads, previous, ..
+            } => Operation::Cells {
+                cells: previous.clone(),
+                previous: previous
+                    .iter()
+                    .map(|(grid_cell, _)| (grid_cell, *threads))
+                    .collect(),
+            },
+            Operation::CellEdit {
+                grid_cell,
+                threads,
+                previous,
+            } => Operation::CellEdit {
+                grid_cell: *grid_cell,
+                threads: *previous,
+                previous: *threads,
+            },
+            Operation::Cells { cells, previous } => Operation::Cells {
+                cells: previous.clone(),
+                previous: cells.clone(),
+            },
+            Operation::Marker {
+                position,
+                marker,
+                previous,
+            } => Operation::Marker {
+                position: *position,
+                marker: *previous,
+                previous: *marker,
+            },
+            Operation::Backstitch {
+                index,
+                backstitch,
+                previous,
+            } => Operation::Backstitch {
+                index: *index,
+                backstitch: previous.clone(),
+                previous: backstitch.clone(),
+            },
+            Operation::Replace {
+                grid,
+                overlay,
+                previous_grid,
+                previous_overlay,
+            } => Operation::Replace {
+                grid: previous_grid.clone(),
+                overlay: previous_overlay.clone(),
+                previous_grid: grid.clone(),
+                previous_overlay: overlay.clone(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::pattern::line::line_cells;
+    use crate::components::pattern::overlay::MarkerKind;
+    use crate::components::pattern::test_fixtures::{grid_with, BLUE, RED};
+    use crate::components::pattern::{GridIntersection, GridSize};
+
+    const SIZE: GridSize = GridSize(6, 6);
+
+    // A few crosses along the top row and one in the middle, with a marker and two lines over them
+    fn pattern() -> (GridType, Overlay) {
+        let red = CellThreads::full_cross(RED);
+        let grid = grid_with(
+            SIZE,
+            &[
+                (GridCell(0, 0), red),
+                (GridCell(1, 0), red),
+                (GridCell(2, 0), red),
+                (GridCell(2, 2), red),
+            ],
+        );
+        let mut overlay: Overlay = [(
+            MarkerPosition::Centre(GridCell(2, 2)),
+            Marker {
+                kind: MarkerKind::FrenchKnot,
+                color: BLUE,
+            },
+        )]
+        .into_iter()
+        .collect();
+
+        overlay.insert_backstitch(0, line(GridIntersection(0, 0), GridIntersection(3, 3)));
+        overlay.insert_backstitch(1, line(GridIntersection(6, 0), GridIntersection(0, 6)));
+
+        (grid, overlay)
+    }
+
+    fn line(start: GridIntersection, end: GridIntersection) -> Backstitch {
+        Backstitch {
+            points: IArray::from(vec![start, end]),
+            color: BLUE,
+        }
+    }
+
+    // Applies the operation and then its inverse, which has to leave the pattern as it was
+    fn assert_round_trip(operation: Operation) {
+        let (grid, overlay) = pattern();
+        let (mut edited_grid, mut edited_overlay) = (grid.clone(), overlay.clone());
+
+        operation.apply(&mut edited_grid, &mut edited_overlay);
+
+        assert!(
+            edited_grid != grid || edited_overlay != overlay,
+            "{:?} changed nothing",
+            operation
+        );
+
+        operation
+            .invert()
+            .apply(&mut edited_grid, &mut edited_overlay);
+
+        assert_eq!(edited_grid, grid);
+        assert_eq!(edited_overlay, overlay);
+    }
+
+    #[test]
+    fn interpolated_diagonal_round_trip() {
+        let raw_path = line_cells(GridCell(0, 0), GridCell(4, 4));
+        let history = History::from_raw_path(raw_path.clone(), ActionType::Brush).unwrap();
+
+        assert_eq!(
+            history.path.as_slice(),
+            &[
+                Direction::Start(GridCell(0, 0)),
+                Direction::DownRight(GridCell(4, 4))
+            ]
+        );
+        assert_eq!(history.to_raw_path(), raw_path);
+    }
+
+    #[test]
+    fn interpolated_long_jump_is_recorded_as_a_stroke() {
+        let grid = GridType::new(GridSize(30, 30));
+        let threads = CellThreads::full_cross(BLUE);
+        let mut raw_path = line_cells(GridCell(2, 2), GridCell(25, 9));
+        raw_path.extend(line_cells(GridCell(25, 9), GridCell(3, 28)).split_off(1));
+
+        let path = raw_path
+            .iter()
+            .map(|&grid_cell| (grid_cell, threads))
+            .collect();
+        let operation = Operation::stroke(&grid, path, ActionType::Brush);
+        let Operation::Stroke { ref history, .. } = operation else {
+            panic!("expected a stroke, got {:?}", operation);
+        };
+
+        assert_eq!(history.to_raw_path(), raw_path);
+
+        let mut painted = grid.clone();
+        operation.apply(&mut painted, &mut Overlay::default());
+
+        assert!(raw_path
+            .iter()
+            .all(|&grid_cell| painted.get(grid_cell) == Some(threads)));
+
+        operation
+            .invert()
+            .apply(&mut painted, &mut Overlay::default());
+
+        assert_eq!(painted, grid);
+    }
+
+    #[test]
+    fn straight_strokes_with_gaps_leave_the_gaps_alone() {
+        let (grid, mut overlay) = pattern();
+        let mut painted = grid.clone();
+        let threads = CellThreads::full_cross(BLUE);
+        let path = vec![(GridCell(0, 4), threads), (GridCell(3, 4), threads)];
+        let operation = Operation::stroke(&grid, path, ActionType::Brush);
+
+        assert!(matches!(operation, Operation::Cells { .. }));
+
+        operation.apply(&mut painted, &mut overlay);
+
+        assert_eq!(painted.diff(&grid), vec![GridCell(0, 4), GridCell(3, 4)]);
+        assert_round_trip(operation);
+    }
+
+    #[test]
+    fn stroke_round_trip() {
+        let (grid, _) = pattern();
+        let threads = CellThreads::single(ThreadType::BorderTop, BLUE);
+        let path = (0..5).map(|col| (GridCell(col, 0), threads)).collect();
+        let operation = Operation::stroke(&grid, path, ActionType::Brush);
+
+        assert!(matches!(operation, Operation::Stroke { .. }));
+        assert_round_trip(operation);
+    }
+
+    #[test]
+    fn erase_round_trip() {
+        let (grid, _) = pattern();
+        let operation = Operation::erase(
+            &grid,
+            vec![
+                GridCell(0, 0),
+                GridCell(1, 0),
+                GridCell(2, 0),
+                GridCell(3, 0),
+            ],
+            IArray::from(vec![ThreadType::SlashForwards]),
+        );
+
+        assert!(matches!(operation, Operation::Erase { .. }));
+        assert_round_trip(operation);
+    }
+
+    #[test]
+    fn erase_takes_out_only_the_given_thread_types() {
+        let (grid, mut overlay) = pattern();
+        let mut erased = grid.clone();
+        let operation = Operation::erase(
+            &grid,
+            vec![GridCell(0, 0), GridCell(1, 0), GridCell(2, 0)],
+            IArray::from(vec![ThreadType::SlashForwards, ThreadType::BorderTop]),
+        );
+
+        operation.apply(&mut erased, &mut overlay);
+
+        let half = CellThreads::single(ThreadType::SlashBackwards, RED);
+
+        assert_eq!(
+            erased,
+            grid_with(
+                SIZE,
+                &[
+                    (GridCell(0, 0), half),
+                    (GridCell(1, 0), half),
+                    (GridCell(2, 0), half),
+                    (GridCell(2, 2), CellThreads::full_cross(RED)),
+                ],
+            )
+        );
+    }
+
+    #[test]
+    fn erasing_along_a_broken_path_is_recorded_cell_by_cell() {
+        let (grid, mut overlay) = pattern();
+        let mut erased = grid.clone();
+        let operation = Operation::erase(
+            &grid,
+            vec![GridCell(0, 0), GridCell(2, 0), GridCell(2, 2)],
+            IArray::from(ThreadType::ALL.to_vec()),
+        );
+
+        assert!(matches!(operation, Operation::Cells { .. }));
+
+        operation.apply(&mut erased, &mut overlay);
+
+        assert_eq!(
+            erased,
+            grid_with(SIZE, &[(GridCell(1, 0), CellThreads::full_cross(RED))])
+        );
+        assert_round_trip(operation);
+    }
+
+    #[test]
+    fn fill_round_trip() {
+        let (grid, _) = pattern();
+        let operation = Operation::fill(
+            &grid,
+            GridCell(5, 5),
+            FillOptions::default(),
+            CellThreads::full_cross(BLUE),
+        );
+
+        assert!(matches!(operation, Operation::Fill { .. }));
+        assert_round_trip(operation);
+    }
+
+    #[test]
+    fn cell_edit_round_trip() {
+        let (grid, _) = pattern();
+        let operation =
+            Operation::cell_edits(&grid, vec![(GridCell(2, 2), CellThreads::default())]);
+
+        assert!(matches!(operation, Operation::CellEdit { .. }));
+        assert_round_trip(operation);
+    }
+
+    #[test]
+    fn cells_round_trip() {
+        let (grid, _) = pattern();
+        let operation = Operation::cell_edits(
+            &grid,
+            vec![
+                (GridCell(1, 0), CellThreads::full_cross(BLUE)),
+                (
+                    GridCell(4, 4),
+                    CellThreads::single(ThreadType::QuarterTopLeft, RED),
+                ),
+            ],
+        );
+
+        assert!(matches!(operation, Operation::Cells { .. }));
+        assert_round_trip(operation);
+    }
+
+    #[test]
+    fn marker_round_trip() {
+        let (_, overlay) = pattern();
+        let bead = Marker {
+            kind: MarkerKind::Bead,
+            color: RED,
+        };
+        let knot_position = MarkerPosition::Centre(GridCell(2, 2));
+
+        // Placing a new marker, changing the existing one and removing it
+        for (position, marker) in [
+            (MarkerPosition::Corner(GridIntersection(4, 1)), Some(bead)),
+            (knot_position, Some(bead)),
+            (knot_position, None),
+        ] {
+            let operation = Operation::marker(&overlay, position, marker);
+
+            assert!(matches!(operation, Operation::Marker { .. }));
+            assert_round_trip(operation);
+        }
+    }
+
+    #[test]
+    fn backstitch_round_trip() {
+        let (_, overlay) = pattern();
+        let new_line = line(GridIntersection(1, 5), GridIntersection(5, 5));
+
+        // Inserting between the two lines, adding one on top and removing the bottom one
+        for (index, backstitch) in [(1, Some(new_line.clone())), (2, Some(new_line)), (0, None)] {
+            let operation = Operation::backstitch(&overlay, index, backstitch);
+
+            assert!(matches!(operation, Operation::Backstitch { .. }));
+            assert_round_trip(operation);
+        }
+    }
+
+    #[test]
+    fn replace_round_trip() {
+        let (grid, overlay) = pattern();
+        let operation = Operation::replace(
+            (&grid, &overlay),
+            GridType::new(GridSize(3, 3)),
+            Overlay::default(),
+        );
+
+        assert!(matches!(operation, Operation::Replace { .. }));
+        assert_round_trip(operation);
+    }
 }
@@ -1,20 +1,114 @@
-use html::ImplicitClone;
-use implicit_clone::sync::{IArray, IMap};
-use indexmap::IndexMap;
-use std::{collections::HashMap, hash::Hash, rc::Rc, sync::Arc};
+use implicit_clone::sync::IArray;
+use std::rc::Rc;
 use yew::prelude::*;
 
-use crate::components::pattern::{GridType, RawGridType};
+use super::history::{ActionType, Operation};
+use crate::components::pattern::fill::FillOptions;
+use crate::components::pattern::overlay::{Backstitch, Marker, MarkerPosition, Overlay};
+use crate::components::pattern::palette::PaletteEntryId;
+use crate::components::pattern::{CellThreads, GridCell, GridType, Thread, ThreadType};
+
+/// Number of edits that can be undone unless another limit is given.
+pub const DEFAULT_HISTORY_LIMIT: usize = 100;
 
 pub enum PatternEditorAction {
-    AddHistory(RawGridType),
-    RemoveHistory,
+    // The cells a brush stroke passed through, in order, with the threads stitched over each
+    Stroke(Vec<(GridCell, CellThreads)>),
+    // The cells an eraser stroke passed through and the thread types it removes
+    Erase(Vec<GridCell>, IArray<ThreadType>),
+    Fill(GridCell, FillOptions, CellThreads),
+    // Sets the threads of each cell, e.g. when cells are pasted or moved
+    EditCells(Vec<(GridCell, CellThreads)>),
+    // Stitches one thread type in each of the cells with a floss from the palette
+    SetThread(IArray<GridCell>, ThreadType, PaletteEntryId),
+    // Places a marker, or removes it when there's none
+    SetMarker(MarkerPosition, Option<Marker>),
+    AddBackstitch(Backstitch),
+    RemoveBackstitch(usize),
+    Replace(GridType, Overlay),
+    Undo,
+    Redo,
 }
 
-#[derive(Default, PartialEq)]
+#[derive(PartialEq)]
 pub struct PatternEditorState {
-    pub history: IArray<GridType>,
+    pub history: IArray<Operation>,
+    // Number of operations in `history` that have been applied to `grid` and `overlay`. Entries
+    // after it can be redone.
+    pub cursor: usize,
     pub grid: GridType,
+    pub overlay: Overlay,
+    pub history_limit: usize,
+}
+
+impl PatternEditorState {
+    pub fn new(history_limit: usize) -> Self {
+        PatternEditorState {
+            history: IArray::EMPTY,
+            cursor: 0,
+            grid: GridType::default(),
+            overlay: Overlay::default(),
+            history_limit,
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.cursor > 0
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.cursor < self.history.len()
+    }
+
+    fn applied(&self, operation: &Operation) -> (GridType, Overlay) {
+        let mut grid = self.grid.clone();
+        let mut overlay = self.overlay.clone();
+
+        operation.apply(&mut grid, &mut overlay);
+
+        (grid, overlay)
+    }
+
+    fn with_cursor(&self, cursor: usize, (grid, overlay): (GridType, Overlay)) -> Self {
+        PatternEditorState {
+            history: self.history.clone(),
+            cursor,
+            grid,
+            overlay,
+            history_limit: self.history_limit,
+        }
+    }
+
+    fn push(&self, operation: Operation) -> Self {
+        let (grid, overlay) = self.applied(&operation);
+
+        // Nothing changed, so there's nothing worth undoing
+        if grid == self.grid && overlay == self.overlay {
+            return self.with_cursor(self.cursor, (grid, overlay));
+        }
+
+        // A new edit replaces anything that could have been redone
+        let mut history = self.history.as_slice()[..self.cursor].to_vec();
+
+        history.push(operation);
+
+        let overflow = history.len().saturating_sub(self.history_limit);
+        history.drain(..overflow);
+
+        PatternEditorState {
+            cursor: history.len(),
+            history: IArray::from(history),
+            grid,
+            overlay,
+            history_limit: self.history_limit,
+        }
+    }
+}
+
+impl Default for PatternEditorState {
+    fn default() -> Self {
+        PatternEditorState::new(DEFAULT_HISTORY_LIMIT)
+    }
 }
 
 impl Reducible for PatternEditorState {
@@ -22,24 +116,71 @@ impl Reducible for PatternEditorState {
 
     fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
         let next_state: Self = match action {
-            PatternEditorAction::AddHistory(grid) => {
-                let mut history = self.history.to_vec();
-                let grid = hashmap_to_imap(grid);
+            PatternEditorAction::Stroke(path) => {
+                self.push(Operation::stroke(&self.grid, path, ActionType::Brush))
+            }
+            PatternEditorAction::Erase(raw_path, thread_types) => {
+                self.push(Operation::erase(&self.grid, raw_path, thread_types))
+            }
+            PatternEditorAction::Fill(seed, options, threads) => {
+                self.push(Operation::fill(&self.grid, seed, options, threads))
+            }
+            PatternEditorAction::EditCells(cells) => {
+                self.push(Operation::cell_edits(&self.grid, cells))
+            }
+            PatternEditorAction::SetThread(grid_cells, thread_type, entry) => {
+                let thread = Thread {
+                    color: entry.color(),
+                    thread_type,
+                    entry: Some(entry),
+                };
+                let cells = grid_cells
+                    .iter()
+                    .filter_map(|grid_cell| {
+                        let mut threads = self.grid.get(grid_cell)?;
 
-                history.push(grid.clone());
+                        threads.set_thread(thread);
+                        Some((grid_cell, threads))
+                    })
+                    .collect();
 
-                Self {
-                    history: IArray::from(history),
-                    grid,
-                }
+                self.push(Operation::cell_edits(&self.grid, cells))
+            }
+            PatternEditorAction::SetMarker(position, marker) => {
+                self.push(Operation::marker(&self.overlay, position, marker))
             }
-            PatternEditorAction::RemoveHistory => {
-                let (without_last, _) = self.history.as_slice().split_at(self.history.len() - 1);
+            PatternEditorAction::AddBackstitch(backstitch) => {
+                let index = self.overlay.backstitches().count();
 
-                Self {
-                    history: IArray::from(without_last.to_vec()),
-                    grid: self.grid.clone(),
+                self.push(Operation::backstitch(
+                    &self.overlay,
+                    index,
+                    Some(backstitch),
+                ))
+            }
+            PatternEditorAction::RemoveBackstitch(index) => {
+                self.push(Operation::backstitch(&self.overlay, index, None))
+            }
+            PatternEditorAction::Replace(grid, overlay) => self.push(Operation::replace(
+                (&self.grid, &self.overlay),
+                grid,
+                overlay,
+            )),
+            PatternEditorAction::Undo => {
+                if !self.can_undo() {
+                    return self;
                 }
+
+                let operation = self.history[self.cursor - 1].invert();
+
+                self.with_cursor(self.cursor - 1, self.applied(&operation))
+            }
+            PatternEditorAction::Redo => {
+                if !self.can_redo() {
+                    return self;
+                }
+
+                self.with_cursor(self.cursor + 1, self.applied(&self.history[self.cursor]))
             }
         };
 
@@ -47,11 +188,166 @@ impl Reducible for PatternEditorState {
     }
 }
 
-fn hashmap_to_imap<K, V>(hash_map: HashMap<K, V>) -> IMap<K, V>
-where
-    K: Eq + Hash + ImplicitClone + 'static,
-    V: PartialEq + ImplicitClone + 'static,
-{
-    let index_map: IndexMap<K, V> = hash_map.into_iter().collect();
-    IMap::Rc(Arc::new(index_map))
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::pattern::chart::ChartThread;
+    use crate::components::pattern::palette::Palette;
+    use crate::components::pattern::test_fixtures::{BLUE, RED};
+    use crate::components::pattern::{GridSize, Rgb};
+
+    // A blank 3x3 pattern with nothing to undo
+    fn blank_state(history_limit: usize) -> Rc<PatternEditorState> {
+        Rc::new(PatternEditorState {
+            grid: GridType::new(GridSize(3, 3)),
+            ..PatternEditorState::new(history_limit)
+        })
+    }
+
+    fn dispatch(
+        state: Rc<PatternEditorState>,
+        actions: impl IntoIterator<Item = PatternEditorAction>,
+    ) -> Rc<PatternEditorState> {
+        actions
+            .into_iter()
+            .fold(state, |state, action| state.reduce(action))
+    }
+
+    fn stroke(col: usize, color: Rgb) -> PatternEditorAction {
+        PatternEditorAction::Stroke(vec![(GridCell(col, 0), CellThreads::full_cross(color))])
+    }
+
+    #[test]
+    fn redo_restores_what_undo_took_back() {
+        let state = dispatch(blank_state(10), [stroke(0, RED), stroke(1, BLUE)]);
+        let undone = dispatch(state.clone(), [PatternEditorAction::Undo]);
+
+        assert_eq!(undone.cursor, 1);
+        assert_eq!(
+            undone.grid.get(GridCell(1, 0)),
+            Some(CellThreads::default())
+        );
+        assert!(undone.can_redo());
+
+        let redone = dispatch(undone, [PatternEditorAction::Redo]);
+
+        assert_eq!(redone.grid, state.grid);
+        assert_eq!(redone.cursor, 2);
+        assert!(!redone.can_redo());
+    }
+
+    #[test]
+    fn a_new_edit_after_undo_clears_redo() {
+        let state = dispatch(
+            blank_state(10),
+            [
+                stroke(0, RED),
+                stroke(1, RED),
+                PatternEditorAction::Undo,
+                stroke(2, BLUE),
+            ],
+        );
+
+        assert_eq!(state.history.len(), 2);
+        assert_eq!(state.cursor, 2);
+        assert!(!state.can_redo());
+        assert_eq!(state.grid.get(GridCell(1, 0)), Some(CellThreads::default()));
+        assert_eq!(
+            state.grid.get(GridCell(2, 0)),
+            Some(CellThreads::full_cross(BLUE))
+        );
+    }
+
+    #[test]
+    fn the_oldest_edits_are_dropped_past_the_limit() {
+        let state = dispatch(
+            blank_state(2),
+            [stroke(0, RED), stroke(1, RED), stroke(2, RED)],
+        );
+
+        assert_eq!(state.history.len(), 2);
+        assert_eq!(state.cursor, 2);
+
+        let undone = dispatch(
+            state,
+            [PatternEditorAction::Undo, PatternEditorAction::Undo],
+        );
+
+        // The first stroke can no longer be undone
+        assert!(!undone.can_undo());
+        assert_eq!(
+            undone.grid.get(GridCell(0, 0)),
+            Some(CellThreads::full_cross(RED))
+        );
+        assert_eq!(
+            undone.grid.get(GridCell(1, 0)),
+            Some(CellThreads::default())
+        );
+    }
+
+    #[test]
+    fn edits_that_change_nothing_are_not_recorded() {
+        let state = dispatch(blank_state(10), [stroke(0, RED), stroke(0, RED)]);
+
+        assert_eq!(state.history.len(), 1);
+        assert_eq!(state.cursor, 1);
+    }
+
+    #[test]
+    fn undo_and_redo_at_the_ends_of_history_do_nothing() {
+        let state = blank_state(10);
+        let undone = dispatch(state.clone(), [PatternEditorAction::Undo]);
+
+        assert!(Rc::ptr_eq(&state, &undone));
+
+        let edited = dispatch(state, [stroke(0, RED)]);
+        let redone = dispatch(edited.clone(), [PatternEditorAction::Redo]);
+
+        assert!(Rc::ptr_eq(&edited, &redone));
+
+        let undone = dispatch(
+            edited,
+            [PatternEditorAction::Undo, PatternEditorAction::Undo],
+        );
+
+        assert_eq!(undone.cursor, 0);
+        assert_eq!(undone.grid, GridType::new(GridSize(3, 3)));
+    }
+
+    #[test]
+    fn cell_edits_keep_the_floss_they_were_picked_from() {
+        let black = Palette::Dmc.find("310").unwrap();
+        let cells: IArray<GridCell> = [GridCell(0, 0), GridCell(2, 1)].into_iter().collect();
+        let state = dispatch(
+            blank_state(10),
+            [
+                stroke(0, RED),
+                PatternEditorAction::SetThread(cells.clone(), ThreadType::SlashForwards, black),
+            ],
+        );
+
+        for grid_cell in cells.iter() {
+            let thread = state
+                .grid
+                .get(grid_cell)
+                .unwrap()
+                .iter()
+                .find(|thread| thread.thread_type == ThreadType::SlashForwards)
+                .unwrap();
+
+            assert_eq!(thread.entry, Some(black));
+            assert_eq!(thread.color, black.color());
+            assert_eq!(ChartThread::from(thread).label(), black.to_string());
+        }
+
+        // The other half of the cross keeps its own colour
+        assert_eq!(
+            state
+                .grid
+                .get(GridCell(0, 0))
+                .unwrap()
+                .get(ThreadType::SlashBackwards),
+            Some(RED)
+        );
+    }
 }
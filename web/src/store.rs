@@ -0,0 +1,224 @@
+use gloo::storage::errors::StorageError;
+use gloo::storage::{LocalStorage, Storage};
+use log::error;
+use serde::{Deserialize, Serialize};
+
+use crate::components::pattern::document::{
+    PatternDocument, PatternDocumentError, PatternDocumentVersion, PatternMetadata,
+    PATTERN_DOCUMENT_VERSION,
+};
+use crate::components::pattern::overlay::Overlay;
+use crate::components::pattern::palette::PaletteEntryId;
+use crate::components::pattern::{GridType, Rgb};
+use crate::pages::user_pattern_editor::PatternEditorFeature;
+
+const DRAFT_KEY: &str = "paint-project.pattern-editor.draft";
+// Where a draft that couldn't be read is kept, so it isn't lost to the next autosave
+const UNREADABLE_DRAFT_KEY: &str = "paint-project.pattern-editor.unreadable-draft";
+
+/// How long the editor waits after the last change before writing a draft.
+pub const DRAFT_SAVE_DELAY_MS: u32 = 1_000;
+
+/// The pattern editor state that's kept in LocalStorage so work survives the tab closing.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Draft {
+    pub pattern: PatternDocument,
+    pub thread_color: Rgb,
+    // Missing from drafts saved before threads were picked from a palette
+    #[serde(default)]
+    pub thread_entry: Option<PaletteEntryId>,
+    pub active_feature: PatternEditorFeature,
+}
+
+impl Draft {
+    pub fn new(
+        grid: &GridType,
+        overlay: &Overlay,
+        metadata: PatternMetadata,
+        thread_entry: PaletteEntryId,
+        active_feature: PatternEditorFeature,
+    ) -> Self {
+        Draft {
+            pattern: PatternDocument::new(grid, overlay, metadata),
+            thread_color: thread_entry.color(),
+            thread_entry: Some(thread_entry),
+            active_feature,
+        }
+    }
+
+    /// Reads a saved draft, refusing ones written by a newer version like pattern files are.
+    pub fn from_json(json: &str) -> Result<Self, PatternDocumentError> {
+        let DraftVersion {
+            pattern: PatternDocumentVersion { version },
+        } = serde_json::from_str(json)?;
+
+        if version > PATTERN_DOCUMENT_VERSION {
+            return Err(PatternDocumentError::UnsupportedVersion(version));
+        }
+
+        Ok(serde_json::from_str(json)?)
+    }
+}
+
+// Read on its own first, as for pattern files, so a newer draft fails with a version error even
+// when the rest of it has changed shape
+#[derive(Deserialize)]
+struct DraftVersion {
+    pattern: PatternDocumentVersion,
+}
+
+pub fn save_draft(draft: &Draft) -> Result<(), StorageError> {
+    LocalStorage::set(DRAFT_KEY, draft)
+}
+
+/// Drafts that can't be read, e.g. ones written by a newer version, are moved aside to
+/// `UNREADABLE_DRAFT_KEY` so the next autosave doesn't overwrite them.
+pub fn load_draft() -> Option<Draft> {
+    let storage = LocalStorage::raw();
+    let json = storage.get_item(DRAFT_KEY).ok()??;
+
+    match Draft::from_json(&json) {
+        Ok(draft) => Some(draft),
+        Err(err) => {
+            error!("Unable to read the saved draft: {}", err);
+
+            if storage.set_item(UNREADABLE_DRAFT_KEY, &json).is_ok() {
+                let _ = storage.remove_item(DRAFT_KEY);
+            }
+            None
+        }
+    }
+}
+
+pub fn clear_draft() {
+    LocalStorage::delete(DRAFT_KEY);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::pattern::overlay::{Marker, MarkerKind, MarkerPosition};
+    use crate::components::pattern::palette::Palette;
+    use crate::components::pattern::test_fixtures::{BLUE, RED};
+    use crate::components::pattern::{
+        CellThreads, GridCell, GridIntersection, GridSize, Thread, ThreadType,
+    };
+
+    // LocalStorage stores values as JSON, so this is the same trip a draft makes
+    fn round_trip(draft: &Draft) -> Draft {
+        Draft::from_json(&serde_json::to_string(draft).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn draft_round_trip() {
+        let black = Palette::Dmc.find("310").unwrap();
+        let mut threads = CellThreads::full_cross(RED);
+        threads.set(ThreadType::BorderLeft, BLUE);
+        threads.set_thread(Thread {
+            color: black.color(),
+            thread_type: ThreadType::BorderTop,
+            entry: Some(black),
+        });
+
+        let mut grid = GridType::new(GridSize(4, 3));
+        grid.set(GridCell(0, 0), threads);
+        grid.set(GridCell(3, 2), CellThreads::full_cross(BLUE));
+
+        let overlay: Overlay = [
+            (
+                MarkerPosition::Centre(GridCell(1, 1)),
+                Marker {
+                    kind: MarkerKind::FrenchKnot,
+                    color: BLUE,
+                },
+            ),
+            (
+                MarkerPosition::Corner(GridIntersection(4, 3)),
+                Marker {
+                    kind: MarkerKind::Bead,
+                    color: Rgb { r: 0, g: 255, b: 0 },
+                },
+            ),
+        ]
+        .into_iter()
+        .collect();
+
+        let metadata = PatternMetadata {
+            name: "Sampler".to_string(),
+            ..PatternMetadata::default()
+        };
+        let draft = Draft::new(
+            &grid,
+            &overlay,
+            metadata,
+            Palette::Dmc.nearest(BLUE),
+            PatternEditorFeature::Fill,
+        );
+        let restored = round_trip(&draft);
+
+        assert_eq!(restored, draft);
+        assert_eq!(restored.pattern.to_grid().unwrap(), grid);
+        assert_eq!(restored.pattern.to_overlay().unwrap(), overlay);
+    }
+
+    #[test]
+    fn empty_draft_round_trip() {
+        let grid = GridType::new(GridSize(2, 2));
+        let draft = Draft::new(
+            &grid,
+            &Overlay::default(),
+            PatternMetadata::default(),
+            Palette::Dmc.find("310").unwrap(),
+            PatternEditorFeature::Brush,
+        );
+        let restored = round_trip(&draft);
+
+        assert_eq!(restored, draft);
+        assert_eq!(restored.pattern.to_grid().unwrap(), grid);
+    }
+
+    #[test]
+    fn drafts_without_a_palette_entry_still_load() {
+        let grid = GridType::new(GridSize(2, 2));
+        let draft = Draft::new(
+            &grid,
+            &Overlay::default(),
+            PatternMetadata::default(),
+            Palette::Dmc.find("310").unwrap(),
+            PatternEditorFeature::Brush,
+        );
+        let mut json: serde_json::Value = serde_json::to_value(&draft).unwrap();
+
+        json.as_object_mut().unwrap().remove("thread_entry");
+
+        let restored = Draft::from_json(&json.to_string()).unwrap();
+
+        assert_eq!(restored.thread_entry, None);
+        assert_eq!(restored.thread_color, Rgb { r: 0, g: 0, b: 0 });
+    }
+
+    #[test]
+    fn drafts_from_a_newer_version_are_ignored() {
+        let mut draft = Draft::new(
+            &GridType::new(GridSize(2, 2)),
+            &Overlay::default(),
+            PatternMetadata::default(),
+            Palette::Dmc.find("310").unwrap(),
+            PatternEditorFeature::Brush,
+        );
+        draft.pattern.version = PATTERN_DOCUMENT_VERSION + 1;
+
+        let mut json = serde_json::to_value(&draft).unwrap();
+
+        // Newer drafts may also have changed shape, which mustn't hide the version
+        json.as_object_mut().unwrap().remove("active_feature");
+
+        let json = json.to_string();
+
+        assert!(matches!(
+            Draft::from_json(&json),
+            Err(PatternDocumentError::UnsupportedVersion(version))
+                if version == PATTERN_DOCUMENT_VERSION + 1
+        ));
+    }
+}